@@ -3,7 +3,10 @@
 //! Loads sound and music assets from ROM during init and provides
 //! convenience functions for playing game sounds and stage music.
 
-use crate::ffi::*;
+use crate::platform::{
+    load_music, load_sound, music_play, music_set_volume, music_stop, play_sound,
+};
+use crate::stage::MusicTrack;
 
 // =============================================================================
 // SOUND HANDLES
@@ -22,6 +25,11 @@ pub static mut SND_COUNTDOWN: u32 = 0;
 pub static mut SND_GO: u32 = 0;
 pub static mut SND_SPAWN: u32 = 0;
 pub static mut SND_VICTORY: u32 = 0;
+pub static mut SND_PICKUP: u32 = 0;
+pub static mut SND_EXPLOSION: u32 = 0;
+pub static mut SND_CLASH: u32 = 0;
+pub static mut SND_CLANK: u32 = 0;
+pub static mut SND_STOMP: u32 = 0;
 
 // =============================================================================
 // MUSIC HANDLES
@@ -43,21 +51,26 @@ pub static mut MUSIC_RING: u32 = 0;
 pub fn init_audio() {
     unsafe {
         // Load sound effects
-        SND_SHOOT = rom_sound_str("shoot");
-        SND_HIT = rom_sound_str("hit");
-        SND_DEATH = rom_sound_str("death");
-        SND_DEFLECT = rom_sound_str("deflect");
-        SND_JUMP = rom_sound_str("jump");
-        SND_COUNTDOWN = rom_sound_str("countdown");
-        SND_GO = rom_sound_str("go");
-        SND_SPAWN = rom_sound_str("spawn");
-        SND_VICTORY = rom_sound_str("victory");
+        SND_SHOOT = load_sound("shoot");
+        SND_HIT = load_sound("hit");
+        SND_DEATH = load_sound("death");
+        SND_DEFLECT = load_sound("deflect");
+        SND_JUMP = load_sound("jump");
+        SND_COUNTDOWN = load_sound("countdown");
+        SND_GO = load_sound("go");
+        SND_SPAWN = load_sound("spawn");
+        SND_VICTORY = load_sound("victory");
+        SND_PICKUP = load_sound("pickup");
+        SND_EXPLOSION = load_sound("explosion");
+        SND_CLASH = load_sound("clash");
+        SND_CLANK = load_sound("clank");
+        SND_STOMP = load_sound("stomp");
 
         // Load music tracks
-        MUSIC_MENU = rom_tracker_str("music_menu");
-        MUSIC_GRID = rom_tracker_str("music_grid");
-        MUSIC_SCATTER = rom_tracker_str("music_scatter");
-        MUSIC_RING = rom_tracker_str("music_ring");
+        MUSIC_MENU = load_music("music_menu");
+        MUSIC_GRID = load_music("music_grid");
+        MUSIC_SCATTER = load_music("music_scatter");
+        MUSIC_RING = load_music("music_ring");
     }
 }
 
@@ -147,6 +160,56 @@ pub fn play_spawn(pan: f32) {
     }
 }
 
+/// Play ammo pickup clink with spatial panning
+///
+/// # Arguments
+/// * `pan` - -1.0 (left) to 1.0 (right), based on pickup x position
+pub fn play_pickup(pan: f32) {
+    unsafe {
+        play_sound(SND_PICKUP, (0.7 * SFX_VOL).min(1.0), pan);
+    }
+}
+
+/// Play grenade explosion with spatial panning
+///
+/// # Arguments
+/// * `pan` - -1.0 (left) to 1.0 (right), based on blast x position
+pub fn play_explosion(pan: f32) {
+    unsafe {
+        play_sound(SND_EXPLOSION, SFX_VOL.min(1.0), pan);
+    }
+}
+
+/// Play bullet-vs-bullet clash with spatial panning
+///
+/// # Arguments
+/// * `pan` - -1.0 (left) to 1.0 (right), based on clash x position
+pub fn play_clash(pan: f32) {
+    unsafe {
+        play_sound(SND_CLASH, (0.85 * SFX_VOL).min(1.0), pan);
+    }
+}
+
+/// Play blade-on-blade melee clank with spatial panning
+///
+/// # Arguments
+/// * `pan` - -1.0 (left) to 1.0 (right), based on clash x position
+pub fn play_clank(pan: f32) {
+    unsafe {
+        play_sound(SND_CLANK, SFX_VOL.min(1.0), pan);
+    }
+}
+
+/// Play stomp kill thud with spatial panning
+///
+/// # Arguments
+/// * `pan` - -1.0 (left) to 1.0 (right), based on stomp x position
+pub fn play_stomp(pan: f32) {
+    unsafe {
+        play_sound(SND_STOMP, SFX_VOL.min(1.0), pan);
+    }
+}
+
 /// Play victory fanfare (match end celebration)
 pub fn play_victory() {
     unsafe {
@@ -161,31 +224,28 @@ pub fn play_victory() {
 /// Play menu/title screen music
 pub fn play_menu_music() {
     unsafe {
-        music_play(MUSIC_MENU, MUSIC_VOL, 1);
+        music_play(MUSIC_MENU, MUSIC_VOL, true);
     }
 }
 
 /// Play the appropriate music track for a stage
 ///
 /// # Arguments
-/// * `stage` - Stage index (0=Grid Arena, 1=Scatter Field, 2=Ring Void)
-pub fn play_music_for_stage(stage: u32) {
-    unsafe {
-        let handle = match stage {
-            0 => MUSIC_GRID,
-            1 => MUSIC_SCATTER,
-            2 => MUSIC_RING,
-            _ => MUSIC_GRID, // Default to Grid Arena music
+/// * `track` - Track named in the stage file
+pub fn play_music_for_stage(track: MusicTrack) {
+    unsafe {
+        let handle = match track {
+            MusicTrack::Grid => MUSIC_GRID,
+            MusicTrack::Scatter => MUSIC_SCATTER,
+            MusicTrack::Ring => MUSIC_RING,
         };
-        music_play(handle, MUSIC_VOL, 1);
+        music_play(handle, MUSIC_VOL, true);
     }
 }
 
 /// Stop the currently playing music
 pub fn stop_music() {
-    unsafe {
-        music_stop();
-    }
+    music_stop();
 }
 
 /// Set the music volume
@@ -0,0 +1,296 @@
+//! Bot profiles
+//!
+//! Play styles live in `assets/bots/*.bot` and are parsed by a `const fn` at
+//! compile time, like stages. Difficulty still sets how well a bot plays
+//! (perception, reactions, cooldowns); the profile sets how it plays. The
+//! lobby picks a profile per CPU slot (`GameConfig::bot_profiles`).
+//! Adding a profile means dropping in a file and listing it in
+//! `PROFILE_SOURCES`.
+//!
+//! Format: one directive per line, `#` starts a comment. Every directive but
+//! `name` is optional and defaults to the BALANCED style.
+//!
+//! ```text
+//! name        BRAWLER    # display name (rest of line)
+//! aggression  0.9        # 0-1: fights sooner, attacks more often
+//! parry       0.6        # 0-1: chance to swing at a noticed bullet
+//! ammo        0.3        # 0-1: saves shots for closer targets and restocks early
+//! range       1.0        # distance it likes to keep from its target
+//! camping     0.0        # 0-1: share of the time it holds its platform
+//! jump        0.2        # 0-1: how often it hops while moving
+//! ```
+
+use crate::stage::Cursor;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Embedded profile files, in lobby order (the first is the default)
+const PROFILE_SOURCES: [&str; 5] = [
+    include_str!("../assets/bots/balanced.bot"),
+    include_str!("../assets/bots/brawler.bot"),
+    include_str!("../assets/bots/sniper.bot"),
+    include_str!("../assets/bots/turtle.bot"),
+    include_str!("../assets/bots/hopper.bot"),
+];
+
+/// Number of bot profiles in the game
+pub const NUM_BOT_PROFILES: u32 = PROFILE_SOURCES.len() as u32;
+
+/// Every built-in profile, parsed at compile time
+pub static BOT_PROFILES: [BotProfile; NUM_BOT_PROFILES as usize] =
+    parse_bot_profiles(&PROFILE_SOURCES);
+
+// =============================================================================
+// DATA STRUCTURES
+// =============================================================================
+
+/// A parsed bot profile
+#[derive(Clone, Copy)]
+pub struct BotProfile {
+    pub name: &'static str,
+    pub aggression: f32,
+    pub parry: f32,
+    pub ammo: f32, // Ammo conservation
+    pub range: f32,
+    pub camping: f32,
+    pub jump: f32,
+}
+
+impl BotProfile {
+    pub const fn new() -> Self {
+        Self {
+            name: "",
+            aggression: 0.5,
+            parry: 1.0,
+            ammo: 0.0,
+            range: 2.3,
+            camping: 0.0,
+            jump: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BotProfileErrorKind {
+    UnknownDirective,
+    BadNumber,
+    OutOfRange,
+    TrailingInput,
+    MissingName,
+}
+
+impl BotProfileErrorKind {
+    pub fn message(self) -> &'static str {
+        match self {
+            BotProfileErrorKind::UnknownDirective => "unknown directive",
+            BotProfileErrorKind::BadNumber => "expected a number",
+            BotProfileErrorKind::OutOfRange => "value out of range (0-1, range 0.1-20)",
+            BotProfileErrorKind::TrailingInput => "unexpected extra values",
+            BotProfileErrorKind::MissingName => "missing `name`",
+        }
+    }
+}
+
+/// Parse failure; `line` is 1-based, 0 means the file as a whole
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BotProfileError {
+    pub line: u32,
+    pub kind: BotProfileErrorKind,
+}
+
+// =============================================================================
+// PARSING
+// =============================================================================
+
+/// Early-return a `BotProfileError` (no `?` or closures in `const fn`)
+macro_rules! fail {
+    ($line:expr, $kind:expr) => {
+        return Err(BotProfileError {
+            line: $line,
+            kind: $kind,
+        })
+    };
+}
+
+/// Unwrap a number in `min..=max` or fail with the cursor's current line
+macro_rules! number {
+    ($cursor:expr, $min:expr, $max:expr) => {
+        match $cursor.number() {
+            Ok(v) if v >= $min && v <= $max => v,
+            Ok(_) => fail!($cursor.line, BotProfileErrorKind::OutOfRange),
+            Err(_) => fail!($cursor.line, BotProfileErrorKind::BadNumber),
+        }
+    };
+}
+
+/// Parse one profile file (usable at compile time and by host tools)
+pub const fn parse_bot_profile(src: &'static str) -> Result<BotProfile, BotProfileError> {
+    let mut c = Cursor::new(src);
+    let mut profile = BotProfile::new();
+
+    while !c.at_end() {
+        if c.at_line_end() {
+            c.next_line();
+            continue;
+        }
+
+        let line = c.line;
+        let directive = c.word();
+
+        if c.word_is(directive, b"name") {
+            profile.name = c.rest_of_line();
+        } else if c.word_is(directive, b"aggression") {
+            profile.aggression = number!(c, 0.0, 1.0);
+        } else if c.word_is(directive, b"parry") {
+            profile.parry = number!(c, 0.0, 1.0);
+        } else if c.word_is(directive, b"ammo") {
+            profile.ammo = number!(c, 0.0, 1.0);
+        } else if c.word_is(directive, b"range") {
+            profile.range = number!(c, 0.1, 20.0);
+        } else if c.word_is(directive, b"camping") {
+            profile.camping = number!(c, 0.0, 1.0);
+        } else if c.word_is(directive, b"jump") {
+            profile.jump = number!(c, 0.0, 1.0);
+        } else {
+            fail!(line, BotProfileErrorKind::UnknownDirective);
+        }
+
+        if !c.at_line_end() {
+            fail!(line, BotProfileErrorKind::TrailingInput);
+        }
+        c.next_line();
+    }
+
+    if profile.name.is_empty() {
+        fail!(0, BotProfileErrorKind::MissingName);
+    }
+    Ok(profile)
+}
+
+const fn parse_bot_profiles<const N: usize>(sources: &[&'static str; N]) -> [BotProfile; N] {
+    let mut profiles = [BotProfile::new(); N];
+    let mut i = 0;
+    while i < N {
+        profiles[i] = match parse_bot_profile(sources[i]) {
+            Ok(profile) => profile,
+            Err(_) => panic!("invalid bot profile in assets/bots"),
+        };
+        i += 1;
+    }
+    profiles
+}
+
+// =============================================================================
+// LOOKUP
+// =============================================================================
+
+/// Profile for an index (out of range falls back to the first profile)
+pub fn bot_profile(profile: u32) -> &'static BotProfile {
+    BOT_PROFILES
+        .get(profile as usize)
+        .unwrap_or(&BOT_PROFILES[0])
+}
+
+/// Display name (lobby and host tools)
+pub fn bot_profile_name(profile: u32) -> &'static str {
+    bot_profile(profile).name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_err(src: &'static str) -> (u32, BotProfileErrorKind) {
+        let e = parse_bot_profile(src).err().expect("should fail to parse");
+        (e.line, e.kind)
+    }
+
+    #[test]
+    fn parses_every_directive() {
+        let profile = parse_bot_profile(
+            "# Test profile\n\
+             name        TEST BOT   # comment\n\
+             aggression  0.9\n\
+             parry       0.25\n\
+             ammo        1\n\
+             range       12.5\n\
+             camping     0.0\n\
+             jump        0.75\n",
+        )
+        .unwrap();
+        assert_eq!(profile.name, "TEST BOT");
+        assert_eq!(
+            (
+                profile.aggression,
+                profile.parry,
+                profile.ammo,
+                profile.range,
+                profile.camping,
+                profile.jump
+            ),
+            (0.9, 0.25, 1.0, 12.5, 0.0, 0.75)
+        );
+    }
+
+    #[test]
+    fn missing_directives_play_balanced() {
+        let profile = parse_bot_profile("name MINIMAL").unwrap();
+        let balanced = bot_profile(0);
+        assert_eq!(balanced.name, "BALANCED");
+        assert_eq!(
+            (
+                profile.aggression,
+                profile.parry,
+                profile.ammo,
+                profile.range
+            ),
+            (
+                balanced.aggression,
+                balanced.parry,
+                balanced.ammo,
+                balanced.range
+            )
+        );
+        assert_eq!(
+            (profile.camping, profile.jump),
+            (balanced.camping, balanced.jump)
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        assert_eq!(
+            parse_err("name X\nstealth 0.5\n"),
+            (2, BotProfileErrorKind::UnknownDirective)
+        );
+        assert_eq!(
+            parse_err("name X\n\nparry often\n"),
+            (3, BotProfileErrorKind::BadNumber)
+        );
+        assert_eq!(
+            parse_err("name X\naggression 1.5\n"),
+            (2, BotProfileErrorKind::OutOfRange)
+        );
+        assert_eq!(
+            parse_err("name X\nrange 0.05\n"),
+            (2, BotProfileErrorKind::OutOfRange)
+        );
+        assert_eq!(
+            parse_err("name X\njump 0.5 0.5\n"),
+            (2, BotProfileErrorKind::TrailingInput)
+        );
+        assert_eq!(
+            parse_err("aggression 0.5\n"),
+            (0, BotProfileErrorKind::MissingName)
+        );
+    }
+
+    #[test]
+    fn lookup_falls_back_to_the_default() {
+        let names: Vec<&str> = (0..NUM_BOT_PROFILES).map(bot_profile_name).collect();
+        assert_eq!(names, ["BALANCED", "BRAWLER", "SNIPER", "TURTLE", "HOPPER"]);
+        assert_eq!(bot_profile_name(NUM_BOT_PROFILES), "BALANCED");
+    }
+}
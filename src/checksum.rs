@@ -0,0 +1,590 @@
+//! Deterministic state checksums
+//!
+//! Hashes the simulation state once per tick so peers (or a replay) can prove
+//! their worlds are bit-identical. Each subsystem gets its own hash so a
+//! mismatch can be narrowed down to players, bullets, pickups (shells and
+//! weapon crates), platforms, round state, scores or what bots perceive.
+//! Presentation-only fields (trails, flashes, squash) are skipped.
+
+use crate::game_state::GamePhase;
+use crate::perception::PERCEPTION_FRAMES;
+use crate::world::{SimState, WORLD};
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Ticks of local history kept for comparison (covers the rollback window)
+pub const CHECKSUM_HISTORY: usize = 128;
+
+pub const SUBSYSTEM_COUNT: usize = 7;
+
+const FNV_OFFSET: u32 = 0x811C_9DC5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+// =============================================================================
+// DATA STRUCTURES
+// =============================================================================
+
+/// Part of the simulation covered by a checksum
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Subsystem {
+    Players,
+    Bullets,
+    Pickups, // Ammo shells and weapon crates
+    Platforms,
+    Round, // Phase, timers, overtime, arena bounds
+    Kills,
+    Perception, // Bot perception buffer, difficulty and profiles
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; SUBSYSTEM_COUNT] = [
+        Subsystem::Players,
+        Subsystem::Bullets,
+        Subsystem::Pickups,
+        Subsystem::Platforms,
+        Subsystem::Round,
+        Subsystem::Kills,
+        Subsystem::Perception,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Subsystem::Players => "PLAYERS",
+            Subsystem::Bullets => "BULLETS",
+            Subsystem::Pickups => "PICKUPS",
+            Subsystem::Platforms => "PLATFORMS",
+            Subsystem::Round => "ROUND",
+            Subsystem::Kills => "KILLS",
+            Subsystem::Perception => "PERCEPTION",
+        }
+    }
+}
+
+/// Per-subsystem hashes for one tick
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Checksum {
+    pub parts: [u32; SUBSYSTEM_COUNT],
+}
+
+impl Checksum {
+    pub const fn new() -> Self {
+        Self {
+            parts: [0; SUBSYSTEM_COUNT],
+        }
+    }
+
+    /// Single value for cheap comparison (e.g. over the network)
+    pub fn combined(&self) -> u32 {
+        let mut h = Hasher::new();
+        for part in self.parts {
+            h.u32(part);
+        }
+        h.finish()
+    }
+
+    /// First subsystem whose hash differs from `other`
+    pub fn first_mismatch(&self, other: &Checksum) -> Option<Subsystem> {
+        Subsystem::ALL
+            .into_iter()
+            .find(|&s| self.parts[s as usize] != other.parts[s as usize])
+    }
+}
+
+/// First detected divergence between the local and a remote simulation
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Desync {
+    pub tick: u32,
+    pub subsystem: Subsystem,
+    pub local: u32,
+    pub remote: u32,
+}
+
+#[derive(Clone, Copy)]
+struct HistoryEntry {
+    tick: u32,
+    checksum: Checksum,
+    valid: bool,
+}
+
+/// Ring buffer of local checksums plus the first desync found
+pub struct DesyncMonitor {
+    history: [HistoryEntry; CHECKSUM_HISTORY],
+    pub first_desync: Option<Desync>,
+}
+
+impl DesyncMonitor {
+    pub const fn new() -> Self {
+        Self {
+            history: [HistoryEntry {
+                tick: 0,
+                checksum: Checksum::new(),
+                valid: false,
+            }; CHECKSUM_HISTORY],
+            first_desync: None,
+        }
+    }
+
+    /// Store the local checksum for a tick (re-simulated ticks overwrite)
+    pub fn record(&mut self, tick: u32, checksum: Checksum) {
+        self.history[tick as usize % CHECKSUM_HISTORY] = HistoryEntry {
+            tick,
+            checksum,
+            valid: true,
+        };
+    }
+
+    /// Local checksum for a tick, if it is still in the history window
+    pub fn local(&self, tick: u32) -> Option<Checksum> {
+        let entry = &self.history[tick as usize % CHECKSUM_HISTORY];
+        if entry.valid && entry.tick == tick {
+            Some(entry.checksum)
+        } else {
+            None
+        }
+    }
+
+    /// Compare a remote checksum against local history. Returns the desync if
+    /// this tick diverged; only the earliest divergence is kept.
+    pub fn compare(&mut self, tick: u32, remote: &Checksum) -> Option<Desync> {
+        let local = self.local(tick)?;
+        let subsystem = local.first_mismatch(remote)?;
+        let desync = Desync {
+            tick,
+            subsystem,
+            local: local.parts[subsystem as usize],
+            remote: remote.parts[subsystem as usize],
+        };
+        match self.first_desync {
+            Some(first) if first.tick <= tick => {}
+            _ => self.first_desync = Some(desync),
+        }
+        Some(desync)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+// =============================================================================
+// HASHING
+// =============================================================================
+
+/// FNV-1a over little-endian words; floats are hashed by bit pattern
+struct Hasher {
+    state: u32,
+}
+
+impl Hasher {
+    const fn new() -> Self {
+        Self { state: FNV_OFFSET }
+    }
+
+    fn u32(&mut self, v: u32) {
+        for byte in v.to_le_bytes() {
+            self.state ^= byte as u32;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.u32(v.to_bits());
+    }
+
+    fn bool(&mut self, v: bool) {
+        self.u32(v as u32);
+    }
+
+    fn finish(&self) -> u32 {
+        self.state
+    }
+}
+
+fn hash_players(sim: &SimState) -> u32 {
+    let mut h = Hasher::new();
+    for p in &sim.players {
+        h.f32(p.x);
+        h.f32(p.y);
+        h.f32(p.vx);
+        h.f32(p.vy);
+        h.bool(p.on_ground);
+        h.bool(p.facing_right);
+        h.f32(p.aim_x);
+        h.f32(p.aim_y);
+        h.bool(p.active);
+        h.bool(p.is_bot);
+        h.u32(p.ammo);
+        h.u32(p.weapon as u32);
+        h.u32(p.weapon_shots);
+        h.u32(p.melee_timer);
+        h.u32(p.melee_windup);
+        h.u32(p.melee_dir as u32);
+        h.u32(p.pogo_timer);
+        h.bool(p.dead);
+        h.u32(p.respawn_timer);
+        h.u32(p.invuln_timer);
+        h.u32(p.jump_buffer);
+        h.u32(p.coyote_timer);
+        h.u32(p.drop_timer);
+        h.u32(p.ai_seed);
+        h.u32(p.ai_shoot_cooldown);
+        h.u32(p.ai_melee_cooldown);
+        h.u32(p.ai_jump_hold);
+        h.u32(p.ai_nav_edge);
+        h.u32(p.ai_rng.state());
+        h.u32(p.ai_noticed);
+        h.u32(p.ai_ignored);
+        h.u32(p.ai_react_timer);
+        h.u32(p.ai_parry_lead);
+    }
+    h.finish()
+}
+
+fn hash_bullets(sim: &SimState) -> u32 {
+    let mut h = Hasher::new();
+    for (i, b) in sim.bullets.iter().enumerate() {
+        // Stale data in free slots never affects gameplay
+        if !b.active {
+            continue;
+        }
+        h.u32(i as u32);
+        h.f32(b.x);
+        h.f32(b.y);
+        h.f32(b.vx);
+        h.f32(b.vy);
+        h.u32(b.owner);
+        h.u32(b.team);
+        h.u32(b.lifetime);
+        h.u32(b.kind as u32);
+        h.u32(b.bounces);
+    }
+    h.finish()
+}
+
+fn hash_pickups(sim: &SimState) -> u32 {
+    let mut h = Hasher::new();
+    for (i, s) in sim.pickups.iter().enumerate() {
+        if !s.active {
+            continue;
+        }
+        h.u32(i as u32);
+        h.f32(s.x);
+        h.f32(s.y);
+        h.f32(s.vy);
+        h.u32(s.lifetime);
+    }
+    for c in &sim.crates {
+        h.bool(c.active);
+        h.u32(c.weapon as u32);
+        h.u32(c.timer);
+    }
+    h.finish()
+}
+
+fn hash_platforms(sim: &SimState) -> u32 {
+    let mut h = Hasher::new();
+    h.bool(sim.has_pit);
+    h.f32(sim.pit_y);
+    for (i, p) in sim.platforms.iter().enumerate() {
+        if !p.active {
+            continue;
+        }
+        h.u32(i as u32);
+        h.f32(p.x);
+        h.f32(p.y);
+        h.f32(p.width);
+        h.f32(p.height);
+        h.bool(p.moving);
+        h.f32(p.move_speed);
+        h.f32(p.move_min);
+        h.f32(p.move_max);
+        h.u32(p.collision as u32);
+    }
+    let hill = &sim.hill;
+    h.bool(hill.active);
+    h.u32(hill.platform as u32);
+    h.u32(hill.stage);
+    h.u32(hill.move_timer);
+    h.bool(hill.held);
+    h.bool(hill.contested);
+    h.u32(hill.holder);
+    h.finish()
+}
+
+fn hash_round(sim: &SimState) -> u32 {
+    let g = &sim.game;
+    // The pause menu overlays the sim without changing it; hash the phase
+    // underneath so a paused replay/peer still matches one that isn't
+    let phase = if g.phase == GamePhase::Paused {
+        g.paused_from
+    } else {
+        g.phase
+    };
+    let mut h = Hasher::new();
+    h.u32(phase as u32);
+    h.u32(g.countdown);
+    h.u32(g.round_end_timer);
+    h.u32(g.current_stage);
+    h.u32(g.round_time_left);
+    h.bool(g.overtime);
+    h.f32(g.arena_left);
+    h.f32(g.arena_right);
+    h.u32(g.final_ko_timer);
+    h.u32(sim.round_number);
+    h.u32(sim.hit_freeze);
+    h.u32(sim.rng.state());
+    h.finish()
+}
+
+fn hash_kills(sim: &SimState) -> u32 {
+    let mut h = Hasher::new();
+    for p in &sim.players {
+        h.u32(p.kills);
+        h.u32(p.stocks);
+        h.bool(p.eliminated);
+        h.u32(p.hill_ticks);
+    }
+    h.u32(sim.game.winner_idx);
+    // Team setup decides who can hurt whom and how kills add up
+    h.bool(sim.config.team_mode);
+    h.bool(sim.config.friendly_fire);
+    for &team in &sim.config.teams {
+        h.u32(team);
+    }
+    // As do the settings that decide when the match ends
+    h.u32(sim.config.rule as u32);
+    h.u32(sim.config.kills_to_win);
+    h.u32(sim.config.round_time_seconds);
+    h.u32(sim.config.stocks);
+    h.u32(sim.config.hill_seconds);
+    h.finish()
+}
+
+fn hash_perception(sim: &SimState) -> u32 {
+    let mut h = Hasher::new();
+    // Oldest to newest, so where the ring buffer wraps doesn't matter
+    let buf = &sim.perception;
+    h.u32(buf.len as u32);
+    for age in (0..buf.len).rev() {
+        let frame = &buf.frames[(buf.head + PERCEPTION_FRAMES - age) % PERCEPTION_FRAMES];
+        for p in &frame.players {
+            h.f32(p.x);
+            h.f32(p.y);
+            h.f32(p.vx);
+            h.f32(p.vy);
+            h.u32(p.invuln);
+            h.bool(p.alive);
+        }
+        for b in frame.bullets.iter().filter(|b| b.active) {
+            h.f32(b.x);
+            h.f32(b.y);
+            h.f32(b.vx);
+            h.f32(b.vy);
+            h.u32(b.owner);
+            h.u32(b.team);
+            h.u32(b.kind as u32);
+        }
+    }
+    // How bots read and act on what they see
+    h.u32(sim.config.bot_difficulty);
+    for &profile in &sim.config.bot_profiles {
+        h.u32(profile);
+    }
+    h.finish()
+}
+
+/// Hash every subsystem of a simulation state
+pub fn compute(sim: &SimState) -> Checksum {
+    let mut checksum = Checksum::new();
+    checksum.parts[Subsystem::Players as usize] = hash_players(sim);
+    checksum.parts[Subsystem::Bullets as usize] = hash_bullets(sim);
+    checksum.parts[Subsystem::Pickups as usize] = hash_pickups(sim);
+    checksum.parts[Subsystem::Platforms as usize] = hash_platforms(sim);
+    checksum.parts[Subsystem::Round as usize] = hash_round(sim);
+    checksum.parts[Subsystem::Kills as usize] = hash_kills(sim);
+    checksum.parts[Subsystem::Perception as usize] = hash_perception(sim);
+    checksum
+}
+
+// =============================================================================
+// GLOBAL STATE
+// =============================================================================
+
+pub static mut DESYNC: DesyncMonitor = DesyncMonitor::new();
+
+/// Optional log hook, called with each desync earlier than any seen before
+pub static mut DESYNC_HOOK: Option<fn(&Desync)> = None;
+
+// =============================================================================
+// CONVENIENCE FUNCTIONS
+// =============================================================================
+
+/// Checksum of the live world
+pub fn current() -> Checksum {
+    unsafe { compute(&WORLD.sim) }
+}
+
+/// Record the live world's checksum under the current tick (end of update)
+pub fn record_tick() {
+    unsafe {
+        DESYNC.record(WORLD.sim.tick, compute(&WORLD.sim));
+    }
+}
+
+/// Feed in a checksum received from a peer (or a replay file)
+pub fn report_remote(tick: u32, remote: &Checksum) -> Option<Desync> {
+    unsafe {
+        let earliest = DESYNC.first_desync;
+        let desync = DESYNC.compare(tick, remote)?;
+        // Reports can arrive out of order: tell the hook every time the
+        // first known divergence moves earlier
+        if DESYNC.first_desync != earliest {
+            if let Some(hook) = DESYNC_HOOK {
+                hook(&desync);
+            }
+        }
+        Some(desync)
+    }
+}
+
+pub fn set_desync_hook(hook: Option<fn(&Desync)>) {
+    unsafe {
+        DESYNC_HOOK = hook;
+    }
+}
+
+pub fn first_desync() -> Option<Desync> {
+    unsafe { DESYNC.first_desync }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform;
+    use crate::world::SimState;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn each_subsystem_hashes_its_own_state() {
+        let base = SimState::new();
+        let reference = compute(&base);
+        type Change = (Subsystem, fn(&mut SimState));
+        let changes: [Change; SUBSYSTEM_COUNT] = [
+            (Subsystem::Players, |s| s.players[1].x += 0.5),
+            (Subsystem::Bullets, |s| s.bullets[3].active = true),
+            (Subsystem::Pickups, |s| s.crates[0].timer += 1),
+            (Subsystem::Platforms, |s| s.platforms[0].active = true),
+            (Subsystem::Round, |s| s.game.arena_left += 1.0),
+            (Subsystem::Kills, |s| s.players[2].kills += 1),
+            (Subsystem::Perception, |s| s.config.bot_profiles[0] = 1),
+        ];
+        for (subsystem, change) in changes {
+            let mut sim = base;
+            change(&mut sim);
+            let checksum = compute(&sim);
+            for s in Subsystem::ALL {
+                let moved = checksum.parts[s as usize] != reference.parts[s as usize];
+                assert_eq!(moved, s == subsystem, "{s:?} after changing {subsystem:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn match_end_settings_are_hashed() {
+        let base = SimState::new();
+        let reference = compute(&base);
+        let changes: [fn(&mut SimState); 4] = [
+            |s| s.config.kills_to_win = 7,
+            |s| s.config.round_time_seconds = 0,
+            |s| s.config.stocks += 1,
+            |s| s.config.hill_seconds += 1,
+        ];
+        for change in changes {
+            let mut sim = base;
+            change(&mut sim);
+            assert_eq!(
+                compute(&sim).first_mismatch(&reference),
+                Some(Subsystem::Kills)
+            );
+        }
+    }
+
+    #[test]
+    fn free_slots_and_the_pause_menu_are_not_hashed() {
+        let base = SimState::new();
+        let reference = compute(&base);
+
+        let mut sim = base;
+        sim.bullets[5].x = 3.0;
+        sim.platforms[2].y = -1.0;
+        assert_eq!(compute(&sim), reference);
+
+        let mut sim = base;
+        sim.game.paused_from = sim.game.phase;
+        sim.game.phase = GamePhase::Paused;
+        assert_eq!(compute(&sim), reference);
+    }
+
+    #[test]
+    fn monitor_keeps_the_earliest_desync() {
+        let mut monitor = DesyncMonitor::new();
+        let local = Checksum::new();
+        for tick in 0..CHECKSUM_HISTORY as u32 + 10 {
+            monitor.record(tick, local);
+        }
+        let mut remote = local;
+        remote.parts[Subsystem::Bullets as usize] = 1;
+
+        // Matching ticks and ticks outside the history say nothing
+        assert_eq!(monitor.compare(50, &local), None);
+        assert_eq!(monitor.compare(3, &remote), None);
+
+        let late = monitor.compare(60, &remote).unwrap();
+        assert_eq!((late.tick, late.subsystem), (60, Subsystem::Bullets));
+        monitor.compare(40, &remote);
+        monitor.compare(70, &remote);
+        assert_eq!(monitor.first_desync.map(|d| d.tick), Some(40));
+    }
+
+    static HOOK_CALLS: AtomicU32 = AtomicU32::new(0);
+    static HOOK_TICK: AtomicU32 = AtomicU32::new(0);
+
+    fn count_desync(desync: &Desync) {
+        HOOK_CALLS.fetch_add(1, Ordering::Relaxed);
+        HOOK_TICK.store(desync.tick, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn hook_fires_whenever_the_first_desync_moves_earlier() {
+        let _lock = platform::test_lock();
+        unsafe {
+            DESYNC.clear();
+            for tick in 10..=20 {
+                DESYNC.record(tick, Checksum::new());
+            }
+        }
+        set_desync_hook(Some(count_desync));
+        HOOK_CALLS.store(0, Ordering::Relaxed);
+        let mut remote = Checksum::new();
+        remote.parts[Subsystem::Players as usize] = 1;
+
+        // Reports arriving out of order: 18, then the earlier 15, then 19
+        report_remote(18, &remote);
+        assert_eq!(HOOK_CALLS.load(Ordering::Relaxed), 1);
+        report_remote(15, &remote);
+        assert_eq!(HOOK_CALLS.load(Ordering::Relaxed), 2);
+        assert_eq!(HOOK_TICK.load(Ordering::Relaxed), 15);
+        report_remote(19, &remote);
+        report_remote(15, &remote);
+        report_remote(12, &Checksum::new());
+        assert_eq!(HOOK_CALLS.load(Ordering::Relaxed), 2);
+        assert_eq!(first_desync().map(|d| d.tick), Some(15));
+
+        set_desync_hook(None);
+        unsafe {
+            DESYNC.clear();
+        }
+    }
+}
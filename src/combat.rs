@@ -1,14 +1,22 @@
 //! Combat system
 //!
-//! Contains Bullet struct, shooting, melee, and collision detection.
+//! Contains Bullet struct, shooting, melee, and collision detection. Per-weapon
+//! bullet behavior (spread, ricochet, laser, grenade) keys off `Bullet::kind`.
 
 use crate::audio;
 use crate::game_state;
 use crate::particles;
+use crate::pickups::{self, SHELL_SIZE};
 use crate::player::{
-    aabb_overlap, kill_player, MELEE_RANGE, PLAYERS, PLAYER_COLORS, PLAYER_HEIGHT, PLAYER_WIDTH,
+    aabb_overlap, abs, can_pogo, in_perfect_parry, kill_player, player_color, pogo, MeleeDir,
+    Player, MAX_PLAYERS, MELEE_RANGE, PLAYER_HEIGHT, PLAYER_WIDTH,
 };
-use crate::stage::PLATFORMS;
+use crate::stats::{self, KillCause};
+use crate::weapons::{
+    Weapon, GRENADE_BLAST_RADIUS, GRENADE_GRAVITY, GRENADE_MAX_FALL, RICOCHET_BOUNCES,
+    SPREAD_ANGLE, SPREAD_PELLETS,
+};
+use crate::world::WORLD;
 
 // =============================================================================
 // CONSTANTS
@@ -17,6 +25,17 @@ use crate::stage::PLATFORMS;
 pub const MAX_BULLETS: usize = 32;
 pub const BULLET_SPEED: f32 = 0.4;
 pub const BULLET_LIFETIME: u32 = 120; // 2 seconds at 60fps
+/// Opposing bullets closer than this annihilate each other
+pub const CLASH_RADIUS: f32 = 0.2;
+/// Perfect parries send the bullet back this much faster
+pub const PERFECT_PARRY_SPEED_MULT: f32 = 1.5;
+/// Up/down slashes reach this far past each side of the body
+const MELEE_VERTICAL_OVERHANG: f32 = 0.4;
+/// Stick deflection needed to count as aiming
+const AIM_THRESHOLD: f32 = 0.3;
+/// Melee clash knockback (horizontal speed and upward pop)
+pub const MELEE_CLASH_KNOCKBACK: f32 = 0.15;
+pub const MELEE_CLASH_POP: f32 = 0.25;
 
 // =============================================================================
 // DATA STRUCTURES
@@ -29,10 +48,21 @@ pub struct Bullet {
     pub vx: f32,
     pub vy: f32,
     pub owner: u32, // Player index who fired
+    pub team: u32,  // Owner's team (follows the owner on deflect)
     pub lifetime: u32,
+    pub kind: Weapon,
+    pub bounces: u32, // Ricochet bounces left
     pub active: bool,
 }
 
+/// What a bullet runs into first along its path this tick
+#[derive(Clone, Copy)]
+enum Contact {
+    Platform(usize),
+    Deflect(usize),
+    Player(usize),
+}
+
 impl Bullet {
     pub const fn new() -> Self {
         Self {
@@ -41,18 +71,15 @@ impl Bullet {
             vx: 0.0,
             vy: 0.0,
             owner: 0,
+            team: 0,
             lifetime: 0,
+            kind: Weapon::Pistol,
+            bounces: 0,
             active: false,
         }
     }
 }
 
-// =============================================================================
-// GLOBAL STATE
-// =============================================================================
-
-pub static mut BULLETS: [Bullet; MAX_BULLETS] = [Bullet::new(); MAX_BULLETS];
-
 // =============================================================================
 // HELPER FUNCTIONS
 // =============================================================================
@@ -62,10 +89,86 @@ pub fn point_in_aabb(px: f32, py: f32, x: f32, y: f32, w: f32, h: f32) -> bool {
     px >= x && px <= x + w && py >= y && py <= y + h
 }
 
+/// Swept point vs AABB: earliest fraction `t` in [0, 1] along the segment
+/// `start + t * delta` at which the point is inside `(x, y, w, h)`
+pub fn segment_aabb_entry(
+    start: (f32, f32),
+    delta: (f32, f32),
+    rect: (f32, f32, f32, f32),
+) -> Option<f32> {
+    let (x0, y0) = start;
+    let (dx, dy) = delta;
+    let (x, y, w, h) = rect;
+    let mut t_enter = 0.0_f32;
+    let mut t_exit = 1.0_f32;
+    for (p0, d, lo, hi) in [(x0, dx, x, x + w), (y0, dy, y, y + h)] {
+        if d == 0.0 {
+            if p0 < lo || p0 > hi {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (lo - p0) / d;
+        let t2 = (hi - p0) / d;
+        t_enter = t_enter.max(t1.min(t2));
+        t_exit = t_exit.min(t1.max(t2));
+        if t_enter > t_exit {
+            return None;
+        }
+    }
+    Some(t_enter)
+}
+
+/// Swept point vs circle: earliest fraction `t` in [0, 1] along the segment
+/// at which the point is within `r` of the center
+pub fn segment_circle_entry(
+    start: (f32, f32),
+    delta: (f32, f32),
+    center: (f32, f32),
+    r: f32,
+) -> Option<f32> {
+    let (x0, y0) = start;
+    let (dx, dy) = delta;
+    let (cx, cy) = center;
+    let fx = x0 - cx;
+    let fy = y0 - cy;
+    let c = fx * fx + fy * fy - r * r;
+    if c < 0.0 {
+        return Some(0.0); // Already inside
+    }
+    let a = dx * dx + dy * dy;
+    if a == 0.0 {
+        return None;
+    }
+    let b = 2.0 * (fx * dx + fy * dy);
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return None;
+    }
+    let t = (-b - libm::sqrtf(disc)) / (2.0 * a);
+    if (0.0..=1.0).contains(&t) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// Center of a player's melee deflect zone
+fn deflect_center(player: &Player) -> (f32, f32) {
+    let cx = player.x + PLAYER_WIDTH / 2.0;
+    let cy = player.y + PLAYER_HEIGHT / 2.0;
+    match player.melee_dir {
+        MeleeDir::Side if player.facing_right => (cx + MELEE_RANGE / 2.0, cy),
+        MeleeDir::Side => (cx - MELEE_RANGE / 2.0, cy),
+        MeleeDir::Up => (cx, cy + MELEE_RANGE / 2.0),
+        MeleeDir::Down => (cx, cy - MELEE_RANGE / 2.0),
+    }
+}
+
 /// Normalize aim to 8 directions
-fn normalize_aim(x: f32, y: f32, facing_right: bool) -> (f32, f32) {
+pub fn normalize_aim(x: f32, y: f32, facing_right: bool) -> (f32, f32) {
     // Snap to 8 directions
-    let threshold = 0.3;
+    let threshold = AIM_THRESHOLD;
 
     let ax = if x > threshold {
         1.0
@@ -100,170 +203,467 @@ fn normalize_aim(x: f32, y: f32, facing_right: bool) -> (f32, f32) {
 // BULLET LOGIC
 // =============================================================================
 
-pub fn spawn_bullet(player_idx: usize, aim_x: f32, aim_y: f32) {
+/// Fire `kind` from a player; spread shot fans out several pellets
+pub fn spawn_bullet(player_idx: usize, aim_x: f32, aim_y: f32, kind: Weapon) {
     unsafe {
-        let p = &PLAYERS[player_idx];
+        let p = &WORLD.sim.players[player_idx];
+
+        // Determine aim direction (8-directional)
+        let (dx, dy) = normalize_aim(aim_x, aim_y, p.facing_right);
+
+        if kind == Weapon::Spread {
+            let half = (SPREAD_PELLETS - 1) as f32 * 0.5;
+            for i in 0..SPREAD_PELLETS {
+                let angle = (i as f32 - half) * SPREAD_ANGLE;
+                let (c, s) = (libm::cosf(angle), libm::sinf(angle));
+                spawn_projectile(player_idx, (dx * c - dy * s, dx * s + dy * c), kind);
+            }
+        } else {
+            spawn_projectile(player_idx, (dx, dy), kind);
+        }
+
+        // Spawn muzzle flash effect light (weapon color, fast decay)
+        let spawn_x = p.x + PLAYER_WIDTH / 2.0;
+        let spawn_y = p.y + PLAYER_HEIGHT / 2.0;
+        game_state::spawn_effect_light(spawn_x, spawn_y, kind.color(), 1.5, 0.7);
+    }
+}
+
+/// Put one bullet in a free slot, leaving the player's center along `dir`
+fn spawn_projectile(player_idx: usize, dir: (f32, f32), kind: Weapon) {
+    unsafe {
+        let p = &WORLD.sim.players[player_idx];
 
         // Find inactive bullet slot
-        for bullet in &mut BULLETS {
+        for bullet in &mut WORLD.sim.bullets {
             if bullet.active {
                 continue;
             }
 
-            // Determine aim direction (8-directional)
-            let (dx, dy) = normalize_aim(aim_x, aim_y, p.facing_right);
-
-            // Spawn bullet
-            let spawn_x = p.x + PLAYER_WIDTH / 2.0;
-            let spawn_y = p.y + PLAYER_HEIGHT / 2.0;
-            bullet.x = spawn_x;
-            bullet.y = spawn_y;
-            bullet.vx = dx * BULLET_SPEED;
-            bullet.vy = dy * BULLET_SPEED;
+            let speed = kind.speed();
+            bullet.x = p.x + PLAYER_WIDTH / 2.0;
+            bullet.y = p.y + PLAYER_HEIGHT / 2.0;
+            bullet.vx = dir.0 * speed;
+            bullet.vy = dir.1 * speed;
             bullet.owner = player_idx as u32;
-            bullet.lifetime = BULLET_LIFETIME;
+            bullet.team = game_state::team_of(player_idx);
+            bullet.lifetime = kind.lifetime();
+            bullet.kind = kind;
+            bullet.bounces = if kind == Weapon::Ricochet {
+                RICOCHET_BOUNCES
+            } else {
+                0
+            };
             bullet.active = true;
-
-            // Spawn muzzle flash effect light (yellow, fast decay)
-            game_state::spawn_effect_light(spawn_x, spawn_y, 0xFFFF00FF, 1.5, 0.7);
-
             return;
         }
     }
 }
 
-pub fn update_bullets() {
+/// Whether a point on the edge of an AABB is on a left/right side (vs top/bottom)
+fn hits_side(x: f32, y: f32, rect: (f32, f32, f32, f32)) -> bool {
+    let (rx, ry, w, h) = rect;
+    let side = abs(x - rx).min(abs(x - (rx + w)));
+    let cap = abs(y - ry).min(abs(y - (ry + h)));
+    side < cap
+}
+
+/// Grenade blast: kills every hurtable player in range, the thrower included
+fn explode(x: f32, y: f32, owner: u32, team: u32) {
     unsafe {
-        for bullet in &mut BULLETS {
-            if !bullet.active {
+        audio::play_explosion(x / 10.0);
+        game_state::trigger_shake(0.7);
+        game_state::spawn_effect_light(x, y, Weapon::Grenade.color(), 4.0, 0.85);
+        particles::spawn_death_particles(x, y, 0xFF8020FF);
+        particles::spawn_death_particles(x, y, 0xFFE040FF);
+
+        for i in 0..WORLD.sim.players.len() {
+            let p = &WORLD.sim.players[i];
+            if !p.active || p.dead || p.invuln_timer > 0 {
                 continue;
             }
+            if i as u32 != owner && !game_state::can_hurt(team, i) {
+                continue;
+            }
+            let dx = p.x + PLAYER_WIDTH / 2.0 - x;
+            let dy = p.y + PLAYER_HEIGHT / 2.0 - y;
+            if dx * dx + dy * dy <= GRENADE_BLAST_RADIUS * GRENADE_BLAST_RADIUS {
+                kill_player(i, owner, KillCause::Grenade);
+            }
+        }
+    }
+}
 
-            // Move bullet (fixed timestep)
-            bullet.x += bullet.vx;
-            bullet.y += bullet.vy;
+/// Opposing bullets whose paths meet this tick cancel out in a spark burst.
+/// Grenades go off, lasers burn through and keep going.
+fn resolve_clashes() {
+    unsafe {
+        let bullets = &mut WORLD.sim.bullets;
+        for i in 0..bullets.len() {
+            for j in (i + 1)..bullets.len() {
+                let (a, b) = (bullets[i], bullets[j]);
+                if !a.active || !b.active || a.owner == b.owner {
+                    continue;
+                }
+                // Only bullets that could hurt each other's shooter clash
+                if !game_state::can_hurt(a.team, b.owner as usize) {
+                    continue;
+                }
 
-            // Spawn bullet trail particles every 3 frames
-            if bullet.lifetime % 3 == 0 {
-                particles::spawn_bullet_trail(bullet.x, bullet.y);
+                // Sweep b relative to a over this tick's movement
+                let Some(t) = segment_circle_entry(
+                    (b.x - a.x, b.y - a.y),
+                    (b.vx - a.vx, b.vy - a.vy),
+                    (0.0, 0.0),
+                    CLASH_RADIUS,
+                ) else {
+                    continue;
+                };
+                let x = (a.x + a.vx * t + b.x + b.vx * t) * 0.5;
+                let y = (a.y + a.vy * t + b.y + b.vy * t) * 0.5;
+
+                // Lasers survive unless they meet another laser
+                let lasers = (a.kind == Weapon::Laser, b.kind == Weapon::Laser);
+                bullets[i].active = lasers.0 && !lasers.1;
+                bullets[j].active = lasers.1 && !lasers.0;
+
+                audio::play_clash(x / 10.0);
+                game_state::trigger_shake(0.25);
+                game_state::trigger_hit_freeze(3);
+                game_state::spawn_effect_light(x, y, 0xFFB040FF, 2.0, 0.75);
+                particles::spawn_clash_sparks(x, y);
+                stats::record_clash();
+
+                for bullet in [a, b] {
+                    if bullet.kind == Weapon::Grenade {
+                        explode(x, y, bullet.owner, bullet.team);
+                    }
+                }
             }
+        }
+    }
+}
 
-            // Lifetime
-            bullet.lifetime -= 1;
-            if bullet.lifetime == 0 {
-                bullet.active = false;
+/// Earliest thing a bullet runs into along this tick's path `p0 + d * t`, at
+/// `t >= from`. Ties keep the first found, so platforms win over players and
+/// deflects win over hits.
+fn first_contact(
+    bullet: &Bullet,
+    p0: (f32, f32),
+    d: (f32, f32),
+    from: f32,
+) -> Option<(f32, Contact)> {
+    unsafe {
+        let mut contact: Option<(f32, Contact)> = None;
+        let mut consider = |t: Option<f32>, c: Contact| {
+            if let Some(t) = t {
+                if t >= from && contact.is_none_or(|(best, _)| t < best) {
+                    contact = Some((t, c));
+                }
+            }
+        };
+
+        // Platform collision (bullets stop on platforms; ricochets bounce)
+        for (k, platform) in WORLD.sim.platforms.iter().enumerate() {
+            if !platform.active {
                 continue;
             }
+            consider(
+                segment_aabb_entry(
+                    p0,
+                    d,
+                    (platform.x, platform.y, platform.width, platform.height),
+                ),
+                Contact::Platform(k),
+            );
+        }
 
-            // Screen bounds
-            if bullet.x < -12.0 || bullet.x > 12.0 || bullet.y < -10.0 || bullet.y > 10.0 {
-                bullet.active = false;
+        // Player collision
+        for (i, player) in WORLD.sim.players.iter().enumerate() {
+            if !player.active || player.dead {
                 continue;
             }
 
-            // Platform collision (bullets stop on platforms)
-            for platform in &PLATFORMS {
-                if !platform.active {
-                    continue;
-                }
+            // Can't hit self (or teammates without friendly fire)
+            if i as u32 == bullet.owner || !game_state::can_hurt(bullet.team, i) {
+                continue;
+            }
 
-                if point_in_aabb(
-                    bullet.x,
-                    bullet.y,
-                    platform.x,
-                    platform.y,
-                    platform.width,
-                    platform.height,
-                ) {
-                    bullet.active = false;
-                    break;
-                }
+            // Melee deflection zone
+            if player.melee_timer > 0 {
+                let (cx, cy) = deflect_center(player);
+                consider(
+                    segment_circle_entry(p0, d, (cx, cy), MELEE_RANGE),
+                    Contact::Deflect(i),
+                );
+            }
+
+            // Hitbox
+            if player.invuln_timer == 0 {
+                consider(
+                    segment_aabb_entry(p0, d, (player.x, player.y, PLAYER_WIDTH, PLAYER_HEIGHT)),
+                    Contact::Player(i),
+                );
             }
+        }
+
+        contact
+    }
+}
+
+/// A bullet reached a player: kill them, with the impact effects
+fn bullet_kills_player(i: usize, bullet: &Bullet) {
+    unsafe {
+        let player = &WORLD.sim.players[i];
+        let px = player.x;
+        let py = player.y;
+        let pw = PLAYER_WIDTH;
+        let ph = PLAYER_HEIGHT;
+
+        // Play hit sound before killing player
+        audio::play_hit();
+        // Screen shake on bullet hit
+        game_state::trigger_shake(0.6);
+        // Hit freeze for impact (5 frames ~83ms)
+        game_state::trigger_hit_freeze(5);
+        // Impact flash on hit
+        game_state::trigger_impact_flash();
+        // Camera zoom on kill
+        game_state::trigger_camera_zoom();
+        // Death effect light (victim's color, bright)
+        game_state::spawn_effect_light(px + pw / 2.0, py + ph / 2.0, player_color(i), 3.0, 0.8);
+        // Kill player
+        kill_player(i, bullet.owner, bullet.kind.kill_cause());
+    }
+}
 
+pub fn update_bullets() {
+    unsafe {
+        resolve_clashes();
+
+        for bullet in &mut WORLD.sim.bullets {
             if !bullet.active {
                 continue;
             }
 
-            // Player collision
-            for (i, player) in PLAYERS.iter().enumerate() {
-                if !player.active || player.dead {
-                    continue;
+            // Grenades arc
+            if bullet.kind == Weapon::Grenade {
+                bullet.vy = (bullet.vy - GRENADE_GRAVITY).max(-GRENADE_MAX_FALL);
+            }
+
+            // Move bullet (fixed timestep), remembering the path for swept tests
+            let (x0, y0) = (bullet.x, bullet.y);
+            let (dx, dy) = (bullet.vx, bullet.vy);
+            bullet.x += dx;
+            bullet.y += dy;
+
+            // Spawn bullet trail particles every 3 frames
+            if bullet.lifetime % 3 == 0 {
+                particles::spawn_bullet_trail(bullet.x, bullet.y);
+            }
+
+            // Lifetime
+            bullet.lifetime -= 1;
+            if bullet.lifetime == 0 {
+                bullet.active = false;
+                match bullet.kind {
+                    Weapon::Pistol => pickups::spawn_shell(bullet.x, bullet.y),
+                    Weapon::Grenade => explode(bullet.x, bullet.y, bullet.owner, bullet.team),
+                    _ => {}
                 }
+                continue;
+            }
 
-                // Can't hit self
-                if i as u32 == bullet.owner {
-                    continue;
+            let mut contact = first_contact(bullet, (x0, y0), (dx, dy), 0.0);
+            // A laser kills each player it meets and sweeps on from there
+            while let Some((t, Contact::Player(i))) = contact {
+                if bullet.kind != Weapon::Laser {
+                    break;
                 }
+                bullet_kills_player(i, bullet);
+                contact = first_contact(bullet, (x0, y0), (dx, dy), t);
+            }
 
-                // Check melee deflection
-                if player.melee_timer > 0 {
-                    let melee_x = player.x
-                        + PLAYER_WIDTH / 2.0
-                        + (if player.facing_right {
-                            MELEE_RANGE / 2.0
+            if let Some((t, contact)) = contact {
+                // Resolve at the point of contact, not the end of the tick
+                bullet.x = x0 + dx * t;
+                bullet.y = y0 + dy * t;
+
+                match contact {
+                    Contact::Platform(k) => match bullet.kind {
+                        Weapon::Ricochet if bullet.bounces > 0 => {
+                            let pl = &WORLD.sim.platforms[k];
+                            let rect = (pl.x, pl.y, pl.width, pl.height);
+                            if hits_side(bullet.x, bullet.y, rect) {
+                                bullet.vx = -bullet.vx;
+                            } else {
+                                bullet.vy = -bullet.vy;
+                            }
+                            // Step off the surface so the next sweep starts outside
+                            bullet.x += bullet.vx * 0.05;
+                            bullet.y += bullet.vy * 0.05;
+                            bullet.bounces -= 1;
+                            particles::spawn_deflect_burst(bullet.x, bullet.y);
+                        }
+                        Weapon::Grenade => {
+                            bullet.active = false;
+                            explode(bullet.x, bullet.y, bullet.owner, bullet.team);
+                        }
+                        Weapon::Pistol => {
+                            bullet.active = false;
+                            // Back the shell off the surface so it doesn't start embedded
+                            let back = SHELL_SIZE * 0.5 / libm::sqrtf(dx * dx + dy * dy).max(0.001);
+                            pickups::spawn_shell(bullet.x - dx * back, bullet.y - dy * back);
+                        }
+                        _ => {
+                            bullet.active = false;
+                        }
+                    },
+                    Contact::Deflect(i) => {
+                        let deflector = &WORLD.sim.players[i];
+                        // A swing that comes out on top of a bullet still deflects
+                        // it, but only meeting one on its way in is a perfect parry
+                        let perfect = in_perfect_parry(deflector) && t > 0.0;
+
+                        // Held direction aims the return; neutral sends it straight back
+                        let (ax, ay) = (deflector.aim_x, deflector.aim_y);
+                        let (dir_x, dir_y) = if abs(ax) > AIM_THRESHOLD || abs(ay) > AIM_THRESHOLD {
+                            normalize_aim(ax, ay, deflector.facing_right)
                         } else {
-                            -MELEE_RANGE / 2.0
-                        });
-                    let melee_y = player.y + PLAYER_HEIGHT / 2.0;
-
-                    let dx = bullet.x - melee_x;
-                    let dy = bullet.y - melee_y;
-                    let dist = libm::sqrtf(dx * dx + dy * dy);
-
-                    if dist < MELEE_RANGE {
-                        // Deflect bullet - reverse direction and change owner
-                        bullet.vx = -bullet.vx;
-                        bullet.vy = -bullet.vy;
+                            let len = libm::sqrtf(dx * dx + dy * dy).max(0.001);
+                            (-dx / len, -dy / len)
+                        };
+                        let speed = bullet.kind.speed()
+                            * if perfect {
+                                PERFECT_PARRY_SPEED_MULT
+                            } else {
+                                1.0
+                            };
+                        bullet.vx = dir_x * speed;
+                        bullet.vy = dir_y * speed;
                         bullet.owner = i as u32;
-                        bullet.lifetime = BULLET_LIFETIME; // Reset lifetime
-                                                           // Play deflect sound
+                        bullet.team = game_state::team_of(i);
+                        bullet.lifetime = bullet.kind.lifetime(); // Reset lifetime
+
+                        // Play deflect sound
                         audio::play_deflect();
-                        // Screen shake on deflect
-                        game_state::trigger_shake(0.3);
-                        // Hit freeze for deflect (3 frames ~50ms, brief acknowledgment)
-                        game_state::trigger_hit_freeze(3);
-                        // Deflect effect light (cyan flash)
-                        game_state::spawn_effect_light(bullet.x, bullet.y, 0x00FFFFFF, 2.0, 0.75);
+                        if perfect {
+                            // Perfect parry: bigger hit, gold flash
+                            game_state::trigger_shake(0.5);
+                            game_state::trigger_hit_freeze(6);
+                            game_state::spawn_effect_light(
+                                bullet.x, bullet.y, 0xFFD040FF, 3.0, 0.8,
+                            );
+                        } else {
+                            // Screen shake on deflect
+                            game_state::trigger_shake(0.3);
+                            // Hit freeze for deflect (3 frames ~50ms, brief acknowledgment)
+                            game_state::trigger_hit_freeze(3);
+                            // Deflect effect light (cyan flash)
+                            game_state::spawn_effect_light(
+                                bullet.x, bullet.y, 0x00FFFFFF, 2.0, 0.75,
+                            );
+                        }
                         // Deflect particles + UI popup
                         particles::spawn_deflect_burst(bullet.x, bullet.y);
-                        game_state::register_deflect(i as u32);
-                        continue;
+                        game_state::register_deflect(i as u32, perfect);
+                        stats::record_deflect(perfect);
+                        if can_pogo(&WORLD.sim.players[i]) {
+                            pogo(&mut WORLD.sim.players[i]);
+                        }
+                    }
+                    Contact::Player(_) if bullet.kind == Weapon::Grenade => {
+                        bullet.active = false;
+                        explode(bullet.x, bullet.y, bullet.owner, bullet.team);
+                    }
+                    Contact::Player(i) => {
+                        bullet_kills_player(i, bullet);
+                        bullet.active = false;
                     }
                 }
+                continue;
+            }
+
+            // Screen bounds
+            if bullet.x < -12.0 || bullet.x > 12.0 || bullet.y < -10.0 || bullet.y > 10.0 {
+                bullet.active = false;
+            }
+        }
+    }
+}
+
+/// Area a player's active melee covers
+fn melee_hitbox(p: &Player) -> (f32, f32, f32, f32) {
+    let vx = p.x - MELEE_VERTICAL_OVERHANG;
+    let vw = PLAYER_WIDTH + MELEE_VERTICAL_OVERHANG * 2.0;
+    match p.melee_dir {
+        MeleeDir::Side if p.facing_right => (p.x + PLAYER_WIDTH, p.y, MELEE_RANGE, PLAYER_HEIGHT),
+        MeleeDir::Side => (p.x - MELEE_RANGE, p.y, MELEE_RANGE, PLAYER_HEIGHT),
+        MeleeDir::Up => (vx, p.y + PLAYER_HEIGHT, vw, MELEE_RANGE),
+        MeleeDir::Down => (vx, p.y - MELEE_RANGE, vw, MELEE_RANGE),
+    }
+}
 
-                // Hit detection
-                if player.invuln_timer > 0 {
+/// Whether `attacker`'s melee would kill `target` this tick
+fn melee_connects(attacker_idx: usize, target_idx: usize) -> bool {
+    unsafe {
+        let attacker = &WORLD.sim.players[attacker_idx];
+        let target = &WORLD.sim.players[target_idx];
+        attacker_idx != target_idx
+            && attacker.active
+            && !attacker.dead
+            && attacker.melee_timer > 0
+            && target.active
+            && !target.dead
+            && target.invuln_timer == 0
+            && game_state::can_hurt(game_state::team_of(attacker_idx), target_idx)
+            && aabb_overlap(
+                melee_hitbox(attacker),
+                (target.x, target.y, PLAYER_WIDTH, PLAYER_HEIGHT),
+            )
+    }
+}
+
+/// Blade-on-blade: two opposing melees that meet cancel out and knock both apart
+fn resolve_melee_clashes() {
+    unsafe {
+        for a in 0..MAX_PLAYERS {
+            for b in (a + 1)..MAX_PLAYERS {
+                let (pa, pb) = (&WORLD.sim.players[a], &WORLD.sim.players[b]);
+                let swinging = |p: &Player| p.active && !p.dead && p.melee_timer > 0;
+                if !swinging(pa) || !swinging(pb) {
                     continue;
                 }
-                let px = player.x;
-                let py = player.y;
-                let pw = PLAYER_WIDTH;
-                let ph = PLAYER_HEIGHT;
-
-                if point_in_aabb(bullet.x, bullet.y, px, py, pw, ph) {
-                    // Play hit sound before killing player
-                    audio::play_hit();
-                    // Screen shake on bullet hit
-                    game_state::trigger_shake(0.6);
-                    // Hit freeze for impact (5 frames ~83ms)
-                    game_state::trigger_hit_freeze(5);
-                    // Impact flash on hit
-                    game_state::trigger_impact_flash();
-                    // Camera zoom on kill
-                    game_state::trigger_camera_zoom();
-                    // Death effect light (victim's color, bright)
-                    game_state::spawn_effect_light(
-                        px + pw / 2.0,
-                        py + ph / 2.0,
-                        PLAYER_COLORS[i],
-                        3.0,
-                        0.8,
-                    );
-                    // Kill player
-                    kill_player(i, bullet.owner);
-                    bullet.active = false;
-                    break;
+                if !game_state::can_hurt(game_state::team_of(a), b) {
+                    continue;
                 }
+                let blades_meet = aabb_overlap(melee_hitbox(pa), melee_hitbox(pb));
+                let trade = melee_connects(a, b) && melee_connects(b, a);
+                if !blades_meet && !trade {
+                    continue;
+                }
+
+                // Push apart, away from each other's centers
+                let a_left = pa.x <= pb.x;
+                let x = (pa.x + pb.x + PLAYER_WIDTH) * 0.5;
+                let y = (pa.y + pb.y + PLAYER_HEIGHT) * 0.5;
+                for (idx, push_left) in [(a, a_left), (b, !a_left)] {
+                    let p = &mut WORLD.sim.players[idx];
+                    p.melee_timer = 0;
+                    p.vx = if push_left {
+                        -MELEE_CLASH_KNOCKBACK
+                    } else {
+                        MELEE_CLASH_KNOCKBACK
+                    };
+                    p.vy = p.vy.max(MELEE_CLASH_POP);
+                    p.on_ground = false;
+                }
+
+                audio::play_clank(x / 10.0);
+                game_state::trigger_shake(0.35);
+                game_state::trigger_hit_freeze(4);
+                game_state::spawn_effect_light(x, y, 0xFFFFFFFF, 2.0, 0.75);
+                particles::spawn_clash_sparks(x, y);
             }
         }
     }
@@ -271,58 +671,242 @@ pub fn update_bullets() {
 
 pub fn update_melee_hits() {
     unsafe {
-        for (attacker_idx, attacker) in PLAYERS.iter().enumerate() {
-            if !attacker.active || attacker.dead || attacker.melee_timer == 0 {
+        resolve_melee_clashes();
+
+        // Decide every hit from the same snapshot so index order can't pick
+        // who wins a trade
+        let mut hits: [Option<usize>; MAX_PLAYERS] = [None; MAX_PLAYERS];
+        for (target_idx, hit) in hits.iter_mut().enumerate() {
+            *hit = (0..MAX_PLAYERS).find(|&a| melee_connects(a, target_idx));
+        }
+
+        for (target_idx, hit) in hits.iter().enumerate() {
+            let Some(attacker_idx) = *hit else {
                 continue;
+            };
+            let target = &WORLD.sim.players[target_idx];
+
+            // Play hit sound for melee hit
+            audio::play_hit();
+            // Screen shake on melee hit
+            game_state::trigger_shake(0.5);
+            // Hit freeze for melee (6 frames ~100ms, slightly longer for up-close hit)
+            game_state::trigger_hit_freeze(6);
+            // Impact flash on hit
+            game_state::trigger_impact_flash();
+            // Camera zoom on kill
+            game_state::trigger_camera_zoom();
+            // Death effect light (victim's color, bright)
+            game_state::spawn_effect_light(
+                target.x + PLAYER_WIDTH / 2.0,
+                target.y + PLAYER_HEIGHT / 2.0,
+                player_color(target_idx),
+                3.0,
+                0.8,
+            );
+            kill_player(target_idx, attacker_idx as u32, KillCause::Melee);
+            if can_pogo(&WORLD.sim.players[attacker_idx]) {
+                pogo(&mut WORLD.sim.players[attacker_idx]);
             }
+        }
+    }
+}
 
-            // Melee hitbox
-            let melee_x = if attacker.facing_right {
-                attacker.x + PLAYER_WIDTH
-            } else {
-                attacker.x - MELEE_RANGE
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform;
+    use crate::player::MELEE_DURATION;
+    use crate::stage::Platform;
+    use crate::world::World;
+
+    fn near(a: Option<f32>, b: f32) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 1e-5)
+    }
+
+    /// Fresh world with one pistol bullet from an absent player 0
+    fn world_with_bullet(x: f32, y: f32, vx: f32, vy: f32) {
+        unsafe {
+            WORLD = World::new();
+            WORLD.sim.bullets[0] = Bullet {
+                x,
+                y,
+                vx,
+                vy,
+                lifetime: Weapon::Pistol.lifetime(),
+                active: true,
+                ..Bullet::new()
             };
-            let melee_y = attacker.y;
-            let melee_w = MELEE_RANGE;
-            let melee_h = PLAYER_HEIGHT;
+        }
+    }
 
-            for (target_idx, target) in PLAYERS.iter().enumerate() {
-                if target_idx == attacker_idx {
-                    continue;
-                }
-                if !target.active || target.dead {
-                    continue;
-                }
-                if target.invuln_timer > 0 {
-                    continue;
-                }
+    #[test]
+    fn segment_aabb_entry_finds_the_first_touch() {
+        let rect = (0.0, 0.0, 1.0, 0.4);
+        assert!(near(segment_aabb_entry((0.5, 1.0), (0.0, -1.2), rect), 0.5));
+        // Clips a corner with both ends outside
+        assert!(near(
+            segment_aabb_entry((-0.3, 0.5), (0.6, -0.6), rect),
+            0.5
+        ));
+        assert_eq!(segment_aabb_entry((0.5, 0.2), (3.0, 0.0), rect), Some(0.0));
+        // Stops short, passes beside, runs parallel outside
+        assert_eq!(segment_aabb_entry((0.5, 1.0), (0.0, -0.5), rect), None);
+        assert_eq!(segment_aabb_entry((1.2, 1.0), (0.0, -2.0), rect), None);
+        assert_eq!(segment_aabb_entry((-1.0, 0.5), (3.0, 0.0), rect), None);
+    }
 
-                // Check if target is hit by melee
-                if aabb_overlap(
-                    (melee_x, melee_y, melee_w, melee_h),
-                    (target.x, target.y, PLAYER_WIDTH, PLAYER_HEIGHT),
-                ) {
-                    // Play hit sound for melee hit
-                    audio::play_hit();
-                    // Screen shake on melee hit
-                    game_state::trigger_shake(0.5);
-                    // Hit freeze for melee (6 frames ~100ms, slightly longer for up-close hit)
-                    game_state::trigger_hit_freeze(6);
-                    // Impact flash on hit
-                    game_state::trigger_impact_flash();
-                    // Camera zoom on kill
-                    game_state::trigger_camera_zoom();
-                    // Death effect light (victim's color, bright)
-                    game_state::spawn_effect_light(
-                        target.x + PLAYER_WIDTH / 2.0,
-                        target.y + PLAYER_HEIGHT / 2.0,
-                        PLAYER_COLORS[target_idx],
-                        3.0,
-                        0.8,
-                    );
-                    kill_player(target_idx, attacker_idx as u32);
-                }
+    #[test]
+    fn segment_circle_entry_finds_the_first_touch() {
+        assert!(near(
+            segment_circle_entry((-2.0, 0.0), (4.0, 0.0), (0.0, 0.0), 1.0),
+            0.25
+        ));
+        assert_eq!(
+            segment_circle_entry((0.5, 0.0), (4.0, 0.0), (0.0, 0.0), 1.0),
+            Some(0.0)
+        );
+        assert_eq!(
+            segment_circle_entry((-2.0, 1.5), (4.0, 0.0), (0.0, 0.0), 1.0),
+            None
+        );
+        assert_eq!(
+            segment_circle_entry((-3.0, 0.0), (1.5, 0.0), (0.0, 0.0), 1.0),
+            None
+        );
+        assert_eq!(
+            segment_circle_entry((-3.0, 0.0), (0.0, 0.0), (0.0, 0.0), 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn bullets_stop_on_platforms_they_would_skip() {
+        let _lock = platform::test_lock();
+        // Starts above a 0.4 thick platform and ends the tick below it
+        world_with_bullet(0.0, 0.8, 0.3, -0.9);
+        unsafe {
+            WORLD.sim.platforms[0] = Platform {
+                x: -1.0,
+                y: 0.0,
+                width: 2.0,
+                height: 0.4,
+                active: true,
+                ..Platform::new()
+            };
+            update_bullets();
+
+            assert!(!WORLD.sim.bullets[0].active);
+            // The spent shell is backed off the top, not left inside
+            let shell = &WORLD.sim.pickups[0];
+            assert!(shell.active);
+            let (cx, cy) = (shell.x + SHELL_SIZE * 0.5, shell.y + SHELL_SIZE * 0.5);
+            let hit = (0.0 + 0.3 * (0.4 / 0.9), 0.4);
+            assert!(cy > 0.4);
+            assert!(near(
+                Some(libm::sqrtf((cx - hit.0).powi(2) + (cy - hit.1).powi(2))),
+                SHELL_SIZE * 0.5
+            ));
+        }
+    }
+
+    #[test]
+    fn deflected_bullets_join_the_deflectors_team() {
+        let _lock = platform::test_lock();
+        // A RED player swings at a BLUE shot coming in from the right
+        let (cx, cy) = (PLAYER_WIDTH / 2.0 + MELEE_RANGE / 2.0, PLAYER_HEIGHT / 2.0);
+        world_with_bullet(cx + MELEE_RANGE + 0.2, cy, -0.4, 0.0);
+        unsafe {
+            WORLD.sim.config.team_mode = true;
+            WORLD.sim.config.teams = [0, 1, 0, 1];
+            WORLD.sim.bullets[0].owner = 1;
+            WORLD.sim.bullets[0].team = 1;
+            let p = &mut WORLD.sim.players[0];
+            p.active = true;
+            p.facing_right = true;
+            p.melee_timer = 8;
+            update_bullets();
+
+            let b = &WORLD.sim.bullets[0];
+            assert!(b.active);
+            assert_eq!((b.owner, b.team), (0, 0));
+            assert!(b.vx > 0.0);
+            assert!(!game_state::can_hurt(b.team, 2));
+            assert!(game_state::can_hurt(b.team, 1));
+        }
+    }
+
+    #[test]
+    fn perfect_parries_meet_the_bullet_on_its_way_in() {
+        let _lock = platform::test_lock();
+        let (cx, cy) = (PLAYER_WIDTH / 2.0 + MELEE_RANGE / 2.0, PLAYER_HEIGHT / 2.0);
+        // Entering the swing on its first live frame, then already inside
+        // the swing when it comes out
+        for (start_x, perfect) in [(cx + MELEE_RANGE + 0.2, 1), (cx + 0.5, 0)] {
+            world_with_bullet(start_x, cy, -0.4, 0.0);
+            unsafe {
+                WORLD.sim.bullets[0].owner = 1;
+                WORLD.sim.bullets[0].team = 1;
+                let p = &mut WORLD.sim.players[0];
+                p.active = true;
+                p.facing_right = true;
+                p.melee_timer = MELEE_DURATION - 1;
+                update_bullets();
+
+                assert_eq!(WORLD.sim.bullets[0].owner, 0);
+                assert_eq!(WORLD.sim.stats.deflects, 1);
+                assert_eq!(WORLD.sim.stats.perfect_parries, perfect);
+            }
+        }
+    }
+
+    #[test]
+    fn lasers_keep_their_pace_through_lined_up_players() {
+        let _lock = platform::test_lock();
+        let speed = Weapon::Laser.speed();
+        world_with_bullet(-0.1, 0.5, speed, 0.0);
+        unsafe {
+            WORLD.sim.bullets[0].kind = Weapon::Laser;
+            for (i, x) in [(1, 0.0), (2, PLAYER_WIDTH + 0.3)] {
+                let p = &mut WORLD.sim.players[i];
+                p.active = true;
+                p.x = x;
+                p.y = 0.0;
             }
+
+            // Each pierce is part of a tick, not the end of one
+            for tick in 1..=10 {
+                update_bullets();
+                let b = &WORLD.sim.bullets[0];
+                assert!(b.active);
+                assert!((b.x - (-0.1 + speed * tick as f32)).abs() < 1e-5);
+            }
+            assert!(WORLD.sim.players[1].dead && WORLD.sim.players[2].dead);
+            assert_eq!(
+                WORLD.sim.stats.deaths_by_cause[KillCause::Laser as usize],
+                2
+            );
+        }
+    }
+
+    #[test]
+    fn bullets_hit_players_they_would_skip() {
+        let _lock = platform::test_lock();
+        // Ends the tick past a player it never overlaps at either end
+        world_with_bullet(-1.0, 0.5, 1.9, 0.0);
+        unsafe {
+            let p = &mut WORLD.sim.players[1];
+            p.active = true;
+            p.x = 0.0;
+            p.y = 0.0;
+            update_bullets();
+
+            assert!(!WORLD.sim.bullets[0].active);
+            assert!(WORLD.sim.players[1].dead);
+            assert_eq!(
+                WORLD.sim.stats.deaths_by_cause[KillCause::Bullet as usize],
+                1
+            );
         }
     }
 }
@@ -2,6 +2,9 @@
 //!
 //! Contains GamePhase enum and GameState struct for match flow control.
 
+use crate::player::MAX_PLAYERS;
+use crate::world::WORLD;
+
 /// Represents the current phase of the game
 #[derive(Clone, Copy, PartialEq)]
 pub enum GamePhase {
@@ -27,6 +30,18 @@ pub enum GamePhase {
 pub const STAGE_SELECT_RANDOM: u32 = NUM_STAGES;
 pub const STAGE_SELECT_ROTATE: u32 = NUM_STAGES + 1;
 
+/// How a match is won
+#[derive(Clone, Copy, PartialEq)]
+pub enum MatchRule {
+    Kills, // First to kills_to_win
+    Stock, // Last player/team with lives left
+    Hill,  // First to hold the capture zone for hill_seconds
+}
+
+impl MatchRule {
+    pub const ALL: [MatchRule; 3] = [MatchRule::Kills, MatchRule::Stock, MatchRule::Hill];
+}
+
 #[derive(Clone, Copy)]
 pub struct GameConfig {
     pub stage_select: u32,
@@ -34,6 +49,13 @@ pub struct GameConfig {
     pub round_time_seconds: u32, // 0 = infinite
     pub fill_bots: bool,
     pub bot_difficulty: u32, // 0=Easy, 1=Normal, 2=Hard
+    pub team_mode: bool,
+    pub friendly_fire: bool,              // Team mode only
+    pub teams: [u32; MAX_PLAYERS],        // Team per slot (team mode only)
+    pub bot_profiles: [u32; MAX_PLAYERS], // Bot profile per slot (CPU slots only)
+    pub rule: MatchRule,
+    pub stocks: u32,       // Lives per player (Stock rule)
+    pub hill_seconds: u32, // Hold time to win (Hill rule)
 }
 
 impl GameConfig {
@@ -44,6 +66,13 @@ impl GameConfig {
             round_time_seconds: 45,
             fill_bots: true,
             bot_difficulty: 1,
+            team_mode: false,
+            friendly_fire: false,
+            teams: [0, 1, 0, 1],
+            bot_profiles: [0; MAX_PLAYERS],
+            rule: MatchRule::Kills,
+            stocks: 3,
+            hill_seconds: 60,
         }
     }
 }
@@ -67,7 +96,6 @@ impl Options {
     }
 }
 
-pub static mut CONFIG: GameConfig = GameConfig::new();
 pub static mut OPTIONS: Options = Options::new();
 
 /// Main game state tracking match progress
@@ -106,23 +134,121 @@ impl GameState {
     }
 }
 
+pub use crate::stage::NUM_STAGES;
+
 // =============================================================================
-// GLOBAL STATE
+// TEAMS
 // =============================================================================
 
-pub static mut GAME_STATE: GameState = GameState::new();
-pub static mut TICK: u32 = 0;
-pub static mut ROUND_NUMBER: u32 = 1;
+pub const NUM_TEAMS: u32 = 2;
+pub const TEAM_NAMES: [&str; NUM_TEAMS as usize] = ["RED", "BLUE"];
+pub const TEAM_COLORS: [u32; NUM_TEAMS as usize] = [
+    0xFF3040FF, // Red
+    0x3070FFFF, // Blue
+];
+
+/// Team a player slot fights for (in free-for-all every slot is its own team)
+pub fn team_of(idx: usize) -> u32 {
+    unsafe {
+        if WORLD.sim.config.team_mode {
+            WORLD.sim.config.teams[idx]
+        } else {
+            idx as u32
+        }
+    }
+}
+
+/// True if two different slots are on the same team
+pub fn are_teammates(a: usize, b: usize) -> bool {
+    a != b && team_of(a) == team_of(b)
+}
+
+/// True if an attack from `attacker_team` can kill `victim_idx`
+pub fn can_hurt(attacker_team: u32, victim_idx: usize) -> bool {
+    unsafe { WORLD.sim.config.friendly_fire || team_of(victim_idx) != attacker_team }
+}
+
+/// A player's own score under the current rule (kills, or hill ticks)
+pub fn player_score(idx: usize) -> u32 {
+    unsafe {
+        let p = &WORLD.sim.players[idx];
+        match WORLD.sim.config.rule {
+            MatchRule::Hill => p.hill_ticks,
+            _ => p.kills,
+        }
+    }
+}
+
+/// Combined score of every active player on `team`
+pub fn team_score(team: u32) -> u32 {
+    unsafe {
+        (0..WORLD.sim.players.len())
+            .filter(|&i| WORLD.sim.players[i].active && team_of(i) == team)
+            .map(player_score)
+            .sum()
+    }
+}
 
-/// Number of stages in the game
-pub const NUM_STAGES: u32 = 3;
+/// Score that counts toward the win (the team total in team mode)
+pub fn match_score(idx: usize) -> u32 {
+    unsafe {
+        if WORLD.sim.config.team_mode {
+            team_score(team_of(idx))
+        } else {
+            player_score(idx)
+        }
+    }
+}
+
+// =============================================================================
+// KING OF THE HILL
+// =============================================================================
 
-/// Title idle counter for attract/demo mode.
-pub static mut TITLE_IDLE_TICKS: u32 = 0;
+/// Hold time needed to win, in ticks
+pub fn hill_target_ticks() -> u32 {
+    unsafe { WORLD.sim.config.hill_seconds.max(1) * 60 }
+}
+
+/// Score the zone: one team inside earns a tick, more than one stalls it
+pub fn update_hill_scoring() {
+    unsafe {
+        if !WORLD.sim.hill.active {
+            return;
+        }
+        let zone = crate::stage::hill_rect();
+        let mut teams_inside = 0u32;
+        let mut first = None;
+        for (i, p) in WORLD.sim.players.iter().enumerate() {
+            if !p.active || p.dead {
+                continue;
+            }
+            if crate::player::aabb_overlap(
+                zone,
+                (
+                    p.x,
+                    p.y,
+                    crate::player::PLAYER_WIDTH,
+                    crate::player::PLAYER_HEIGHT,
+                ),
+            ) {
+                teams_inside |= 1 << team_of(i);
+                first.get_or_insert(i);
+            }
+        }
 
-/// Deflect popup (short UI feedback when someone parries a bullet)
-pub static mut DEFLECT_POPUP_TICKS: u32 = 0;
-pub static mut DEFLECT_PLAYER: u32 = 0;
+        let hill = &mut WORLD.sim.hill;
+        hill.contested = teams_inside.count_ones() > 1;
+        hill.held = teams_inside.count_ones() == 1;
+        if let (true, Some(holder)) = (hill.held, first) {
+            // One tick per tick for the team, credited to the first player inside
+            hill.holder = holder as u32;
+            WORLD.sim.players[holder].hill_ticks += 1;
+            if match_score(holder) >= hill_target_ticks() {
+                crate::player::start_final_ko(holder);
+            }
+        }
+    }
+}
 
 // =============================================================================
 // MENU STATE
@@ -134,60 +260,38 @@ pub enum PausePage {
     Options,
 }
 
-pub static mut PAUSE_PAGE: PausePage = PausePage::Main;
-pub static mut PAUSE_INDEX: u32 = 0;
-pub static mut LOBBY_INDEX: u32 = 0;
-
 pub fn round_time_limit_ticks() -> u32 {
-    unsafe { CONFIG.round_time_seconds.saturating_mul(60) }
+    unsafe { WORLD.sim.config.round_time_seconds.saturating_mul(60) }
 }
 
 // =============================================================================
-// HIT FREEZE STATE
-// =============================================================================
-
-/// Hit freeze countdown (game pauses when > 0)
-pub static mut HIT_FREEZE: u32 = 0;
-
-// =============================================================================
-// HIT FREEZE FUNCTIONS
+// HIT FREEZE
 // =============================================================================
 
 /// Trigger hit freeze for given duration
 pub fn trigger_hit_freeze(frames: u32) {
     unsafe {
         // Don't override a longer freeze
-        if frames > HIT_FREEZE {
-            HIT_FREEZE = frames;
+        if frames > WORLD.sim.hit_freeze {
+            WORLD.sim.hit_freeze = frames;
         }
     }
 }
 
 /// Check if game is in hit freeze
 pub fn is_frozen() -> bool {
-    unsafe { HIT_FREEZE > 0 }
+    unsafe { WORLD.sim.hit_freeze > 0 }
 }
 
 /// Update hit freeze (decrement each frame)
 pub fn update_hit_freeze() {
     unsafe {
-        HIT_FREEZE = HIT_FREEZE.saturating_sub(1);
+        WORLD.sim.hit_freeze = WORLD.sim.hit_freeze.saturating_sub(1);
     }
 }
 
 // =============================================================================
-// SCREEN SHAKE STATE
-// =============================================================================
-
-/// Current shake intensity (0.0 - 1.0)
-pub static mut SCREEN_SHAKE: f32 = 0.0;
-/// Current X offset from shake
-pub static mut SCREEN_SHAKE_X: f32 = 0.0;
-/// Current Y offset from shake
-pub static mut SCREEN_SHAKE_Y: f32 = 0.0;
-
-// =============================================================================
-// SCREEN SHAKE FUNCTIONS
+// SCREEN SHAKE
 // =============================================================================
 
 /// Trigger screen shake with given intensity (0.0 - 1.0)
@@ -196,37 +300,30 @@ pub fn trigger_shake(intensity: f32) {
         if !OPTIONS.screen_shake {
             return;
         }
-        SCREEN_SHAKE = intensity.min(1.0);
+        WORLD.fx.shake = intensity.min(1.0);
     }
 }
 
 /// Update shake state (call each frame during Playing phase)
 pub fn update_shake() {
     unsafe {
-        if SCREEN_SHAKE > 0.01 {
+        if WORLD.fx.shake > 0.01 {
             // Random offset based on intensity
-            let shake_amount = SCREEN_SHAKE * 0.5; // Max 0.5 world units
-            SCREEN_SHAKE_X = (crate::ffi::random_f32() - 0.5) * 2.0 * shake_amount;
-            SCREEN_SHAKE_Y = (crate::ffi::random_f32() - 0.5) * 2.0 * shake_amount;
+            let shake_amount = WORLD.fx.shake * 0.5; // Max 0.5 world units
+            WORLD.fx.shake_x = (crate::rng::fx_f32() - 0.5) * 2.0 * shake_amount;
+            WORLD.fx.shake_y = (crate::rng::fx_f32() - 0.5) * 2.0 * shake_amount;
             // Decay shake over time
-            SCREEN_SHAKE *= 0.85; // Quick falloff
+            WORLD.fx.shake *= 0.85; // Quick falloff
         } else {
-            SCREEN_SHAKE = 0.0;
-            SCREEN_SHAKE_X = 0.0;
-            SCREEN_SHAKE_Y = 0.0;
+            WORLD.fx.shake = 0.0;
+            WORLD.fx.shake_x = 0.0;
+            WORLD.fx.shake_y = 0.0;
         }
     }
 }
 
 // =============================================================================
-// IMPACT FLASH STATE
-// =============================================================================
-
-/// Impact flash countdown (white screen overlay when > 0)
-pub static mut IMPACT_FLASH: u32 = 0;
-
-// =============================================================================
-// IMPACT FLASH FUNCTIONS
+// IMPACT FLASH
 // =============================================================================
 
 /// Trigger impact flash for 3 frames
@@ -235,19 +332,19 @@ pub fn trigger_impact_flash() {
         if !OPTIONS.screen_flash {
             return;
         }
-        IMPACT_FLASH = 3; // 3 frame flash
+        WORLD.fx.impact_flash = 3; // 3 frame flash
     }
 }
 
 /// Update impact flash (decrement each frame)
 pub fn update_impact_flash() {
     unsafe {
-        IMPACT_FLASH = IMPACT_FLASH.saturating_sub(1);
+        WORLD.fx.impact_flash = WORLD.fx.impact_flash.saturating_sub(1);
     }
 }
 
 // =============================================================================
-// CAMERA ZOOM STATE
+// CAMERA ZOOM
 // =============================================================================
 
 /// Default camera FOV
@@ -255,16 +352,11 @@ pub const CAMERA_FOV_DEFAULT: f32 = 50.0;
 /// Zoomed in FOV for kill impact
 pub const CAMERA_FOV_MIN: f32 = 40.0;
 
-/// Current camera FOV
-pub static mut CAMERA_FOV: f32 = CAMERA_FOV_DEFAULT;
-/// Target camera FOV (for smooth interpolation)
-pub static mut CAMERA_FOV_TARGET: f32 = CAMERA_FOV_DEFAULT;
-
 /// Trigger camera zoom for kill impact
 pub fn trigger_camera_zoom() {
     unsafe {
-        CAMERA_FOV_TARGET = CAMERA_FOV_MIN;
-        CAMERA_FOV = CAMERA_FOV_MIN; // Instant zoom on impact
+        WORLD.fx.camera_fov_target = CAMERA_FOV_MIN;
+        WORLD.fx.camera_fov = CAMERA_FOV_MIN; // Instant zoom on impact
     }
 }
 
@@ -273,13 +365,13 @@ pub fn update_camera_fov() {
     unsafe {
         // When not frozen, gradually return FOV to default
         if !is_frozen() {
-            CAMERA_FOV_TARGET = CAMERA_FOV_DEFAULT;
+            WORLD.fx.camera_fov_target = CAMERA_FOV_DEFAULT;
         }
         // Interpolate current FOV toward target
-        CAMERA_FOV += (CAMERA_FOV_TARGET - CAMERA_FOV) * 0.15;
+        WORLD.fx.camera_fov += (WORLD.fx.camera_fov_target - WORLD.fx.camera_fov) * 0.15;
         // Snap to target if close enough
-        if (CAMERA_FOV - CAMERA_FOV_TARGET).abs() < 0.1 {
-            CAMERA_FOV = CAMERA_FOV_TARGET;
+        if (WORLD.fx.camera_fov - WORLD.fx.camera_fov_target).abs() < 0.1 {
+            WORLD.fx.camera_fov = WORLD.fx.camera_fov_target;
         }
     }
 }
@@ -317,14 +409,10 @@ impl EffectLight {
     }
 }
 
-/// Global effect lights pool
-pub static mut EFFECT_LIGHTS: [EffectLight; MAX_EFFECT_LIGHTS] =
-    [EffectLight::new(); MAX_EFFECT_LIGHTS];
-
 /// Spawn an effect light at position with color
 pub fn spawn_effect_light(x: f32, y: f32, color: u32, intensity: f32, decay: f32) {
     unsafe {
-        for light in &mut EFFECT_LIGHTS {
+        for light in &mut WORLD.fx.effect_lights {
             if !light.active {
                 light.active = true;
                 light.x = x;
@@ -342,7 +430,7 @@ pub fn spawn_effect_light(x: f32, y: f32, color: u32, intensity: f32, decay: f32
 /// Update effect lights (decay and deactivate)
 pub fn update_effect_lights() {
     unsafe {
-        for light in &mut EFFECT_LIGHTS {
+        for light in &mut WORLD.fx.effect_lights {
             if light.active {
                 light.intensity *= light.decay;
                 if light.intensity < 0.05 {
@@ -354,41 +442,39 @@ pub fn update_effect_lights() {
 }
 
 // =============================================================================
-// MATCH END ANIMATION STATE
+// MATCH END ANIMATION
 // =============================================================================
 
-/// Tick counter for match end screen animations
-pub static mut MATCH_END_TICK: u32 = 0;
-
 /// Reset match end tick (call when entering MatchEnd phase)
 pub fn reset_match_end_tick() {
     unsafe {
-        MATCH_END_TICK = 0;
+        WORLD.sim.match_end_tick = 0;
     }
 }
 
 /// Increment match end tick
 pub fn update_match_end_tick() {
     unsafe {
-        MATCH_END_TICK += 1;
+        WORLD.sim.match_end_tick += 1;
     }
 }
 
-pub fn register_deflect(player_idx: u32) {
+pub fn register_deflect(player_idx: u32, perfect: bool) {
     unsafe {
-        DEFLECT_PLAYER = player_idx.min(3);
-        DEFLECT_POPUP_TICKS = 45;
+        WORLD.fx.deflect_player = player_idx.min(3);
+        WORLD.fx.deflect_perfect = perfect;
+        WORLD.fx.deflect_popup_ticks = if perfect { 60 } else { 45 };
     }
 }
 
 pub fn update_deflect_popup() {
     unsafe {
-        DEFLECT_POPUP_TICKS = DEFLECT_POPUP_TICKS.saturating_sub(1);
+        WORLD.fx.deflect_popup_ticks = WORLD.fx.deflect_popup_ticks.saturating_sub(1);
     }
 }
 
 // =============================================================================
-// STAGE TRANSITION STATE
+// STAGE TRANSITION
 // =============================================================================
 
 /// Transition state for stage changes
@@ -399,49 +485,45 @@ pub enum TransitionPhase {
     FadeIn,
 }
 
-/// Current transition phase
-pub static mut TRANSITION_PHASE: TransitionPhase = TransitionPhase::None;
-/// Transition progress (0.0 to 1.0)
-pub static mut TRANSITION_PROGRESS: f32 = 0.0;
 /// Transition speed (progress per frame)
 pub const TRANSITION_SPEED: f32 = 0.05;
 
 /// Start a fade-out transition
 pub fn start_transition_out() {
     unsafe {
-        TRANSITION_PHASE = TransitionPhase::FadeOut;
-        TRANSITION_PROGRESS = 0.0;
+        WORLD.fx.transition_phase = TransitionPhase::FadeOut;
+        WORLD.fx.transition_progress = 0.0;
     }
 }
 
 /// Start a fade-in transition
 pub fn start_transition_in() {
     unsafe {
-        TRANSITION_PHASE = TransitionPhase::FadeIn;
-        TRANSITION_PROGRESS = 1.0;
+        WORLD.fx.transition_phase = TransitionPhase::FadeIn;
+        WORLD.fx.transition_progress = 1.0;
     }
 }
 
 /// Update transition state
 pub fn update_transition() -> bool {
     unsafe {
-        match TRANSITION_PHASE {
+        match WORLD.fx.transition_phase {
             TransitionPhase::None => false,
             TransitionPhase::FadeOut => {
-                TRANSITION_PROGRESS += TRANSITION_SPEED;
-                if TRANSITION_PROGRESS >= 1.0 {
-                    TRANSITION_PROGRESS = 1.0;
-                    TRANSITION_PHASE = TransitionPhase::None;
+                WORLD.fx.transition_progress += TRANSITION_SPEED;
+                if WORLD.fx.transition_progress >= 1.0 {
+                    WORLD.fx.transition_progress = 1.0;
+                    WORLD.fx.transition_phase = TransitionPhase::None;
                     true // Fade out complete
                 } else {
                     false
                 }
             }
             TransitionPhase::FadeIn => {
-                TRANSITION_PROGRESS -= TRANSITION_SPEED;
-                if TRANSITION_PROGRESS <= 0.0 {
-                    TRANSITION_PROGRESS = 0.0;
-                    TRANSITION_PHASE = TransitionPhase::None;
+                WORLD.fx.transition_progress -= TRANSITION_SPEED;
+                if WORLD.fx.transition_progress <= 0.0 {
+                    WORLD.fx.transition_progress = 0.0;
+                    WORLD.fx.transition_phase = TransitionPhase::None;
                     true // Fade in complete
                 } else {
                     false
@@ -453,5 +535,83 @@ pub fn update_transition() -> bool {
 
 /// Check if currently transitioning
 pub fn is_transitioning() -> bool {
-    unsafe { TRANSITION_PHASE != TransitionPhase::None }
+    unsafe { WORLD.fx.transition_phase != TransitionPhase::None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform;
+    use crate::player::kill_player;
+    use crate::stats::KillCause;
+    use crate::world::World;
+
+    /// Four active players; in team mode slots alternate RED/BLUE
+    fn four_players(team_mode: bool, friendly_fire: bool) {
+        unsafe {
+            WORLD = World::new();
+            WORLD.sim.config.team_mode = team_mode;
+            WORLD.sim.config.friendly_fire = friendly_fire;
+            WORLD.sim.config.teams = [0, 1, 0, 1];
+            WORLD.sim.game.phase = GamePhase::Playing;
+            for p in &mut WORLD.sim.players {
+                p.active = true;
+            }
+        }
+    }
+
+    #[test]
+    fn free_for_all_has_no_teammates() {
+        let _lock = platform::test_lock();
+        four_players(false, false);
+        for a in 0..4 {
+            assert_eq!(team_of(a), a as u32);
+            for b in 0..4 {
+                assert!(!are_teammates(a, b));
+                assert_eq!(can_hurt(team_of(a), b), a != b);
+            }
+        }
+    }
+
+    #[test]
+    fn friendly_fire_decides_whether_teammates_can_hurt() {
+        let _lock = platform::test_lock();
+        four_players(true, false);
+        assert!(are_teammates(0, 2) && are_teammates(1, 3));
+        assert!(!are_teammates(0, 1) && !are_teammates(2, 2));
+        assert!(!can_hurt(0, 2));
+        assert!(can_hurt(0, 1));
+
+        four_players(true, true);
+        assert!(can_hurt(0, 2));
+        assert!(can_hurt(1, 3));
+    }
+
+    #[test]
+    fn team_kills_score_nothing_and_enemy_kills_are_shared() {
+        let _lock = platform::test_lock();
+        four_players(true, true);
+        unsafe {
+            WORLD.sim.config.kills_to_win = 3;
+
+            kill_player(2, 0, KillCause::Bullet);
+            assert_eq!(WORLD.sim.players[0].kills, 0);
+
+            kill_player(1, 0, KillCause::Bullet);
+            kill_player(3, 2, KillCause::Melee);
+            assert_eq!(
+                (WORLD.sim.players[0].kills, WORLD.sim.players[2].kills),
+                (1, 1)
+            );
+            assert_eq!((match_score(0), match_score(2)), (2, 2));
+            assert_eq!((team_score(0), team_score(1)), (2, 0));
+            assert!(WORLD.sim.game.phase == GamePhase::Playing);
+
+            // The team total reaches the target on a kill by either member
+            WORLD.sim.players[1].dead = false;
+            kill_player(1, 2, KillCause::Bullet);
+            assert!(WORLD.sim.game.phase == GamePhase::FinalKo);
+            assert_eq!(WORLD.sim.game.winner_idx, 2);
+        }
+    }
 }
@@ -0,0 +1,109 @@
+//! Stage hazards, as bots see them
+//!
+//! Everything that kills a player without an attacker: falling past
+//! `DEATH_Y` into the pit, and in overtime the arena walls, which close in
+//! by `OVERTIME_SHRINK_SPEED` a side every tick and kill on contact. Bot
+//! movement checks in `nav` and the bot's own positioning ask here, looking
+//! ahead in time since the walls keep moving; a new hazard only has to be
+//! added to `deadly` (and to the wall-like helpers if it moves players).
+
+use crate::player::{DEATH_Y, PLAYER_WIDTH};
+use crate::world::WORLD;
+use crate::{OVERTIME_MIN_WIDTH, OVERTIME_SHRINK_SPEED};
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Ticks ahead bots plan their distance from the walls
+pub const HAZARD_LOOKAHEAD: u32 = 30;
+/// Room bots keep between themselves and a lethal wall
+const WALL_MARGIN: f32 = 1.0;
+/// Touching a wall counts from this close (positions get clamped to it)
+const WALL_TOUCH: f32 = 0.01;
+
+// =============================================================================
+// QUERIES
+// =============================================================================
+
+/// Ticks from now until the walls start closing (0 once in overtime, `None`
+/// without a round clock)
+fn ticks_to_overtime() -> Option<u32> {
+    unsafe {
+        let g = &WORLD.sim.game;
+        if g.overtime {
+            Some(0)
+        } else if g.round_time_left > 0 {
+            Some(g.round_time_left)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether the arena walls will be lethal `ticks` from now
+pub fn walls_lethal(ticks: u32) -> bool {
+    ticks_to_overtime().is_some_and(|t| t <= ticks)
+}
+
+/// Where the arena walls will be `ticks` from now
+pub fn arena_at(ticks: u32) -> (f32, f32) {
+    unsafe {
+        let g = &WORLD.sim.game;
+        let closing = ticks_to_overtime().map_or(0, |t| ticks.saturating_sub(t));
+        let room = ((g.arena_right - g.arena_left - OVERTIME_MIN_WIDTH) * 0.5).max(0.0);
+        let shrink = (closing as f32 * OVERTIME_SHRINK_SPEED).min(room);
+        (g.arena_left + shrink, g.arena_right - shrink)
+    }
+}
+
+/// Whether a player at (x, y) `ticks` from now is killed by the stage
+pub fn deadly(x: f32, y: f32, ticks: u32) -> bool {
+    if y < DEATH_Y {
+        return true;
+    }
+    if walls_lethal(ticks) {
+        let (left, right) = arena_at(ticks);
+        if x <= left + WALL_TOUCH || x + PLAYER_WIDTH >= right - WALL_TOUCH {
+            return true;
+        }
+    }
+    false
+}
+
+/// Range of x (a player's left side) that stays clear of the walls for the
+/// next `HAZARD_LOOKAHEAD` ticks, keeping `WALL_MARGIN` where there's room
+pub fn safe_band() -> (f32, f32) {
+    let (left, right) = arena_at(HAZARD_LOOKAHEAD);
+    if !walls_lethal(HAZARD_LOOKAHEAD) {
+        return (left, right - PLAYER_WIDTH);
+    }
+    let margin = WALL_MARGIN.min((right - left - PLAYER_WIDTH) * 0.5 - WALL_TOUCH);
+    (left + margin, right - PLAYER_WIDTH - margin)
+}
+
+/// Stick x that gets a player at `x` back inside `safe_band`, or 0 if it's
+/// already inside
+pub fn wall_escape(x: f32) -> f32 {
+    let (lo, hi) = safe_band();
+    if x < lo {
+        1.0
+    } else if x > hi {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+/// Whether holding `input_x` from `x` heads out of `safe_band`
+pub fn toward_wall(x: f32, input_x: f32) -> bool {
+    let (lo, hi) = safe_band();
+    (input_x < 0.0 && x <= lo) || (input_x > 0.0 && x >= hi)
+}
+
+/// A goal's x pulled inside `safe_band` (center x, as goals are)
+pub fn safe_goal_x(x: f32) -> f32 {
+    let (lo, hi) = safe_band();
+    let half = PLAYER_WIDTH * 0.5;
+    x.clamp(lo + half, (hi + half).max(lo + half))
+}
@@ -3,12 +3,16 @@
 //! A 2-4 player one-hit-kill arena game inspired by Towerfall and Samurai Gunn.
 //! Showcases ZX rollback netcode, EPU procedural backgrounds, and matcap rendering.
 
-#![no_std]
-#![no_main]
+#![cfg_attr(target_arch = "wasm32", no_std)]
+#![cfg_attr(target_arch = "wasm32", no_main)]
 #![allow(static_mut_refs)]
+// `const fn new()` is how every pooled type initializes its `static mut`.
+#![allow(clippy::new_without_default)]
 
+#[cfg(target_arch = "wasm32")]
 use core::panic::PanicInfo;
 
+#[cfg(target_arch = "wasm32")]
 #[panic_handler]
 fn panic(_info: &PanicInfo) -> ! {
     core::arch::wasm32::unreachable()
@@ -18,73 +22,91 @@ fn panic(_info: &PanicInfo) -> ! {
 // MODULES
 // =============================================================================
 
-mod audio;
-mod combat;
+pub mod audio;
+pub mod bot_profile;
+pub mod checksum;
+pub mod combat;
+#[cfg(target_arch = "wasm32")]
 mod ffi;
-mod game_state;
-mod particles;
-mod player;
+pub mod game_state;
+pub mod hazard;
+pub mod nav;
+pub mod particles;
+pub mod perception;
+pub mod pickups;
+pub mod platform;
+pub mod player;
+#[cfg(target_arch = "wasm32")]
 mod render;
-mod stage;
-
-use combat::{update_bullets, update_melee_hits, BULLETS};
-use ffi::*;
+pub mod replay;
+pub mod rng;
+pub mod stage;
+pub mod stats;
+pub mod threat;
+pub mod weapons;
+pub mod world;
+
+use bot_profile::NUM_BOT_PROFILES;
+use combat::{update_bullets, update_melee_hits};
 use game_state::{
     is_frozen, round_time_limit_ticks, update_camera_fov, update_deflect_popup,
     update_effect_lights, update_hit_freeze, update_impact_flash, update_match_end_tick,
-    update_shake, update_transition, GamePhase, PausePage, CAMERA_FOV, CONFIG, GAME_STATE,
-    LOBBY_INDEX, OPTIONS, PAUSE_INDEX, PAUSE_PAGE, ROUND_NUMBER, SCREEN_SHAKE_X, SCREEN_SHAKE_Y,
-    STAGE_SELECT_RANDOM, STAGE_SELECT_ROTATE, TICK, TITLE_IDLE_TICKS,
+    update_shake, update_transition, GamePhase, MatchRule, PausePage, OPTIONS, STAGE_SELECT_RANDOM,
+    STAGE_SELECT_ROTATE,
 };
+use platform::{button_pressed, player_count};
 use player::{
     spawn_players, update_player, BUTTON_A, BUTTON_B, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT,
-    BUTTON_START, BUTTON_UP, MAX_PLAYERS, PLAYERS,
+    BUTTON_START, BUTTON_UP, MAX_PLAYERS,
 };
+#[cfg(target_arch = "wasm32")]
 use render::{
-    apply_effect_lights, init_meshes, render_bullets, render_particles, render_players,
-    render_stage, render_ui,
+    apply_effect_lights, init_meshes, render_bullets, render_desync_overlay, render_particles,
+    render_pickups, render_players, render_stage, render_ui,
 };
+use rng::game_range;
 use stage::{setup_current_stage, update_platforms};
+use world::WORLD;
 
 // =============================================================================
 // GAME FLOW
 // =============================================================================
 
 const TITLE_DEMO_DELAY_TICKS: u32 = 60 * 10;
-const ARENA_LEFT_DEFAULT: f32 = -10.0;
-const ARENA_RIGHT_DEFAULT: f32 = 10.0;
 const OVERTIME_SHRINK_SPEED: f32 = 0.03; // world units/frame per side
 const OVERTIME_MIN_WIDTH: f32 = 2.5; // when reached, someone is getting crushed
+const LOBBY_SETTING_ROWS: u32 = 8; // Match settings rows (see render_lobby)
+/// Settings, then a bot profile row for each slot that can hold a CPU (P2-P4)
+const LOBBY_ROWS: u32 = LOBBY_SETTING_ROWS + MAX_PLAYERS as u32 - 1;
 
 fn any_input_pressed() -> bool {
-    unsafe {
-        for i in 0..player_count() {
-            if button_pressed(i, BUTTON_A) != 0
-                || button_pressed(i, BUTTON_B) != 0
-                || button_pressed(i, player::BUTTON_X) != 0
-                || button_pressed(i, BUTTON_START) != 0
-                || button_pressed(i, BUTTON_UP) != 0
-                || button_pressed(i, BUTTON_DOWN) != 0
-                || button_pressed(i, BUTTON_LEFT) != 0
-                || button_pressed(i, BUTTON_RIGHT) != 0
-            {
-                return true;
-            }
+    for i in 0..player_count() {
+        if button_pressed(i, BUTTON_A)
+            || button_pressed(i, BUTTON_B)
+            || button_pressed(i, player::BUTTON_X)
+            || button_pressed(i, BUTTON_START)
+            || button_pressed(i, BUTTON_UP)
+            || button_pressed(i, BUTTON_DOWN)
+            || button_pressed(i, BUTTON_LEFT)
+            || button_pressed(i, BUTTON_RIGHT)
+        {
+            return true;
         }
-        false
     }
+    false
 }
 
 fn enter_title() {
     unsafe {
-        GAME_STATE.phase = GamePhase::Title;
-        GAME_STATE.demo_mode = false;
-        TITLE_IDLE_TICKS = 0;
-        game_state::TRANSITION_PHASE = game_state::TransitionPhase::None;
-        game_state::TRANSITION_PROGRESS = 0.0;
+        replay::stop();
+        WORLD.sim.game.phase = GamePhase::Title;
+        WORLD.sim.game.demo_mode = false;
+        WORLD.sim.title_idle_ticks = 0;
+        WORLD.fx.transition_phase = game_state::TransitionPhase::None;
+        WORLD.fx.transition_progress = 0.0;
 
         // Reset lobby state
-        for p in &mut PLAYERS {
+        for p in &mut WORLD.sim.players {
             *p = player::Player::new();
         }
 
@@ -94,15 +116,16 @@ fn enter_title() {
 
 fn enter_lobby() {
     unsafe {
-        GAME_STATE.phase = GamePhase::Lobby;
-        GAME_STATE.demo_mode = false;
-        TITLE_IDLE_TICKS = 0;
-        LOBBY_INDEX = 0;
-        game_state::TRANSITION_PHASE = game_state::TransitionPhase::None;
-        game_state::TRANSITION_PROGRESS = 0.0;
+        replay::stop();
+        WORLD.sim.game.phase = GamePhase::Lobby;
+        WORLD.sim.game.demo_mode = false;
+        WORLD.sim.title_idle_ticks = 0;
+        WORLD.sim.lobby_index = 0;
+        WORLD.fx.transition_phase = game_state::TransitionPhase::None;
+        WORLD.fx.transition_progress = 0.0;
 
         // Clear join/ready; keep config/options.
-        for p in &mut PLAYERS {
+        for p in &mut WORLD.sim.players {
             *p = player::Player::new();
         }
     }
@@ -110,31 +133,34 @@ fn enter_lobby() {
 
 fn apply_round_defaults() {
     unsafe {
-        GAME_STATE.overtime = false;
-        GAME_STATE.arena_left = ARENA_LEFT_DEFAULT;
-        GAME_STATE.arena_right = ARENA_RIGHT_DEFAULT;
-        GAME_STATE.round_time_left = round_time_limit_ticks();
+        WORLD.sim.game.overtime = false;
+        let def = stage::current_stage_def();
+        WORLD.sim.game.arena_left = def.arena_left;
+        WORLD.sim.game.arena_right = def.arena_right;
+        WORLD.sim.game.round_time_left = round_time_limit_ticks();
     }
 }
 
 fn pick_stage_for_new_round() {
     unsafe {
-        let sel = CONFIG.stage_select;
+        let sel = WORLD.sim.config.stage_select;
         if sel == STAGE_SELECT_ROTATE {
-            GAME_STATE.current_stage = (GAME_STATE.current_stage + 1) % game_state::NUM_STAGES;
+            WORLD.sim.game.current_stage =
+                (WORLD.sim.game.current_stage + 1) % game_state::NUM_STAGES;
         } else if sel == STAGE_SELECT_RANDOM {
-            GAME_STATE.current_stage = random_range(0, game_state::NUM_STAGES as i32).max(0) as u32;
+            WORLD.sim.game.current_stage =
+                game_range(0, game_state::NUM_STAGES as i32).max(0) as u32;
         }
     }
 }
 
 fn set_start_stage_for_match() {
     unsafe {
-        let sel = CONFIG.stage_select;
-        GAME_STATE.current_stage = if sel < game_state::NUM_STAGES {
+        let sel = WORLD.sim.config.stage_select;
+        WORLD.sim.game.current_stage = if sel < game_state::NUM_STAGES {
             sel
         } else if sel == STAGE_SELECT_RANDOM {
-            random_range(0, game_state::NUM_STAGES as i32).max(0) as u32
+            game_range(0, game_state::NUM_STAGES as i32).max(0) as u32
         } else {
             0 // rotate
         };
@@ -144,19 +170,25 @@ fn set_start_stage_for_match() {
 fn reset_round() {
     unsafe {
         // Clear bullets
-        for b in &mut BULLETS {
+        for b in &mut WORLD.sim.bullets {
             b.active = false;
         }
 
-        // Clear particles
+        // Clear particles and spent shells
         particles::clear_particles();
+        pickups::clear_pickups();
+
+        // Bots start the round with fresh eyes
+        perception::clear_perception();
 
         // Setup stage and spawn players
         setup_current_stage();
+        stage::setup_hill();
+        weapons::setup_crates();
         spawn_players();
 
         // Start music for the current stage
-        audio::play_music_for_stage(GAME_STATE.current_stage);
+        audio::play_music_for_stage(stage::current_stage_def().music);
 
         apply_round_defaults();
 
@@ -164,20 +196,28 @@ fn reset_round() {
         game_state::start_transition_in();
 
         // Start countdown
-        GAME_STATE.phase = GamePhase::Countdown;
-        GAME_STATE.countdown = 180; // 3 seconds
+        WORLD.sim.game.phase = GamePhase::Countdown;
+        WORLD.sim.game.countdown = 180; // 3 seconds
     }
 }
 
 fn reset_match() {
     unsafe {
-        // Reset all kills
-        for p in &mut PLAYERS {
+        // Capture setup + seed before the match consumes any randomness
+        replay::begin_recording();
+
+        // Reset all kills and stocks
+        for p in &mut WORLD.sim.players {
             p.kills = 0;
+            p.stocks = WORLD.sim.config.stocks.max(1);
+            p.eliminated = false;
+            p.hill_ticks = 0;
         }
+        WORLD.sim.hill.active = false; // Re-placed by the first reset_round
 
         set_start_stage_for_match();
-        ROUND_NUMBER = 1;
+        stats::reset_stats();
+        WORLD.sim.round_number = 1;
         reset_round();
     }
 }
@@ -185,56 +225,46 @@ fn reset_match() {
 fn start_match(demo_mode: bool) {
     unsafe {
         audio::stop_music();
-        GAME_STATE.demo_mode = demo_mode;
+        WORLD.sim.game.demo_mode = demo_mode;
 
         // Activate players (humans that are ready), and fill with bots if enabled.
         let connected = player_count().min(MAX_PLAYERS as u32) as usize;
 
         // Ensure connected slots are humans (even if inactive).
-        for i in 0..MAX_PLAYERS {
-            PLAYERS[i].is_bot = false;
+        for (i, p) in WORLD.sim.players.iter_mut().enumerate() {
+            p.is_bot = false;
             if i >= connected {
-                PLAYERS[i].ready = false;
+                p.ready = false;
             }
         }
 
         // If demo: force 4 bots.
         if demo_mode {
-            for i in 0..MAX_PLAYERS {
-                PLAYERS[i].active = true;
-                PLAYERS[i].ready = true;
-                PLAYERS[i].is_bot = true;
+            for p in &mut WORLD.sim.players {
+                p.active = true;
+                p.ready = true;
+                p.is_bot = true;
             }
         } else {
             // If nobody is ready but someone hit START, auto-ready P1.
-            let mut any_ready = false;
-            for i in 0..connected {
-                if PLAYERS[i].ready {
-                    any_ready = true;
-                    break;
-                }
-            }
+            let any_ready = WORLD.sim.players.iter().take(connected).any(|p| p.ready);
             if !any_ready && connected > 0 {
-                PLAYERS[0].ready = true;
-                PLAYERS[0].active = true;
+                WORLD.sim.players[0].ready = true;
+                WORLD.sim.players[0].active = true;
             }
 
             // Disable non-ready humans.
-            for i in 0..MAX_PLAYERS {
-                if i < connected {
-                    PLAYERS[i].active = PLAYERS[i].ready;
-                } else {
-                    PLAYERS[i].active = false;
-                }
+            for (i, p) in WORLD.sim.players.iter_mut().enumerate() {
+                p.active = i < connected && p.ready;
             }
 
             // Fill remaining empty seats with bots (only for non-connected slots).
-            if CONFIG.fill_bots {
-                for i in connected..MAX_PLAYERS {
-                    if !PLAYERS[i].active {
-                        PLAYERS[i].active = true;
-                        PLAYERS[i].ready = true;
-                        PLAYERS[i].is_bot = true;
+            if WORLD.sim.config.fill_bots {
+                for p in WORLD.sim.players.iter_mut().skip(connected) {
+                    if !p.active {
+                        p.active = true;
+                        p.ready = true;
+                        p.is_bot = true;
                     }
                 }
             }
@@ -242,7 +272,7 @@ fn start_match(demo_mode: bool) {
 
         // Need at least 2 participants.
         let mut participants = 0;
-        for p in &PLAYERS {
+        for p in &WORLD.sim.players {
             if p.active {
                 participants += 1;
             }
@@ -251,24 +281,153 @@ fn start_match(demo_mode: bool) {
             return;
         }
 
-        reset_match();
+        // Team mode needs someone on each side
+        assign_bot_teams();
+        if WORLD.sim.config.team_mode && !both_teams_filled() {
+            return;
+        }
+
+        begin_match();
+    }
+}
+
+/// Put each bot on whichever team is short-handed (humans pick in the lobby)
+fn assign_bot_teams() {
+    unsafe {
+        let mut counts = [0u32; game_state::NUM_TEAMS as usize];
+        for (i, p) in WORLD.sim.players.iter().enumerate() {
+            if p.active && !p.is_bot {
+                counts[WORLD.sim.config.teams[i] as usize] += 1;
+            }
+        }
+        for (i, p) in WORLD.sim.players.iter().enumerate() {
+            if p.active && p.is_bot {
+                let team = if counts[1] < counts[0] { 1 } else { 0 };
+                WORLD.sim.config.teams[i] = team;
+                counts[team as usize] += 1;
+            }
+        }
+    }
+}
+
+/// True if every team has at least one active player
+fn both_teams_filled() -> bool {
+    unsafe {
+        (0..game_state::NUM_TEAMS).all(|team| {
+            WORLD
+                .sim
+                .players
+                .iter()
+                .enumerate()
+                .any(|(i, p)| p.active && WORLD.sim.config.teams[i] == team)
+        })
+    }
+}
+
+/// Player slot a lobby row picks the bot profile for (None for settings rows)
+fn lobby_row_slot(row: u32) -> Option<usize> {
+    if (LOBBY_SETTING_ROWS..LOBBY_ROWS).contains(&row) {
+        Some((row - LOBBY_SETTING_ROWS) as usize + 1)
+    } else {
+        None
+    }
+}
+
+/// False for profile rows of slots that won't get a CPU (humans, or no fill)
+fn lobby_row_enabled(row: u32, connected: usize) -> bool {
+    unsafe {
+        lobby_row_slot(row).is_none_or(|slot| slot >= connected && WORLD.sim.config.fill_bots)
     }
 }
 
+/// Move the lobby cursor by `step` rows (mod LOBBY_ROWS), skipping disabled
+/// profile rows
+fn step_lobby_row(step: u32, connected: usize) {
+    unsafe {
+        let mut row = WORLD.sim.lobby_index;
+        loop {
+            row = (row + step) % LOBBY_ROWS;
+            if lobby_row_enabled(row, connected) {
+                break;
+            }
+        }
+        WORLD.sim.lobby_index = row;
+    }
+}
+
+/// Cycle the bot profile on a profile row by `step` (mod NUM_BOT_PROFILES)
+fn cycle_bot_profile(row: u32, step: u32) {
+    unsafe {
+        if let Some(slot) = lobby_row_slot(row) {
+            let profile = &mut WORLD.sim.config.bot_profiles[slot];
+            *profile = (*profile + step) % NUM_BOT_PROFILES;
+        }
+    }
+}
+
+/// Seed the gameplay RNG and start round one with the current participants,
+/// recording the match as a replay
+fn begin_match() {
+    // Every peer draws the same seed from the runtime RNG
+    rng::seed_gameplay(platform::random_range(1, i32::MAX) as u32);
+
+    replay::arm_recording();
+    reset_match();
+}
+
+/// Play the match again with the same participants, recording it as well
+fn restart_match() {
+    if !replay::is_playing() {
+        replay::arm_recording();
+    }
+    reset_match();
+}
+
+/// Start a CPU-only match in the first `bots` slots with the current config
+/// (headless tools; there is no lobby involved)
+pub fn start_bot_match(bots: usize) {
+    unsafe {
+        audio::stop_music();
+        WORLD.sim.game.demo_mode = false;
+        for (i, p) in WORLD.sim.players.iter_mut().enumerate() {
+            *p = player::Player::new();
+            p.active = i < bots;
+            p.ready = p.active;
+            p.is_bot = p.active;
+        }
+    }
+
+    assign_bot_teams();
+    begin_match();
+}
+
 fn update_overtime() {
     unsafe {
-        if !GAME_STATE.overtime {
+        if !WORLD.sim.game.overtime {
             return;
         }
 
-        let width = GAME_STATE.arena_right - GAME_STATE.arena_left;
+        let width = WORLD.sim.game.arena_right - WORLD.sim.game.arena_left;
         if width > OVERTIME_MIN_WIDTH {
-            GAME_STATE.arena_left += OVERTIME_SHRINK_SPEED;
-            GAME_STATE.arena_right -= OVERTIME_SHRINK_SPEED;
+            WORLD.sim.game.arena_left += OVERTIME_SHRINK_SPEED;
+            WORLD.sim.game.arena_right -= OVERTIME_SHRINK_SPEED;
         }
     }
 }
 
+/// Start watching a recorded match; it plays out through `update()`
+pub fn play_replay(data: &[u8]) -> Result<(), replay::ReplayError> {
+    let header = replay::start_playback(data)?;
+    audio::stop_music();
+    // Desyncs found from here on are the replay's own
+    unsafe {
+        checksum::DESYNC.clear();
+    }
+    header.apply();
+    reset_match();
+    Ok(())
+}
+
 // =============================================================================
 // ENTRY POINTS
 // =============================================================================
@@ -276,11 +435,17 @@ fn update_overtime() {
 #[no_mangle]
 pub extern "C" fn init() {
     unsafe {
-        // Dark background
-        set_clear_color(0x0a0a1aff);
+        #[cfg(target_arch = "wasm32")]
+        {
+            // Dark background
+            ffi::set_clear_color(0x0a0a1aff);
 
-        // Create mesh handles for 3D rendering
-        init_meshes();
+            // Create mesh handles for 3D rendering
+            init_meshes();
+        }
+
+        // Bot navigation graphs for every stage
+        nav::build_nav_graphs();
 
         // Initialize audio system
         audio::init_audio();
@@ -294,136 +459,216 @@ pub extern "C" fn init() {
 
 #[no_mangle]
 pub extern "C" fn update() {
+    if replay::playback_holds_tick() {
+        // Recorded player had the game paused: only time moves
+        unsafe {
+            WORLD.sim.tick += 1;
+        }
+    } else {
+        step();
+    }
+
+    // Hash the finished tick so peers/replays can detect desyncs
+    checksum::record_tick();
+    replay::end_tick();
+}
+
+/// Advance the simulation by one tick
+fn step() {
     unsafe {
-        TICK += 1;
+        WORLD.sim.tick += 1;
 
         if game_state::is_transitioning() {
             let _ = update_transition();
         }
 
-        match GAME_STATE.phase {
+        match WORLD.sim.game.phase {
             GamePhase::Title => {
                 if any_input_pressed() {
-                    TITLE_IDLE_TICKS = 0;
+                    WORLD.sim.title_idle_ticks = 0;
                 } else {
-                    TITLE_IDLE_TICKS += 1;
+                    WORLD.sim.title_idle_ticks += 1;
                 }
 
                 // A/START -> lobby
                 for i in 0..player_count() {
-                    if button_pressed(i, BUTTON_A) != 0 || button_pressed(i, BUTTON_START) != 0 {
+                    if button_pressed(i, BUTTON_A) || button_pressed(i, BUTTON_START) {
                         enter_lobby();
                         return;
                     }
                 }
 
                 // Attract mode demo
-                if TITLE_IDLE_TICKS > TITLE_DEMO_DELAY_TICKS {
+                if WORLD.sim.title_idle_ticks > TITLE_DEMO_DELAY_TICKS {
                     start_match(true);
-                    return;
                 }
             }
 
             GamePhase::Lobby => {
                 // Back to title
-                if player_count() > 0 && button_pressed(0, BUTTON_B) != 0 {
+                if player_count() > 0 && button_pressed(0, BUTTON_B) {
                     enter_title();
                     return;
                 }
 
                 // Join/ready toggles for connected players
                 let connected = player_count().min(MAX_PLAYERS as u32) as usize;
-                for i in 0..connected {
-                    if button_pressed(i as u32, BUTTON_A) != 0 {
-                        let p = &mut PLAYERS[i];
+                for (i, p) in WORLD.sim.players.iter_mut().enumerate().take(connected) {
+                    if button_pressed(i as u32, BUTTON_A) {
                         p.ready = !p.ready;
                         p.active = p.ready;
                         p.is_bot = false;
                     }
+                    // X switches team (team mode only)
+                    if WORLD.sim.config.team_mode && button_pressed(i as u32, player::BUTTON_X) {
+                        let team = &mut WORLD.sim.config.teams[i];
+                        *team = (*team + 1) % game_state::NUM_TEAMS;
+                    }
                 }
                 // Clear non-connected slots
-                for i in connected..MAX_PLAYERS {
-                    PLAYERS[i].ready = false;
-                    PLAYERS[i].active = false;
-                    PLAYERS[i].is_bot = false;
+                for p in WORLD.sim.players.iter_mut().skip(connected) {
+                    p.ready = false;
+                    p.active = false;
+                    p.is_bot = false;
                 }
 
                 // Settings navigation (P1)
                 if connected > 0 {
-                    if button_pressed(0, BUTTON_UP) != 0 {
-                        LOBBY_INDEX = (LOBBY_INDEX + 5 - 1) % 5;
-                    } else if button_pressed(0, BUTTON_DOWN) != 0 {
-                        LOBBY_INDEX = (LOBBY_INDEX + 1) % 5;
+                    if button_pressed(0, BUTTON_UP) {
+                        step_lobby_row(LOBBY_ROWS - 1, connected);
+                    } else if button_pressed(0, BUTTON_DOWN) {
+                        step_lobby_row(1, connected);
+                    }
+                    // A human joining (or FILL CPU going off) can disable the row
+                    if !lobby_row_enabled(WORLD.sim.lobby_index, connected) {
+                        WORLD.sim.lobby_index = 0;
                     }
 
-                    if button_pressed(0, BUTTON_LEFT) != 0 {
-                        match LOBBY_INDEX {
+                    if button_pressed(0, BUTTON_LEFT) {
+                        match WORLD.sim.lobby_index {
                             0 => {
                                 // Stage select
-                                if CONFIG.stage_select == 0 {
-                                    CONFIG.stage_select = STAGE_SELECT_ROTATE;
+                                if WORLD.sim.config.stage_select == 0 {
+                                    WORLD.sim.config.stage_select = STAGE_SELECT_ROTATE;
                                 } else {
-                                    CONFIG.stage_select -= 1;
+                                    WORLD.sim.config.stage_select -= 1;
                                 }
                             }
                             1 => {
+                                // Rule
+                                let rules = MatchRule::ALL;
+                                let i = WORLD.sim.config.rule as usize;
+                                WORLD.sim.config.rule = rules[(i + rules.len() - 1) % rules.len()];
+                            }
+                            2 if WORLD.sim.config.rule == MatchRule::Stock => {
+                                // Lives
+                                WORLD.sim.config.stocks = match WORLD.sim.config.stocks {
+                                    5 => 3,
+                                    3 => 1,
+                                    _ => 5,
+                                };
+                            }
+                            2 if WORLD.sim.config.rule == MatchRule::Hill => {
+                                // Hold time
+                                WORLD.sim.config.hill_seconds = match WORLD.sim.config.hill_seconds
+                                {
+                                    90 => 60,
+                                    60 => 30,
+                                    _ => 90,
+                                };
+                            }
+                            2 => {
                                 // Kills
-                                CONFIG.kills_to_win = match CONFIG.kills_to_win {
+                                WORLD.sim.config.kills_to_win = match WORLD.sim.config.kills_to_win
+                                {
                                     7 => 5,
                                     5 => 3,
                                     _ => 7,
                                 };
                             }
-                            2 => {
+                            3 => {
                                 // Time
-                                CONFIG.round_time_seconds = match CONFIG.round_time_seconds {
-                                    0 => 90,
-                                    30 => 0,
-                                    45 => 30,
-                                    60 => 45,
-                                    90 => 60,
-                                    _ => 45,
-                                };
+                                WORLD.sim.config.round_time_seconds =
+                                    match WORLD.sim.config.round_time_seconds {
+                                        0 => 90,
+                                        30 => 0,
+                                        45 => 30,
+                                        60 => 45,
+                                        90 => 60,
+                                        _ => 45,
+                                    };
                             }
-                            3 => CONFIG.fill_bots = !CONFIG.fill_bots,
-                            4 => {
-                                if CONFIG.bot_difficulty == 0 {
-                                    CONFIG.bot_difficulty = 2;
+                            4 => WORLD.sim.config.fill_bots = !WORLD.sim.config.fill_bots,
+                            5 => {
+                                if WORLD.sim.config.bot_difficulty == 0 {
+                                    WORLD.sim.config.bot_difficulty = 2;
                                 } else {
-                                    CONFIG.bot_difficulty -= 1;
+                                    WORLD.sim.config.bot_difficulty -= 1;
                                 }
                             }
-                            _ => {}
+                            6 => WORLD.sim.config.team_mode = !WORLD.sim.config.team_mode,
+                            7 => WORLD.sim.config.friendly_fire = !WORLD.sim.config.friendly_fire,
+                            row => cycle_bot_profile(row, NUM_BOT_PROFILES - 1),
                         }
-                    } else if button_pressed(0, BUTTON_RIGHT) != 0 {
-                        match LOBBY_INDEX {
+                    } else if button_pressed(0, BUTTON_RIGHT) {
+                        match WORLD.sim.lobby_index {
                             0 => {
                                 // Stage select
-                                CONFIG.stage_select =
-                                    (CONFIG.stage_select + 1) % (STAGE_SELECT_ROTATE + 1);
+                                WORLD.sim.config.stage_select =
+                                    (WORLD.sim.config.stage_select + 1) % (STAGE_SELECT_ROTATE + 1);
                             }
                             1 => {
+                                // Rule
+                                let rules = MatchRule::ALL;
+                                let i = WORLD.sim.config.rule as usize;
+                                WORLD.sim.config.rule = rules[(i + 1) % rules.len()];
+                            }
+                            2 if WORLD.sim.config.rule == MatchRule::Stock => {
+                                // Lives
+                                WORLD.sim.config.stocks = match WORLD.sim.config.stocks {
+                                    1 => 3,
+                                    3 => 5,
+                                    _ => 1,
+                                };
+                            }
+                            2 if WORLD.sim.config.rule == MatchRule::Hill => {
+                                // Hold time
+                                WORLD.sim.config.hill_seconds = match WORLD.sim.config.hill_seconds
+                                {
+                                    30 => 60,
+                                    60 => 90,
+                                    _ => 30,
+                                };
+                            }
+                            2 => {
                                 // Kills
-                                CONFIG.kills_to_win = match CONFIG.kills_to_win {
+                                WORLD.sim.config.kills_to_win = match WORLD.sim.config.kills_to_win
+                                {
                                     3 => 5,
                                     5 => 7,
                                     _ => 3,
                                 };
                             }
-                            2 => {
+                            3 => {
                                 // Time
-                                CONFIG.round_time_seconds = match CONFIG.round_time_seconds {
-                                    0 => 30,
-                                    30 => 45,
-                                    45 => 60,
-                                    60 => 90,
-                                    90 => 0,
-                                    _ => 45,
-                                };
+                                WORLD.sim.config.round_time_seconds =
+                                    match WORLD.sim.config.round_time_seconds {
+                                        0 => 30,
+                                        30 => 45,
+                                        45 => 60,
+                                        60 => 90,
+                                        90 => 0,
+                                        _ => 45,
+                                    };
                             }
-                            3 => CONFIG.fill_bots = !CONFIG.fill_bots,
-                            4 => CONFIG.bot_difficulty = (CONFIG.bot_difficulty + 1) % 3,
-                            _ => {}
+                            4 => WORLD.sim.config.fill_bots = !WORLD.sim.config.fill_bots,
+                            5 => {
+                                WORLD.sim.config.bot_difficulty =
+                                    (WORLD.sim.config.bot_difficulty + 1) % 3
+                            }
+                            6 => WORLD.sim.config.team_mode = !WORLD.sim.config.team_mode,
+                            7 => WORLD.sim.config.friendly_fire = !WORLD.sim.config.friendly_fire,
+                            row => cycle_bot_profile(row, 1),
                         }
                     }
                 }
@@ -431,60 +676,63 @@ pub extern "C" fn update() {
                 // Start match
                 let mut start_pressed = false;
                 for i in 0..player_count() {
-                    if button_pressed(i, BUTTON_START) != 0 {
+                    if button_pressed(i, BUTTON_START) {
                         start_pressed = true;
                         break;
                     }
                 }
                 if start_pressed {
                     start_match(false);
-                    return;
                 }
             }
 
             GamePhase::Countdown => {
-                // Pause
+                // Pause (not while watching a replay)
                 for i in 0..player_count() {
-                    if button_pressed(i, BUTTON_START) != 0 {
-                        GAME_STATE.paused_from = GamePhase::Countdown;
-                        GAME_STATE.phase = GamePhase::Paused;
-                        PAUSE_PAGE = PausePage::Main;
-                        PAUSE_INDEX = 0;
+                    if !replay::is_playing() && button_pressed(i, BUTTON_START) {
+                        WORLD.sim.game.paused_from = GamePhase::Countdown;
+                        WORLD.sim.game.phase = GamePhase::Paused;
+                        WORLD.sim.pause_page = PausePage::Main;
+                        WORLD.sim.pause_index = 0;
                         return;
                     }
                 }
 
-                if GAME_STATE.countdown > 0 {
+                if WORLD.sim.game.countdown > 0 {
                     // Play countdown beep at each second (180=3, 120=2, 60=1)
-                    if GAME_STATE.countdown == 180
-                        || GAME_STATE.countdown == 120
-                        || GAME_STATE.countdown == 60
+                    if WORLD.sim.game.countdown == 180
+                        || WORLD.sim.game.countdown == 120
+                        || WORLD.sim.game.countdown == 60
                     {
                         audio::play_countdown();
                     }
-                    GAME_STATE.countdown -= 1;
+                    WORLD.sim.game.countdown -= 1;
                 } else {
                     // Play GO sound when countdown ends
                     audio::play_go();
-                    GAME_STATE.phase = GamePhase::Playing;
+                    WORLD.sim.game.phase = GamePhase::Playing;
                 }
             }
 
             GamePhase::Playing => {
                 // Demo: any input exits back to lobby.
-                if GAME_STATE.demo_mode && any_input_pressed() {
+                if WORLD.sim.game.demo_mode && any_input_pressed() {
                     audio::stop_music();
                     enter_lobby();
                     return;
                 }
 
-                // Pause (only humans)
-                for (i, p) in PLAYERS.iter().enumerate() {
-                    if p.active && !p.is_bot && button_pressed(i as u32, BUTTON_START) != 0 {
-                        GAME_STATE.paused_from = GamePhase::Playing;
-                        GAME_STATE.phase = GamePhase::Paused;
-                        PAUSE_PAGE = PausePage::Main;
-                        PAUSE_INDEX = 0;
+                // Pause (only humans, not while watching a replay)
+                for (i, p) in WORLD.sim.players.iter().enumerate() {
+                    if p.active
+                        && !p.is_bot
+                        && !replay::is_playing()
+                        && button_pressed(i as u32, BUTTON_START)
+                    {
+                        WORLD.sim.game.paused_from = GamePhase::Playing;
+                        WORLD.sim.game.phase = GamePhase::Paused;
+                        WORLD.sim.pause_page = PausePage::Main;
+                        WORLD.sim.pause_index = 0;
                         return;
                     }
                 }
@@ -506,10 +754,10 @@ pub extern "C" fn update() {
                 // Only update game logic if not frozen
                 if !is_frozen() {
                     // Round timer / overtime
-                    if !GAME_STATE.overtime && GAME_STATE.round_time_left > 0 {
-                        GAME_STATE.round_time_left -= 1;
-                        if GAME_STATE.round_time_left == 0 {
-                            GAME_STATE.overtime = true;
+                    if !WORLD.sim.game.overtime && WORLD.sim.game.round_time_left > 0 {
+                        WORLD.sim.game.round_time_left -= 1;
+                        if WORLD.sim.game.round_time_left == 0 {
+                            WORLD.sim.game.overtime = true;
                             // Audible cue using existing countdown beep.
                             audio::play_countdown();
                             // Small shake to sell the transition.
@@ -520,25 +768,30 @@ pub extern "C" fn update() {
                     update_overtime();
 
                     update_platforms();
+                    stage::update_hill();
 
+                    perception::record_perception();
                     for i in 0..MAX_PLAYERS {
                         update_player(i);
                     }
 
                     update_bullets();
                     update_melee_hits();
+                    pickups::update_pickups();
+                    weapons::update_crates();
+                    game_state::update_hill_scoring();
 
                     // Update particles
                     particles::update_particles();
 
                     // Handle round end timer (brief pause after kill)
-                    if GAME_STATE.round_end_timer > 0 {
-                        GAME_STATE.round_end_timer -= 1;
+                    if WORLD.sim.game.round_end_timer > 0 {
+                        WORLD.sim.game.round_end_timer -= 1;
 
                         // When timer hits 0 after a kill, rotate stage (if configured) and reset round
-                        if GAME_STATE.round_end_timer == 0 {
+                        if WORLD.sim.game.round_end_timer == 0 {
                             pick_stage_for_new_round();
-                            ROUND_NUMBER += 1;
+                            WORLD.sim.round_number += 1;
                             reset_round();
                         }
                     }
@@ -554,35 +807,37 @@ pub extern "C" fn update() {
                     return;
                 }
 
-                let up = button_pressed(0, BUTTON_UP) != 0;
-                let down = button_pressed(0, BUTTON_DOWN) != 0;
-                let left = button_pressed(0, BUTTON_LEFT) != 0;
-                let right = button_pressed(0, BUTTON_RIGHT) != 0;
-                let confirm = button_pressed(0, BUTTON_A) != 0;
-                let back = button_pressed(0, BUTTON_B) != 0 || button_pressed(0, BUTTON_START) != 0;
+                let up = button_pressed(0, BUTTON_UP);
+                let down = button_pressed(0, BUTTON_DOWN);
+                let left = button_pressed(0, BUTTON_LEFT);
+                let right = button_pressed(0, BUTTON_RIGHT);
+                let confirm = button_pressed(0, BUTTON_A);
+                let back = button_pressed(0, BUTTON_B) || button_pressed(0, BUTTON_START);
 
-                match PAUSE_PAGE {
+                match WORLD.sim.pause_page {
                     PausePage::Main => {
                         const MAIN_ITEMS: u32 = 5;
                         if up {
-                            PAUSE_INDEX = (PAUSE_INDEX + MAIN_ITEMS - 1) % MAIN_ITEMS;
+                            WORLD.sim.pause_index =
+                                (WORLD.sim.pause_index + MAIN_ITEMS - 1) % MAIN_ITEMS;
                         } else if down {
-                            PAUSE_INDEX = (PAUSE_INDEX + 1) % MAIN_ITEMS;
+                            WORLD.sim.pause_index = (WORLD.sim.pause_index + 1) % MAIN_ITEMS;
                         }
 
                         if confirm || back {
-                            match PAUSE_INDEX {
+                            match WORLD.sim.pause_index {
                                 0 => {
                                     // Resume
-                                    GAME_STATE.phase = GAME_STATE.paused_from;
+                                    WORLD.sim.game.phase = WORLD.sim.game.paused_from;
                                 }
                                 1 => {
-                                    // Restart round
+                                    // Restart round (a replay can't express this)
+                                    replay::stop();
                                     reset_round();
                                 }
                                 2 => {
                                     // Restart match
-                                    reset_match();
+                                    restart_match();
                                 }
                                 3 => {
                                     // Return to lobby
@@ -591,8 +846,8 @@ pub extern "C" fn update() {
                                 }
                                 4 => {
                                     // Options
-                                    PAUSE_PAGE = PausePage::Options;
-                                    PAUSE_INDEX = 0;
+                                    WORLD.sim.pause_page = PausePage::Options;
+                                    WORLD.sim.pause_index = 0;
                                 }
                                 _ => {}
                             }
@@ -601,12 +856,13 @@ pub extern "C" fn update() {
                     PausePage::Options => {
                         const OPT_ITEMS: u32 = 5;
                         if up {
-                            PAUSE_INDEX = (PAUSE_INDEX + OPT_ITEMS - 1) % OPT_ITEMS;
+                            WORLD.sim.pause_index =
+                                (WORLD.sim.pause_index + OPT_ITEMS - 1) % OPT_ITEMS;
                         } else if down {
-                            PAUSE_INDEX = (PAUSE_INDEX + 1) % OPT_ITEMS;
+                            WORLD.sim.pause_index = (WORLD.sim.pause_index + 1) % OPT_ITEMS;
                         }
 
-                        match PAUSE_INDEX {
+                        match WORLD.sim.pause_index {
                             0 => {
                                 if left {
                                     OPTIONS.music_volume = (OPTIONS.music_volume - 0.05).max(0.0);
@@ -625,28 +881,22 @@ pub extern "C" fn update() {
                                     audio::set_sfx_volume(OPTIONS.sfx_volume);
                                 }
                             }
-                            2 => {
-                                if confirm || left || right {
-                                    OPTIONS.screen_shake = !OPTIONS.screen_shake;
-                                }
+                            2 if confirm || left || right => {
+                                OPTIONS.screen_shake = !OPTIONS.screen_shake;
                             }
-                            3 => {
-                                if confirm || left || right {
-                                    OPTIONS.screen_flash = !OPTIONS.screen_flash;
-                                }
+                            3 if confirm || left || right => {
+                                OPTIONS.screen_flash = !OPTIONS.screen_flash;
                             }
-                            4 => {
-                                if confirm || back {
-                                    PAUSE_PAGE = PausePage::Main;
-                                    PAUSE_INDEX = 0;
-                                }
+                            4 if confirm || back => {
+                                WORLD.sim.pause_page = PausePage::Main;
+                                WORLD.sim.pause_index = 0;
                             }
                             _ => {}
                         }
 
-                        if back && PAUSE_INDEX != 4 {
-                            PAUSE_PAGE = PausePage::Main;
-                            PAUSE_INDEX = 0;
+                        if back && WORLD.sim.pause_index != 4 {
+                            WORLD.sim.pause_page = PausePage::Main;
+                            WORLD.sim.pause_index = 0;
                         }
                     }
                 }
@@ -664,20 +914,20 @@ pub extern "C" fn update() {
                 update_deflect_popup();
 
                 // Let particles linger in slow motion (every other frame).
-                if TICK % 2 == 0 {
+                if WORLD.sim.tick.is_multiple_of(2) {
                     particles::update_particles();
                 }
 
-                if GAME_STATE.final_ko_timer > 0 {
-                    GAME_STATE.final_ko_timer -= 1;
+                if WORLD.sim.game.final_ko_timer > 0 {
+                    WORLD.sim.game.final_ko_timer -= 1;
                 } else {
                     // Enter match end presentation
-                    GAME_STATE.phase = GamePhase::MatchEnd;
+                    WORLD.sim.game.phase = GamePhase::MatchEnd;
                     game_state::reset_match_end_tick();
                     audio::play_victory();
-                    particles::spawn_victory_confetti(
-                        player::PLAYER_COLORS[GAME_STATE.winner_idx as usize],
-                    );
+                    particles::spawn_victory_confetti(player::player_color(
+                        WORLD.sim.game.winner_idx as usize,
+                    ));
                 }
             }
 
@@ -688,32 +938,29 @@ pub extern "C" fn update() {
                 // Update particles (for victory confetti)
                 particles::update_particles();
 
-                if GAME_STATE.demo_mode {
+                if WORLD.sim.game.demo_mode {
                     // Any input -> lobby; otherwise return to title after a bit.
                     if any_input_pressed() {
                         audio::stop_music();
                         enter_lobby();
                         return;
                     }
-                    if game_state::MATCH_END_TICK > 300 {
+                    if WORLD.sim.match_end_tick > 300 {
                         audio::stop_music();
                         enter_title();
-                        return;
                     }
                 } else {
                     // Rematch / back to lobby
-                    if player_count() > 0 && button_pressed(0, BUTTON_B) != 0 {
+                    if player_count() > 0 && button_pressed(0, BUTTON_B) {
                         audio::stop_music();
                         enter_lobby();
                         return;
                     }
-                    for (i, player) in PLAYERS.iter().enumerate() {
-                        if player.active
-                            && !player.is_bot
-                            && button_pressed(i as u32, BUTTON_START) != 0
+                    for (i, player) in WORLD.sim.players.iter().enumerate() {
+                        if player.active && !player.is_bot && button_pressed(i as u32, BUTTON_START)
                         {
                             // Rematch with same config/participants
-                            reset_match();
+                            restart_match();
                             return;
                         }
                     }
@@ -723,15 +970,16 @@ pub extern "C" fn update() {
     }
 }
 
+#[cfg(target_arch = "wasm32")]
 #[no_mangle]
 pub extern "C" fn render() {
     unsafe {
         // Get shake offsets
-        let shake_x = SCREEN_SHAKE_X;
-        let shake_y = SCREEN_SHAKE_Y;
+        let shake_x = WORLD.fx.shake_x;
+        let shake_y = WORLD.fx.shake_y;
 
         // Set camera for side-view with shake offset applied
-        camera_set(
+        ffi::camera_set(
             0.0 + shake_x,
             2.0 + shake_y,
             12.0,
@@ -741,7 +989,7 @@ pub extern "C" fn render() {
         );
 
         // Use dynamic camera FOV (zooms in on kills)
-        camera_fov(CAMERA_FOV);
+        ffi::camera_fov(WORLD.fx.camera_fov);
 
         // Apply effect lights for visual feedback
         apply_effect_lights();
@@ -749,8 +997,108 @@ pub extern "C" fn render() {
         // Render in order
         render_stage();
         render_players();
+        render_pickups();
         render_bullets();
         render_particles();
         render_ui();
+        render_desync_overlay();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::KillCause;
+    use crate::world::World;
+
+    /// One frame with these buttons held on each connected pad
+    fn tick(held: [u32; 2]) {
+        for (i, &mask) in held.iter().enumerate() {
+            platform::host().set_input(i, mask, 0.0, 0.0);
+        }
+        update();
+        platform::host().end_tick();
+    }
+
+    /// Fresh boot, then both pads join in the lobby and P1 starts a match
+    unsafe fn start_two_player_match(kills_to_win: u32) {
+        WORLD = World::new();
+        platform::host().reset(2, 11);
+        init();
+        WORLD.sim.config.fill_bots = false;
+        WORLD.sim.config.stage_select = 0;
+        WORLD.sim.config.kills_to_win = kills_to_win;
+
+        // Title -> lobby, both join, P1 starts
+        tick([1 << BUTTON_A, 0]);
+        assert!(WORLD.sim.game.phase == GamePhase::Lobby);
+        tick([0, 0]);
+        tick([1 << BUTTON_A, 1 << BUTTON_A]);
+        tick([0, 0]);
+        tick([1 << BUTTON_START, 0]);
+        assert!(WORLD.sim.game.phase == GamePhase::Countdown);
+    }
+
+    /// Let the countdown run out, then P1 takes the winning kill
+    unsafe fn win_match_for_p1() {
+        while WORLD.sim.game.phase == GamePhase::Countdown {
+            tick([0, 0]);
+        }
+        assert!(WORLD.sim.game.phase == GamePhase::Playing);
+        player::kill_player(1, 0, KillCause::Melee);
+        while WORLD.sim.game.phase != GamePhase::MatchEnd {
+            tick([0, 0]);
+        }
+    }
+
+    #[test]
+    fn two_humans_play_through_scripted_input() {
+        let _lock = platform::test_lock();
+        unsafe {
+            start_two_player_match(5);
+            assert_eq!(replay::mode(), replay::ReplayMode::Recording);
+            let humans = WORLD.sim.players.iter().filter(|p| p.active && !p.is_bot);
+            assert_eq!(humans.count(), 2);
+
+            while WORLD.sim.game.phase == GamePhase::Countdown {
+                tick([0, 0]);
+            }
+            assert!(WORLD.sim.game.phase == GamePhase::Playing);
+
+            // Walk toward each other, then P1 jumps
+            let start = (WORLD.sim.players[0].x, WORLD.sim.players[1].x);
+            for _ in 0..20 {
+                tick([1 << BUTTON_RIGHT, 1 << BUTTON_LEFT]);
+            }
+            assert!(WORLD.sim.players[0].x > start.0 + 1.0);
+            assert!(WORLD.sim.players[1].x < start.1 - 1.0);
+            assert!(WORLD.sim.players[0].on_ground);
+            tick([1 << BUTTON_A, 0]);
+            tick([1 << BUTTON_A, 0]);
+            assert!(!WORLD.sim.players[0].on_ground && WORLD.sim.players[0].vy > 0.0);
+            assert!(WORLD.sim.players[1].on_ground);
+        }
+    }
+
+    #[test]
+    fn a_rematch_is_recorded_too() {
+        let _lock = platform::test_lock();
+        unsafe {
+            start_two_player_match(1);
+            win_match_for_p1();
+            let first = replay::recorded().expect("first match recorded").to_vec();
+
+            // START on the results screen plays the same matchup again
+            tick([0, 0]);
+            tick([1 << BUTTON_START, 0]);
+            assert!(WORLD.sim.game.phase == GamePhase::Countdown);
+            assert_eq!(replay::mode(), replay::ReplayMode::Recording);
+            assert!(replay::recorded().is_none());
+
+            win_match_for_p1();
+            let second = replay::recorded().expect("rematch recorded");
+            assert_ne!(second, &first[..]);
+            assert!(replay::read_header(second).is_ok());
+        }
     }
 }
@@ -0,0 +1,798 @@
+//! Bot navigation
+//!
+//! Every stage gets a graph with one node per platform a player can stand
+//! on. Edges are single moves between platforms: walking off an edge, a
+//! jump, a drop through a one-way floor, or a jump that kicks off a wall on
+//! the way. They are found at init by simulating the same physics as
+//! `update_player` from every take-off spot, with moving platforms sampled
+//! along their path.
+//!
+//! Bots plan a route over the graph, then confirm each move right before
+//! taking it with `predict_landing`, which also slides moving platforms
+//! forward tick by tick. A move that would end in a hazard (below
+//! `DEATH_Y`, or against a closing overtime wall) is never taken; for moves
+//! onto or off a mover, the bot waits until the timing works.
+
+use crate::hazard;
+use crate::player::{
+    aabb_overlap, clamp, Player, AIR_FRICTION, DEATH_Y, DROP_THROUGH_FRAMES, FAST_FALL_MULT,
+    FRICTION, GRAVITY, JUMP_FORCE, MOVE_SPEED, PLAYER_HEIGHT, PLAYER_WIDTH, SIDE_EPSILON,
+};
+use crate::stage::{Collision, Platform, StageDef, MAX_PLATFORMS, NUM_STAGES, STAGES};
+use crate::world::WORLD;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+pub const MAX_NAV_EDGES: usize = 256;
+/// `Player::ai_nav_edge` when no move is in progress
+pub const NAV_NONE: u32 = u32::MAX;
+/// Ticks a bot holds jump for a full-height jump (rise time to the apex)
+pub const NAV_JUMP_HOLD: u32 = 20;
+/// Ticks of movement `preview` traces ahead
+pub const PREVIEW_TICKS: usize = 30;
+
+/// Longest flight simulated before giving up on a landing
+const MAX_FLIGHT_TICKS: u32 = 240;
+/// Horizontal spacing of take-off positions along a platform
+const TAKEOFF_STEP: f32 = 0.1;
+/// Moving platforms are sampled at this many points along their path
+const MOVER_SNAPSHOTS: usize = 5;
+/// Landing tolerance used by the player collision code
+const LANDING_SNAP: f32 = 0.2;
+/// Running speed on the ground: `(v + MOVE_SPEED * 0.15) * FRICTION` settles here
+const GROUND_RUN_SPEED: f32 = MOVE_SPEED * 0.15 * FRICTION / (1.0 - FRICTION);
+/// Route cost of a move on top of its flight time, so fewer moves win
+const MOVE_COST: u32 = 30;
+/// How close to a ledge a walking bot checks where the drop lands
+const LEDGE_LOOKAHEAD: f32 = 0.7;
+/// Distance bots keep from a deadly ledge when backing off (a clash knockback
+/// carries about 1.9 units)
+const RETREAT_MARGIN: f32 = 2.0;
+
+// =============================================================================
+// GRAPH
+// =============================================================================
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NavMove {
+    Walk,     // Run off the edge
+    Jump,     // Full-height jump
+    Drop,     // Down + jump through a one-way floor
+    WallJump, // Jump, then kick off the first wall touched
+}
+
+#[derive(Clone, Copy)]
+pub struct NavEdge {
+    pub from: usize, // Platform indices
+    pub to: usize,
+    pub kind: NavMove,
+    pub dir: f32, // Stick x held from take-off
+    // Take-off x range, relative to the `from` platform's left edge
+    pub takeoff_min: f32,
+    pub takeoff_max: f32,
+    pub cost: u32,   // Estimated ticks
+    pub timed: bool, // Touches a moving platform: wait for it rather than reposition
+}
+
+impl NavEdge {
+    pub const fn new() -> Self {
+        Self {
+            from: 0,
+            to: 0,
+            kind: NavMove::Walk,
+            dir: 0.0,
+            takeoff_min: 0.0,
+            takeoff_max: 0.0,
+            cost: 0,
+            timed: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct NavGraph {
+    pub edges: [NavEdge; MAX_NAV_EDGES],
+    pub edge_count: usize,
+}
+
+impl NavGraph {
+    pub const fn new() -> Self {
+        Self {
+            edges: [NavEdge::new(); MAX_NAV_EDGES],
+            edge_count: 0,
+        }
+    }
+
+    pub fn edges(&self) -> &[NavEdge] {
+        &self.edges[..self.edge_count]
+    }
+
+    /// Add a way to make a move, widening the take-off range of a matching edge
+    fn add(&mut self, edge: NavEdge) {
+        for e in &mut self.edges[..self.edge_count] {
+            if e.from == edge.from && e.to == edge.to && e.kind == edge.kind && e.dir == edge.dir {
+                e.takeoff_min = e.takeoff_min.min(edge.takeoff_min);
+                e.takeoff_max = e.takeoff_max.max(edge.takeoff_max);
+                e.cost = e.cost.min(edge.cost);
+                return;
+            }
+        }
+        if self.edge_count < MAX_NAV_EDGES {
+            self.edges[self.edge_count] = edge;
+            self.edge_count += 1;
+        }
+    }
+}
+
+/// Graphs for every built-in stage. Derived from the stage files only, so
+/// they sit outside the rollback state.
+static mut NAV_GRAPHS: [NavGraph; NUM_STAGES as usize] = [NavGraph::new(); NUM_STAGES as usize];
+
+/// Build every stage's graph (called once from `init`)
+pub fn build_nav_graphs() {
+    unsafe {
+        for (graph, def) in NAV_GRAPHS.iter_mut().zip(STAGES.iter()) {
+            *graph = build_graph(def);
+        }
+    }
+}
+
+/// Graph for the stage being played
+pub fn nav_graph() -> &'static NavGraph {
+    unsafe {
+        let stage = (WORLD.sim.game.current_stage as usize).min(NAV_GRAPHS.len() - 1);
+        &*core::ptr::addr_of!(NAV_GRAPHS[stage])
+    }
+}
+
+fn build_graph(def: &StageDef) -> NavGraph {
+    let mut graph = NavGraph::new();
+    let arena = (def.arena_left, def.arena_right);
+    let has_movers = def.platforms().iter().any(|p| p.moving);
+    let snapshots = if has_movers { MOVER_SNAPSHOTS } else { 1 };
+    let has_walls = def.platforms().iter().any(|p| p.has_sides());
+
+    for k in 0..snapshots {
+        // Every mover frozen at the same point of its path
+        let mut platforms = def.platforms;
+        let t = k as f32 / (snapshots - 1).max(1) as f32;
+        for p in &mut platforms {
+            if p.moving {
+                p.x = p.move_min + (p.move_max - p.move_min) * t;
+            }
+        }
+
+        for (from, src) in def.platforms().iter().enumerate() {
+            if !src.has_floor() {
+                continue; // Walls can't be stood on
+            }
+            let src = &platforms[from];
+            let top = src.y + src.height;
+            let mut record = |kind: NavMove, x: f32, dir: f32, flight: Flight| {
+                if let Some((to, ticks)) = simulate(flight, &platforms, arena, kind) {
+                    if to != from {
+                        graph.add(NavEdge {
+                            from,
+                            to,
+                            kind,
+                            dir,
+                            takeoff_min: x - src.x,
+                            takeoff_max: x - src.x,
+                            cost: ticks + MOVE_COST,
+                            timed: src.moving || platforms[to].moving,
+                        });
+                    }
+                }
+            };
+
+            let mut x = src.x - PLAYER_WIDTH + TAKEOFF_STEP;
+            while x < src.x + src.width {
+                for dir in [-1.0, 0.0, 1.0] {
+                    let run = Flight::launch(x, top, GROUND_RUN_SPEED * dir, dir);
+                    record(NavMove::Jump, x, dir, run.jump());
+                    if has_walls {
+                        record(NavMove::WallJump, x, dir, run.jump());
+                    }
+                    if src.collision != Collision::Solid {
+                        record(NavMove::Drop, x, dir, run.drop());
+                    }
+                }
+                x += TAKEOFF_STEP;
+            }
+
+            // Running off either edge
+            for (x, dir) in [
+                (src.x - PLAYER_WIDTH - 0.01, -1.0),
+                (src.x + src.width + 0.01, 1.0),
+            ] {
+                record(
+                    NavMove::Walk,
+                    x,
+                    dir,
+                    Flight::walk_off(x, top, GROUND_RUN_SPEED * dir, dir),
+                );
+            }
+        }
+    }
+    graph
+}
+
+/// Fly to a landing, returning the platform and flight time. `WallJump`
+/// only counts if a wall kick actually happened on the way.
+fn simulate(
+    mut f: Flight,
+    platforms: &[Platform; MAX_PLATFORMS],
+    arena: (f32, f32),
+    kind: NavMove,
+) -> Option<(usize, u32)> {
+    let mut kicked = false;
+    loop {
+        if kind == NavMove::WallJump && !kicked && f.drop_timer == 0 && f.vy < 0.3 {
+            if let Some(away) = touching_wall(f.x, f.y, platforms) {
+                f.vy = JUMP_FORCE * 0.9;
+                f.vx = MOVE_SPEED * 0.8 * away;
+                f.input = away;
+                f.hold = NAV_JUMP_HOLD;
+                kicked = true;
+            }
+        }
+        match f.step(platforms, arena) {
+            Step::Flying => {}
+            Step::Landed(to) if kind != NavMove::WallJump || kicked => return Some((to, f.ticks)),
+            Step::Landed(_) | Step::Lost => return None,
+        }
+    }
+}
+
+// =============================================================================
+// FLIGHT SIMULATION
+// =============================================================================
+
+enum Step {
+    Flying,
+    Landed(usize),
+    Lost, // Fell below DEATH_Y (or never came down)
+}
+
+/// Player state during a simulated flight (mirrors `update_player`)
+#[derive(Clone, Copy)]
+struct Flight {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    input: f32,
+    hold: u32,       // Ticks jump stays held
+    drop_timer: u32, // Falling through one-way floors
+    ground: bool,    // First tick still moves with ground acceleration
+    ticks: u32,
+}
+
+impl Flight {
+    /// Leaving the ground this tick, running at `vx`
+    fn launch(x: f32, y: f32, vx: f32, input: f32) -> Self {
+        Self {
+            x,
+            y,
+            vx,
+            vy: 0.0,
+            input,
+            hold: 0,
+            drop_timer: 0,
+            ground: true,
+            ticks: 0,
+        }
+    }
+
+    /// Running off a ledge at `vx`: the last step on the ground starts one
+    /// step short of `edge_x`, the first spot clear of the platform
+    fn walk_off(edge_x: f32, y: f32, vx: f32, input: f32) -> Self {
+        Self::launch(edge_x - vx, y, vx, input)
+    }
+
+    /// Already airborne, keeping `input` held
+    fn from_player(p: &Player, input: f32) -> Self {
+        Self {
+            x: p.x,
+            y: p.y,
+            vx: p.vx,
+            vy: p.vy,
+            input,
+            hold: p.ai_jump_hold.max(p.pogo_timer.saturating_sub(1)),
+            drop_timer: p.drop_timer,
+            ground: false,
+            ticks: 0,
+        }
+    }
+
+    fn jump(self) -> Self {
+        Self {
+            vy: JUMP_FORCE,
+            hold: NAV_JUMP_HOLD,
+            ..self
+        }
+    }
+
+    /// Down + jump; down held on that tick also fast-falls
+    fn drop(self) -> Self {
+        Self {
+            vy: -0.05 - GRAVITY * (FAST_FALL_MULT - 1.0),
+            drop_timer: DROP_THROUGH_FRAMES,
+            ..self
+        }
+    }
+
+    /// Advance one tick against `platforms`
+    fn step(&mut self, platforms: &[Platform; MAX_PLATFORMS], (left, right): (f32, f32)) -> Step {
+        self.ticks += 1;
+        let (accel, friction) = if self.ground {
+            (MOVE_SPEED * 0.15, FRICTION)
+        } else {
+            (MOVE_SPEED * 0.08, AIR_FRICTION)
+        };
+        self.ground = false;
+        self.vx = clamp(
+            (self.vx + self.input * accel) * friction,
+            -MOVE_SPEED,
+            MOVE_SPEED,
+        );
+
+        // Variable jump height, then gravity
+        if self.hold == 0 && self.vy > 0.0 {
+            self.vy *= 0.5;
+        }
+        self.hold = self.hold.saturating_sub(1);
+        self.vy -= GRAVITY;
+
+        let mut nx = self.x + self.vx;
+        let mut ny = self.y + self.vy;
+
+        // Blocking sides first, at the old height
+        for p in platforms.iter().filter(|p| p.active && p.has_sides()) {
+            if !aabb_overlap((nx, self.y, PLAYER_WIDTH, PLAYER_HEIGHT), rect(p)) {
+                continue;
+            }
+            let from_left = self.x + PLAYER_WIDTH <= p.x + SIDE_EPSILON;
+            let from_right = self.x >= p.x + p.width - SIDE_EPSILON;
+            let nearer_left = self.x + PLAYER_WIDTH * 0.5 < p.x + p.width * 0.5;
+            nx = if from_left || (!from_right && nearer_left) {
+                p.x - PLAYER_WIDTH
+            } else {
+                p.x + p.width
+            };
+            self.vx = 0.0;
+        }
+
+        // Then floors and ceilings
+        for (i, p) in platforms.iter().enumerate() {
+            if !p.active || !aabb_overlap((nx, ny, PLAYER_WIDTH, PLAYER_HEIGHT), rect(p)) {
+                continue;
+            }
+            let can_land =
+                p.has_floor() && (self.drop_timer == 0 || p.collision == Collision::Solid);
+            if can_land && self.vy <= 0.0 && self.y >= p.y + p.height - LANDING_SNAP {
+                self.x = nx;
+                return Step::Landed(i);
+            }
+            if p.has_ceiling() && self.vy > 0.0 && self.y + PLAYER_HEIGHT <= p.y + LANDING_SNAP {
+                ny = p.y - PLAYER_HEIGHT;
+                self.vy = 0.0;
+            }
+        }
+
+        self.x = clamp(nx, left, right - PLAYER_WIDTH);
+        self.y = ny;
+        self.drop_timer = self.drop_timer.saturating_sub(1);
+        if self.y < DEATH_Y || self.ticks >= MAX_FLIGHT_TICKS {
+            Step::Lost
+        } else {
+            Step::Flying
+        }
+    }
+
+    /// `step` on the live stage, `tick` ticks from now: the walls are where
+    /// they'll be by then, and any stage hazard ends the flight
+    fn step_live(&mut self, platforms: &[Platform; MAX_PLATFORMS], tick: u32) -> Step {
+        let step = self.step(platforms, hazard::arena_at(tick));
+        if hazard::deadly(self.x, self.y, tick) {
+            Step::Lost
+        } else {
+            step
+        }
+    }
+}
+
+fn rect(p: &Platform) -> (f32, f32, f32, f32) {
+    (p.x, p.y, p.width, p.height)
+}
+
+/// Direction a wall jump would push, if a wall is right beside the player
+fn touching_wall(x: f32, y: f32, platforms: &[Platform; MAX_PLATFORMS]) -> Option<f32> {
+    for p in platforms.iter().filter(|p| p.active && p.has_sides()) {
+        if y + PLAYER_HEIGHT <= p.y || y >= p.y + p.height {
+            continue;
+        }
+        let left = x - 0.1;
+        let right = x + PLAYER_WIDTH + 0.1;
+        if left >= p.x && left <= p.x + p.width {
+            return Some(1.0); // Wall on the left pushes right
+        }
+        if right >= p.x && right <= p.x + p.width {
+            return Some(-1.0);
+        }
+    }
+    None
+}
+
+/// Where a flight comes down on the live stage, sliding movers along as
+/// `update_platforms` will. `None` means it falls out of the arena.
+fn predict_landing(f: Flight) -> Option<usize> {
+    predict_touchdown(f).map(|(i, _)| i)
+}
+
+/// `predict_landing`, plus how far from the nearer edge it touches down
+fn predict_touchdown(mut f: Flight) -> Option<(usize, f32)> {
+    unsafe {
+        let mut platforms = WORLD.sim.platforms;
+        for tick in 1.. {
+            slide_movers(&mut platforms);
+            match f.step_live(&platforms, tick) {
+                Step::Flying => {}
+                Step::Landed(i) => {
+                    let p = &platforms[i];
+                    let margin = (f.x + PLAYER_WIDTH - p.x).min(p.x + p.width - f.x);
+                    return Some((i, margin));
+                }
+                Step::Lost => return None,
+            }
+        }
+        None
+    }
+}
+
+/// Move a copy of the platforms one tick along, as `update_platforms` will
+fn slide_movers(platforms: &mut [Platform; MAX_PLATFORMS]) {
+    for p in platforms {
+        if p.active && p.moving {
+            p.x += p.move_speed;
+            if p.x <= p.move_min || p.x >= p.move_max {
+                p.move_speed = -p.move_speed;
+            }
+        }
+    }
+}
+
+// =============================================================================
+// QUERIES
+// =============================================================================
+
+/// Platform a grounded player at (x, y) is standing on
+pub fn standing_on(x: f32, y: f32) -> Option<usize> {
+    unsafe {
+        WORLD.sim.platforms.iter().position(|p| {
+            p.active
+                && p.has_floor()
+                && (y - (p.y + p.height)).abs() < 0.02
+                && x < p.x + p.width
+                && x + PLAYER_WIDTH > p.x
+        })
+    }
+}
+
+/// Platform below a point (highest first), or the closest one when the
+/// point is over the pit
+pub fn node_below(x: f32, y: f32) -> Option<usize> {
+    unsafe {
+        let mut best: Option<(usize, f32)> = None;
+        for (i, p) in WORLD.sim.platforms.iter().enumerate() {
+            if !p.active || !p.has_floor() {
+                continue;
+            }
+            let top = p.y + p.height;
+            let over = x > p.x - PLAYER_WIDTH * 0.5 && x < p.x + p.width + PLAYER_WIDTH * 0.5;
+            let score = if over && top <= y {
+                y - top
+            } else {
+                // Distance to the top surface, well behind anything directly below
+                let cx = clamp(x, p.x, p.x + p.width);
+                100.0 + (cx - x) * (cx - x) + (top - y) * (top - y)
+            };
+            if best.is_none_or(|(_, s)| score < s) {
+                best = Some((i, score));
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+}
+
+/// First edge of the cheapest route from one platform to another (Dijkstra)
+pub fn first_move(from: usize, to: usize) -> Option<usize> {
+    let graph = nav_graph();
+    let mut dist = [u32::MAX; MAX_PLATFORMS];
+    let mut first = [usize::MAX; MAX_PLATFORMS];
+    let mut done = [false; MAX_PLATFORMS];
+    dist[from] = 0;
+
+    loop {
+        let mut next: Option<usize> = None;
+        for n in 0..MAX_PLATFORMS {
+            if !done[n] && dist[n] != u32::MAX && next.is_none_or(|b| dist[n] < dist[b]) {
+                next = Some(n);
+            }
+        }
+        let Some(u) = next else {
+            break;
+        };
+        if u == to {
+            break;
+        }
+        done[u] = true;
+        for (e, edge) in graph.edges().iter().enumerate() {
+            if edge.from != u {
+                continue;
+            }
+            let d = dist[u] + edge.cost;
+            if d < dist[edge.to] {
+                dist[edge.to] = d;
+                first[edge.to] = if u == from { e } else { first[u] };
+            }
+        }
+    }
+    (first[to] != usize::MAX).then_some(first[to])
+}
+
+/// Whether holding `input_x` keeps a player from running off into the pit
+/// or up to an overtime wall
+pub fn walk_is_safe(p: &Player, input_x: f32) -> bool {
+    edge_is_safe(p, input_x, LEDGE_LOOKAHEAD)
+}
+
+/// Whether backing off along `input_x` keeps clear of ledges over the pit,
+/// far enough that a clash knockback there can't pop the player off
+pub fn retreat_is_safe(p: &Player, input_x: f32) -> bool {
+    edge_is_safe(p, input_x, RETREAT_MARGIN)
+}
+
+/// True unless `input_x` heads for an overtime wall, or the platform edge
+/// along it is within `reach` and running off it misses every platform
+fn edge_is_safe(p: &Player, input_x: f32, reach: f32) -> bool {
+    if input_x == 0.0 {
+        return true;
+    }
+    if hazard::toward_wall(p.x, input_x) {
+        return false;
+    }
+    if !p.on_ground {
+        return true;
+    }
+    let Some(node) = standing_on(p.x, p.y) else {
+        return true;
+    };
+    let plat = unsafe { WORLD.sim.platforms[node] };
+    let edge_x = if input_x > 0.0 {
+        plat.x + plat.width + 0.01
+    } else {
+        plat.x - PLAYER_WIDTH - 0.01
+    };
+    if (edge_x - p.x).abs() > reach {
+        return true;
+    }
+    let dir = if input_x > 0.0 { 1.0 } else { -1.0 };
+    let speed = run_up_speed(p.vx * dir, (edge_x - p.x).abs()) * dir;
+    predict_landing(Flight::walk_off(edge_x, p.y, speed, dir)).is_some()
+}
+
+/// Ground speed after running `dist` from speed `vx` (both along the run),
+/// so a slow start off a ledge isn't judged as a full-speed one
+fn run_up_speed(vx: f32, dist: f32) -> f32 {
+    let mut v = vx.max(0.0);
+    let mut left = dist;
+    while left > 0.0 && v < GROUND_RUN_SPEED {
+        v = (v + MOVE_SPEED * 0.15) * FRICTION;
+        left -= v;
+    }
+    v
+}
+
+/// Whether a jump from where a grounded player stands comes down on a platform
+pub fn jump_is_safe(p: &Player, input_x: f32) -> bool {
+    predict_landing(Flight::launch(p.x, p.y, p.vx, input_x).jump()).is_some()
+}
+
+// =============================================================================
+// ROUTE FOLLOWING
+// =============================================================================
+
+/// Inputs for one tick of route following
+pub struct NavInput {
+    pub x: f32,
+    pub jump: bool,
+    pub drop: bool, // Down + jump
+}
+
+/// Steer a bot toward `goal`. `None` means the goal is on the bot's own
+/// platform (or out of reach) and the bot should close in directly, minding
+/// `walk_is_safe`.
+pub fn route_input(p: &mut Player, goal: (f32, f32)) -> Option<NavInput> {
+    let graph = nav_graph();
+    let goal_node = node_below(goal.0, goal.1);
+    let toward_goal = if goal.0 > p.x + PLAYER_WIDTH * 0.5 {
+        1.0
+    } else {
+        -1.0
+    };
+
+    if !p.on_ground {
+        let edge = graph.edges().get(p.ai_nav_edge as usize).copied();
+        let target = edge.map(|e| e.to).or(goal_node);
+        let mut input = NavInput {
+            x: edge.map_or(toward_goal, |e| e.dir),
+            jump: false,
+            drop: false,
+        };
+        if let Some(e) = edge.filter(|e| e.kind == NavMove::WallJump) {
+            let platforms = unsafe { &WORLD.sim.platforms };
+            if p.vy < 0.3 && touching_wall(p.x, p.y, platforms).is_some() {
+                input.jump = true;
+                return Some(input);
+            }
+            if p.vx * e.dir >= 0.0 {
+                return Some(input); // Still heading for the wall
+            }
+        }
+        input.x = air_steer(p, target, input.x);
+        return Some(input);
+    }
+
+    p.ai_nav_edge = NAV_NONE;
+    let node = standing_on(p.x, p.y)?;
+    let goal_node = goal_node.filter(|&g| g != node)?;
+    let e = first_move(node, goal_node)?;
+    let edge = graph.edges[e];
+
+    let plat = unsafe { WORLD.sim.platforms[node] };
+    let offset = p.x - plat.x;
+    let in_range = offset >= edge.takeoff_min - 0.05 && offset <= edge.takeoff_max + 0.05;
+    let mut input = NavInput {
+        x: edge.dir,
+        jump: false,
+        drop: false,
+    };
+
+    // Walk-offs just keep running; `walk_is_safe` holds the bot at the
+    // ledge until the landing below is there
+    if edge.kind == NavMove::Walk {
+        if offset < edge.takeoff_min - 0.5 {
+            input.x = 1.0;
+        } else if offset > edge.takeoff_max + 0.5 {
+            input.x = -1.0;
+        }
+        return Some(input);
+    }
+
+    let launch = Flight::launch(p.x, p.y, p.vx, edge.dir);
+    let landing = match edge.kind {
+        NavMove::Drop => predict_landing(launch.drop()),
+        _ => predict_landing(launch.jump()),
+    };
+    // Go when the move lands where planned; on fixed platforms, also when
+    // in range and landing anywhere safe (the route is re-planned there)
+    let go = if edge.kind == NavMove::WallJump {
+        in_range
+    } else {
+        landing == Some(edge.to) || (in_range && !edge.timed && landing.is_some())
+    };
+    if go {
+        p.ai_nav_edge = e as u32;
+        input.jump = edge.kind != NavMove::Drop;
+        input.drop = edge.kind == NavMove::Drop;
+        return Some(input);
+    }
+
+    // Head for the take-off range; once inside, wait for a mover or line up
+    // with the middle of it
+    let mid = (edge.takeoff_min + edge.takeoff_max) * 0.5;
+    input.x = if offset < edge.takeoff_min {
+        1.0
+    } else if offset > edge.takeoff_max {
+        -1.0
+    } else if edge.timed || (offset - mid).abs() < 0.1 {
+        0.0
+    } else if offset < mid {
+        1.0
+    } else {
+        -1.0
+    };
+    Some(input)
+}
+
+/// Stick x for an airborne bot: land on `target` if any input does,
+/// otherwise anywhere safe, as far from the edge as possible (ties go to
+/// `preferred`)
+fn air_steer(p: &Player, target: Option<usize>, preferred: f32) -> f32 {
+    let candidates = if preferred == 0.0 {
+        [0.0, 1.0, -1.0]
+    } else {
+        [preferred, 0.0, -preferred]
+    };
+    // (on target, margin, input)
+    let mut best: Option<(bool, f32, f32)> = None;
+    for input in candidates {
+        let Some((i, margin)) = predict_touchdown(Flight::from_player(p, input)) else {
+            continue;
+        };
+        let on_target = Some(i) == target;
+        let better = best.is_none_or(|(best_on, best_margin, _)| {
+            (on_target, margin) > (best_on, best_margin + 0.05)
+        });
+        if better {
+            best = Some((on_target, margin, input));
+        }
+    }
+    best.map_or(preferred, |(_, _, input)| input)
+}
+
+// =============================================================================
+// MANEUVERS
+// =============================================================================
+
+/// A move a bot can start on the spot, holding its stick input throughout
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Maneuver {
+    Hold,
+    Walk(f32), // Run (steer, in the air) along x
+    Jump(f32), // Full-height jump from the ground, steering along x
+    Drop,      // Down through a one-way floor
+}
+
+impl Maneuver {
+    /// Stick x held during the move
+    pub fn input_x(self) -> f32 {
+        match self {
+            Maneuver::Hold | Maneuver::Drop => 0.0,
+            Maneuver::Walk(x) | Maneuver::Jump(x) => x,
+        }
+    }
+}
+
+/// Where a player will be (bottom-left corner) after each of the next
+/// `PREVIEW_TICKS` ticks if it starts `m` now. `None` when the move can't be
+/// made from here (jumps and drops need the ground, drops a one-way floor)
+/// or it ends up below `DEATH_Y`.
+pub fn preview(p: &Player, m: Maneuver) -> Option<[(f32, f32); PREVIEW_TICKS]> {
+    let input = m.input_x();
+    let mut f = match (p.on_ground, m) {
+        (true, Maneuver::Jump(_)) => Flight::launch(p.x, p.y, p.vx, input).jump(),
+        (true, Maneuver::Drop) => Flight::launch(p.x, p.y, p.vx, input).drop(),
+        (true, _) => Flight::launch(p.x, p.y, p.vx, input),
+        (false, Maneuver::Jump(_) | Maneuver::Drop) => return None,
+        (false, _) => Flight::from_player(p, input),
+    };
+
+    unsafe {
+        let mut platforms = WORLD.sim.platforms;
+        let mut path = [(0.0, 0.0); PREVIEW_TICKS];
+        for (tick, spot) in path.iter_mut().enumerate() {
+            slide_movers(&mut platforms);
+            match f.step_live(&platforms, tick as u32 + 1) {
+                Step::Flying => {}
+                Step::Landed(_) if tick == 0 && m == Maneuver::Drop => return None,
+                Step::Landed(i) => {
+                    // Keep running along the floor it's on
+                    let top = platforms[i].y + platforms[i].height;
+                    f = Flight::launch(f.x, top, f.vx, input);
+                }
+                Step::Lost => return None,
+            }
+            *spot = (f.x, f.y);
+        }
+
+        // Still in the air at the end: it has to come down somewhere
+        if !f.ground && predict_landing(f).is_none() {
+            return None;
+        }
+        Some(path)
+    }
+}
@@ -1,6 +1,7 @@
 //! Particle system for visual effects
 
-use crate::ffi::*;
+use crate::rng::fx_f32;
+use crate::world::WORLD;
 
 /// Maximum particles in pool (increased for trails and sparks)
 pub const MAX_PARTICLES: usize = 128;
@@ -35,9 +36,6 @@ impl Particle {
     }
 }
 
-/// Global particle pool
-pub static mut PARTICLES: [Particle; MAX_PARTICLES] = [Particle::new(); MAX_PARTICLES];
-
 /// Spawn death explosion particles at position with color
 pub fn spawn_death_particles(x: f32, y: f32, color: u32) {
     unsafe {
@@ -45,7 +43,7 @@ pub fn spawn_death_particles(x: f32, y: f32, color: u32) {
 
         for i in 0..particle_count {
             // Find inactive particle
-            for p in &mut PARTICLES {
+            for p in &mut WORLD.fx.particles {
                 if !p.active {
                     p.active = true;
                     p.x = x;
@@ -53,15 +51,15 @@ pub fn spawn_death_particles(x: f32, y: f32, color: u32) {
 
                     // Random velocity in all directions
                     let angle = (i as f32 / particle_count as f32) * core::f32::consts::TAU
-                        + random_f32() * 0.5;
-                    let speed = 0.1 + random_f32() * 0.15;
+                        + fx_f32() * 0.5;
+                    let speed = 0.1 + fx_f32() * 0.15;
                     p.vx = libm::cosf(angle) * speed;
                     p.vy = libm::sinf(angle) * speed + 0.05; // Slight upward bias
 
-                    p.lifetime = 30 + (random_f32() * 20.0) as u32; // 30-50 frames
+                    p.lifetime = 30 + (fx_f32() * 20.0) as u32; // 30-50 frames
                     p.max_lifetime = p.lifetime;
                     p.color = color;
-                    p.size = 0.15 + random_f32() * 0.1;
+                    p.size = 0.15 + fx_f32() * 0.1;
                     break;
                 }
             }
@@ -72,7 +70,7 @@ pub fn spawn_death_particles(x: f32, y: f32, color: u32) {
 /// Update all particles
 pub fn update_particles() {
     unsafe {
-        for p in &mut PARTICLES {
+        for p in &mut WORLD.fx.particles {
             if p.active {
                 // Apply velocity
                 p.x += p.vx;
@@ -99,7 +97,7 @@ pub fn update_particles() {
 /// Clear all particles
 pub fn clear_particles() {
     unsafe {
-        for p in &mut PARTICLES {
+        for p in &mut WORLD.fx.particles {
             p.active = false;
         }
     }
@@ -110,14 +108,14 @@ pub fn spawn_landing_dust(x: f32, y: f32) {
     unsafe {
         let particle_count = 4;
         for _ in 0..particle_count {
-            for p in &mut PARTICLES {
+            for p in &mut WORLD.fx.particles {
                 if !p.active {
                     p.active = true;
-                    p.x = x + (random_f32() - 0.5) * 0.5;
+                    p.x = x + (fx_f32() - 0.5) * 0.5;
                     p.y = y;
-                    p.vx = (random_f32() - 0.5) * 0.1;
-                    p.vy = random_f32() * 0.05;
-                    p.lifetime = 10 + (random_f32() * 5.0) as u32;
+                    p.vx = (fx_f32() - 0.5) * 0.1;
+                    p.vy = fx_f32() * 0.05;
+                    p.lifetime = 10 + (fx_f32() * 5.0) as u32;
                     p.max_lifetime = p.lifetime;
                     p.color = 0xAAAAAAAA; // Gray dust
                     p.size = 0.1;
@@ -143,18 +141,18 @@ pub fn spawn_victory_confetti(winner_color: u32) {
         ];
 
         for i in 0..particle_count {
-            for p in &mut PARTICLES {
+            for p in &mut WORLD.fx.particles {
                 if !p.active {
                     p.active = true;
                     // Spawn across top of screen
-                    p.x = random_f32() * 16.0 - 8.0; // -8 to 8
-                    p.y = 8.0 + random_f32() * 2.0; // Top of screen
-                    p.vx = (random_f32() - 0.5) * 0.1;
-                    p.vy = -random_f32() * 0.15 - 0.05; // Fall down
-                    p.lifetime = 120 + (random_f32() * 60.0) as u32; // 2-3 seconds
+                    p.x = fx_f32() * 16.0 - 8.0; // -8 to 8
+                    p.y = 8.0 + fx_f32() * 2.0; // Top of screen
+                    p.vx = (fx_f32() - 0.5) * 0.1;
+                    p.vy = -fx_f32() * 0.15 - 0.05; // Fall down
+                    p.lifetime = 120 + (fx_f32() * 60.0) as u32; // 2-3 seconds
                     p.max_lifetime = p.lifetime;
                     p.color = colors[i % colors.len()];
-                    p.size = 0.1 + random_f32() * 0.1;
+                    p.size = 0.1 + fx_f32() * 0.1;
                     break;
                 }
             }
@@ -166,17 +164,17 @@ pub fn spawn_victory_confetti(winner_color: u32) {
 pub fn spawn_bullet_trail(x: f32, y: f32) {
     unsafe {
         // Find an inactive particle
-        for p in &mut PARTICLES {
+        for p in &mut WORLD.fx.particles {
             if !p.active {
                 p.active = true;
-                p.x = x + (random_f32() - 0.5) * 0.1;
-                p.y = y + (random_f32() - 0.5) * 0.1;
-                p.vx = (random_f32() - 0.5) * 0.02;
-                p.vy = (random_f32() - 0.5) * 0.02;
+                p.x = x + (fx_f32() - 0.5) * 0.1;
+                p.y = y + (fx_f32() - 0.5) * 0.1;
+                p.vx = (fx_f32() - 0.5) * 0.02;
+                p.vy = (fx_f32() - 0.5) * 0.02;
                 p.lifetime = 8;
                 p.max_lifetime = 8;
                 // Yellow/orange trail
-                p.color = if random_f32() > 0.5 {
+                p.color = if fx_f32() > 0.5 {
                     0xFFFF00FF
                 } else {
                     0xFFAA00FF
@@ -193,23 +191,23 @@ pub fn spawn_bullet_trail(x: f32, y: f32) {
 pub fn spawn_wall_slide_sparks(x: f32, y: f32, wall_on_right: bool) {
     unsafe {
         // Only spawn occasionally (1 in 3 frames)
-        if random_f32() > 0.33 {
+        if fx_f32() > 0.33 {
             return;
         }
 
-        for p in &mut PARTICLES {
+        for p in &mut WORLD.fx.particles {
             if !p.active {
                 p.active = true;
                 p.x = x;
-                p.y = y + (random_f32() - 0.5) * 0.3;
+                p.y = y + (fx_f32() - 0.5) * 0.3;
                 // Sparks fly away from wall
                 let direction = if wall_on_right { -1.0 } else { 1.0 };
-                p.vx = direction * (0.05 + random_f32() * 0.05);
-                p.vy = random_f32() * 0.03;
+                p.vx = direction * (0.05 + fx_f32() * 0.05);
+                p.vy = fx_f32() * 0.03;
                 p.lifetime = 6;
                 p.max_lifetime = 6;
                 // White/gray sparks
-                p.color = if random_f32() > 0.5 {
+                p.color = if fx_f32() > 0.5 {
                     0xFFFFFFFF
                 } else {
                     0xCCCCCCFF
@@ -226,7 +224,7 @@ pub fn spawn_deflect_burst(x: f32, y: f32) {
     unsafe {
         let particle_count = 10;
         for i in 0..particle_count {
-            for p in &mut PARTICLES {
+            for p in &mut WORLD.fx.particles {
                 if p.active {
                     continue;
                 }
@@ -235,14 +233,71 @@ pub fn spawn_deflect_burst(x: f32, y: f32) {
                 p.y = y;
 
                 let angle = (i as f32 / particle_count as f32) * core::f32::consts::TAU;
-                let speed = 0.08 + random_f32() * 0.10;
+                let speed = 0.08 + fx_f32() * 0.10;
                 p.vx = libm::cosf(angle) * speed;
                 p.vy = libm::sinf(angle) * speed;
 
-                p.lifetime = 12 + (random_f32() * 10.0) as u32;
+                p.lifetime = 12 + (fx_f32() * 10.0) as u32;
                 p.max_lifetime = p.lifetime;
                 p.color = if i % 2 == 0 { 0x00FFFFFF } else { 0xFFFFFFFF };
-                p.size = 0.07 + random_f32() * 0.05;
+                p.size = 0.07 + fx_f32() * 0.05;
+                break;
+            }
+        }
+    }
+}
+
+/// Spawn a hot orange/white spark burst where two bullets clash.
+pub fn spawn_clash_sparks(x: f32, y: f32) {
+    unsafe {
+        let particle_count = 12;
+        for i in 0..particle_count {
+            for p in &mut WORLD.fx.particles {
+                if p.active {
+                    continue;
+                }
+                p.active = true;
+                p.x = x;
+                p.y = y;
+
+                let angle =
+                    (i as f32 / particle_count as f32) * core::f32::consts::TAU + fx_f32() * 0.4;
+                let speed = 0.06 + fx_f32() * 0.14;
+                p.vx = libm::cosf(angle) * speed;
+                p.vy = libm::sinf(angle) * speed;
+
+                p.lifetime = 10 + (fx_f32() * 12.0) as u32;
+                p.max_lifetime = p.lifetime;
+                p.color = if i % 3 == 0 { 0xFFFFFFFF } else { 0xFFA030FF };
+                p.size = 0.05 + fx_f32() * 0.05;
+                break;
+            }
+        }
+    }
+}
+
+/// Spawn a flat splash of dust and sparks where a stomp lands on a head.
+pub fn spawn_stomp_burst(x: f32, y: f32, color: u32) {
+    unsafe {
+        let particle_count = 14;
+        for i in 0..particle_count {
+            for p in &mut WORLD.fx.particles {
+                if p.active {
+                    continue;
+                }
+                p.active = true;
+                p.x = x;
+                p.y = y;
+
+                // Squashed outward spray, mostly sideways
+                let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+                p.vx = side * (0.08 + fx_f32() * 0.12);
+                p.vy = 0.02 + fx_f32() * 0.06;
+
+                p.lifetime = 14 + (fx_f32() * 10.0) as u32;
+                p.max_lifetime = p.lifetime;
+                p.color = if i % 3 == 0 { 0xFFFFFFFF } else { color };
+                p.size = 0.08 + fx_f32() * 0.06;
                 break;
             }
         }
@@ -0,0 +1,181 @@
+//! Bot perception
+//!
+//! Bots don't read `players` and `bullets` directly. Every simulated tick the
+//! world is recorded into a short ring buffer, and each bot looks at the frame
+//! from its own delay ago (longer on easier difficulties). Like people, bots
+//! lead what they saw by that delay, so straight-line motion still reads
+//! right; new shots, deflections, bounces and jumps catch them out. The
+//! buffer is part of `SimState`, so it rolls back with everything else and
+//! has its own checksum subsystem.
+
+use crate::combat::MAX_BULLETS;
+use crate::player::MAX_PLAYERS;
+use crate::weapons::Weapon;
+use crate::world::WORLD;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Longest delay plus the current frame
+pub const PERCEPTION_FRAMES: usize = 13;
+
+// =============================================================================
+// DATA STRUCTURES
+// =============================================================================
+
+/// A player as a bot remembers it
+#[derive(Clone, Copy)]
+pub struct SeenPlayer {
+    pub x: f32, // Bottom-left corner, like players
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub invuln: u32, // Spawn protection ticks left
+    pub alive: bool, // Active and not dead
+}
+
+impl SeenPlayer {
+    pub const fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            invuln: 0,
+            alive: false,
+        }
+    }
+}
+
+/// A bullet as a bot remembers it
+#[derive(Clone, Copy)]
+pub struct SeenBullet {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub owner: u32,
+    pub team: u32,
+    pub kind: Weapon,
+    pub active: bool,
+}
+
+impl SeenBullet {
+    pub const fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            owner: 0,
+            team: 0,
+            kind: Weapon::Pistol,
+            active: false,
+        }
+    }
+}
+
+/// Everything bots can see on one tick
+#[derive(Clone, Copy)]
+pub struct PerceptionFrame {
+    pub players: [SeenPlayer; MAX_PLAYERS],
+    pub bullets: [SeenBullet; MAX_BULLETS],
+}
+
+impl PerceptionFrame {
+    pub const fn new() -> Self {
+        Self {
+            players: [SeenPlayer::new(); MAX_PLAYERS],
+            bullets: [SeenBullet::new(); MAX_BULLETS],
+        }
+    }
+}
+
+/// Ring buffer of recent frames; `head` is the newest
+#[derive(Clone, Copy)]
+pub struct Perception {
+    pub frames: [PerceptionFrame; PERCEPTION_FRAMES],
+    pub head: usize,
+    pub len: usize,
+}
+
+impl Perception {
+    pub const fn new() -> Self {
+        Self {
+            frames: [PerceptionFrame::new(); PERCEPTION_FRAMES],
+            head: 0,
+            len: 0,
+        }
+    }
+}
+
+// =============================================================================
+// RECORDING
+// =============================================================================
+
+/// Forget everything seen (called each round, so nothing leaks across spawns)
+pub fn clear_perception() {
+    unsafe {
+        WORLD.sim.perception.len = 0;
+    }
+}
+
+/// Push the current world into the buffer (called once per simulated tick)
+pub fn record_perception() {
+    unsafe {
+        let sim = &mut WORLD.sim;
+        let buf = &mut sim.perception;
+        buf.head = (buf.head + 1) % PERCEPTION_FRAMES;
+        buf.len = (buf.len + 1).min(PERCEPTION_FRAMES);
+
+        let frame = &mut buf.frames[buf.head];
+        for (seen, p) in frame.players.iter_mut().zip(sim.players.iter()) {
+            *seen = SeenPlayer {
+                x: p.x,
+                y: p.y,
+                vx: p.vx,
+                vy: p.vy,
+                invuln: p.invuln_timer,
+                alive: p.active && !p.dead,
+            };
+        }
+        for (seen, b) in frame.bullets.iter_mut().zip(sim.bullets.iter()) {
+            *seen = SeenBullet {
+                x: b.x,
+                y: b.y,
+                vx: b.vx,
+                vy: b.vy,
+                owner: b.owner,
+                team: b.team,
+                kind: b.kind,
+                active: b.active,
+            };
+        }
+    }
+}
+
+// =============================================================================
+// QUERIES
+// =============================================================================
+
+/// Ticks between the world and what a bot sees of it
+pub fn perception_delay(difficulty: u32) -> u32 {
+    match difficulty {
+        0 => 12,
+        1 => 8,
+        _ => 5,
+    }
+}
+
+/// The frame from `delay` ticks ago (the oldest one early in a round)
+pub fn seen_frame(delay: u32) -> PerceptionFrame {
+    unsafe {
+        let buf = &WORLD.sim.perception;
+        if buf.len == 0 {
+            return PerceptionFrame::new();
+        }
+        let back = (delay as usize).min(buf.len - 1);
+        buf.frames[(buf.head + PERCEPTION_FRAMES - back) % PERCEPTION_FRAMES]
+    }
+}
@@ -0,0 +1,162 @@
+//! Ammo pickups
+//!
+//! Bullets that hit a platform or run out of lifetime drop a spent shell at
+//! that spot. Shells fall onto the floor below, and any player can grab one
+//! to get a round back (up to `MAX_AMMO`).
+
+use crate::audio;
+use crate::player::{aabb_overlap, DEATH_Y, MAX_AMMO, PLAYER_HEIGHT, PLAYER_WIDTH};
+use crate::world::WORLD;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+pub const MAX_PICKUPS: usize = 16;
+pub const SHELL_SIZE: f32 = 0.3;
+pub const SHELL_LIFETIME: u32 = 60 * 20; // Shells vanish after 20 seconds
+const SHELL_GRAVITY: f32 = 0.01;
+const SHELL_MAX_FALL: f32 = 0.3;
+
+// =============================================================================
+// DATA STRUCTURES
+// =============================================================================
+
+#[derive(Clone, Copy)]
+pub struct Pickup {
+    pub x: f32, // Bottom-left corner, like players
+    pub y: f32,
+    pub vy: f32,
+    pub lifetime: u32,
+    pub active: bool,
+}
+
+impl Pickup {
+    pub const fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            vy: 0.0,
+            lifetime: 0,
+            active: false,
+        }
+    }
+}
+
+// =============================================================================
+// SPAWNING
+// =============================================================================
+
+/// Drop a shell centered on (x, y); a full pool recycles the oldest shell
+pub fn spawn_shell(x: f32, y: f32) {
+    unsafe {
+        let pool = &mut WORLD.sim.pickups;
+        let slot = match pool.iter().position(|s| !s.active) {
+            Some(i) => i,
+            None => {
+                let mut oldest = 0;
+                for (i, s) in pool.iter().enumerate() {
+                    if s.lifetime < pool[oldest].lifetime {
+                        oldest = i;
+                    }
+                }
+                oldest
+            }
+        };
+        pool[slot] = Pickup {
+            x: x - SHELL_SIZE * 0.5,
+            y: y - SHELL_SIZE * 0.5,
+            vy: 0.0,
+            lifetime: SHELL_LIFETIME,
+            active: true,
+        };
+    }
+}
+
+pub fn clear_pickups() {
+    unsafe {
+        for s in &mut WORLD.sim.pickups {
+            s.active = false;
+        }
+    }
+}
+
+// =============================================================================
+// UPDATE
+// =============================================================================
+
+/// Fall onto floors, expire, and hand ammo to whoever touches a shell
+pub fn update_pickups() {
+    unsafe {
+        for shell in &mut WORLD.sim.pickups {
+            if !shell.active {
+                continue;
+            }
+
+            shell.lifetime -= 1;
+            if shell.lifetime == 0 {
+                shell.active = false;
+                continue;
+            }
+
+            // Fall and land on anything with a floor (re-checked every tick
+            // so shells ride vertical movers and drop off retracting ones)
+            shell.vy = (shell.vy - SHELL_GRAVITY).max(-SHELL_MAX_FALL);
+            let mut new_y = shell.y + shell.vy;
+            for p in &WORLD.sim.platforms {
+                if !p.active || !p.has_floor() {
+                    continue;
+                }
+                let top = p.y + p.height;
+                let overlaps_x = shell.x + SHELL_SIZE > p.x && shell.x < p.x + p.width;
+                if overlaps_x && shell.y >= top - 0.2 && new_y <= top {
+                    new_y = top;
+                    shell.vy = 0.0;
+                }
+            }
+            shell.y = new_y;
+
+            if shell.y < DEATH_Y {
+                shell.active = false;
+                continue;
+            }
+
+            // Pickup (first player to touch it, if they have room)
+            for p in &mut WORLD.sim.players {
+                if !p.active || p.dead || p.ammo >= MAX_AMMO {
+                    continue;
+                }
+                if aabb_overlap(
+                    (shell.x, shell.y, SHELL_SIZE, SHELL_SIZE),
+                    (p.x, p.y, PLAYER_WIDTH, PLAYER_HEIGHT),
+                ) {
+                    p.ammo += 1;
+                    shell.active = false;
+                    audio::play_pickup(shell.x / 10.0);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Center of the closest shell to (x, y), for bots out of ammo
+pub fn nearest_shell(x: f32, y: f32) -> Option<(f32, f32)> {
+    unsafe {
+        let mut best = None;
+        let mut best_dist_sq = f32::MAX;
+        for shell in &WORLD.sim.pickups {
+            if !shell.active {
+                continue;
+            }
+            let cx = shell.x + SHELL_SIZE * 0.5;
+            let cy = shell.y + SHELL_SIZE * 0.5;
+            let d = (cx - x) * (cx - x) + (cy - y) * (cy - y);
+            if d < best_dist_sq {
+                best_dist_sq = d;
+                best = Some((cx, cy));
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,333 @@
+//! Platform abstraction
+//!
+//! Everything the simulation needs from the outside world (controller input,
+//! the runtime RNG, and the audio sink) goes through the `Platform` trait.
+//! On ZX the trait is backed by the FFI; on any other target it is backed by
+//! `HostPlatform`, a pure-Rust implementation with scripted input so the
+//! gameplay code can run in `cargo test` and host-side tools.
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rng::Rng;
+
+// =============================================================================
+// PLATFORM TRAIT
+// =============================================================================
+
+/// Input, randomness and audio services provided by the runtime
+pub trait Platform {
+    /// Number of connected local/remote players
+    fn player_count(&self) -> u32;
+    /// True on the tick a button went down
+    fn button_pressed(&self, player: u32, button: u32) -> bool;
+    /// True while a button is held
+    fn button_held(&self, player: u32, button: u32) -> bool;
+    /// Raw left stick X (-1.0 to 1.0)
+    fn left_stick_x(&self, player: u32) -> f32;
+    /// Raw left stick Y (-1.0 to 1.0, up is positive)
+    fn left_stick_y(&self, player: u32) -> f32;
+
+    /// Random float in [0, 1)
+    fn random_f32(&mut self) -> f32;
+    /// Random integer in [min, max)
+    fn random_range(&mut self, min: i32, max: i32) -> i32;
+
+    /// Resolve a sound asset handle by ROM name
+    fn load_sound(&mut self, name: &str) -> u32;
+    /// Resolve a tracker (music) asset handle by ROM name
+    fn load_music(&mut self, name: &str) -> u32;
+    /// Fire-and-forget sound effect
+    fn play_sound(&mut self, sound: u32, volume: f32, pan: f32);
+    /// Start a music track
+    fn music_play(&mut self, handle: u32, volume: f32, looping: bool);
+    /// Stop the current music track
+    fn music_stop(&mut self);
+    /// Change the volume of the current music track
+    fn music_set_volume(&mut self, volume: f32);
+}
+
+// =============================================================================
+// ZX IMPLEMENTATION
+// =============================================================================
+
+/// ZX console backend (thin wrapper over the FFI)
+#[cfg(target_arch = "wasm32")]
+pub struct ZxPlatform;
+
+#[cfg(target_arch = "wasm32")]
+impl Platform for ZxPlatform {
+    fn player_count(&self) -> u32 {
+        unsafe { crate::ffi::player_count() }
+    }
+
+    fn button_pressed(&self, player: u32, button: u32) -> bool {
+        unsafe { crate::ffi::button_pressed(player, button) != 0 }
+    }
+
+    fn button_held(&self, player: u32, button: u32) -> bool {
+        unsafe { crate::ffi::button_held(player, button) != 0 }
+    }
+
+    fn left_stick_x(&self, player: u32) -> f32 {
+        unsafe { crate::ffi::left_stick_x(player) }
+    }
+
+    fn left_stick_y(&self, player: u32) -> f32 {
+        unsafe { crate::ffi::left_stick_y(player) }
+    }
+
+    fn random_f32(&mut self) -> f32 {
+        unsafe { crate::ffi::random_f32() }
+    }
+
+    fn random_range(&mut self, min: i32, max: i32) -> i32 {
+        unsafe { crate::ffi::random_range(min, max) }
+    }
+
+    fn load_sound(&mut self, name: &str) -> u32 {
+        crate::ffi::rom_sound_str(name)
+    }
+
+    fn load_music(&mut self, name: &str) -> u32 {
+        crate::ffi::rom_tracker_str(name)
+    }
+
+    fn play_sound(&mut self, sound: u32, volume: f32, pan: f32) {
+        unsafe { crate::ffi::play_sound(sound, volume, pan) }
+    }
+
+    fn music_play(&mut self, handle: u32, volume: f32, looping: bool) {
+        unsafe { crate::ffi::music_play(handle, volume, looping as u32) }
+    }
+
+    fn music_stop(&mut self) {
+        unsafe { crate::ffi::music_stop() }
+    }
+
+    fn music_set_volume(&mut self, volume: f32) {
+        unsafe { crate::ffi::music_set_volume(volume) }
+    }
+}
+
+// =============================================================================
+// HOST IMPLEMENTATION
+// =============================================================================
+
+/// Scripted controller state for one host player
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy)]
+pub struct HostPad {
+    pub held: u32,      // Bitmask of held buttons (1 << BUTTON_*)
+    pub prev_held: u32, // Held mask from the previous tick (for "pressed" edges)
+    pub stick_x: f32,
+    pub stick_y: f32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HostPad {
+    pub const fn new() -> Self {
+        Self {
+            held: 0,
+            prev_held: 0,
+            stick_x: 0.0,
+            stick_y: 0.0,
+        }
+    }
+}
+
+/// Pure-Rust backend: scripted input, xorshift RNG, and a counting audio sink
+#[cfg(not(target_arch = "wasm32"))]
+pub struct HostPlatform {
+    pub connected: u32,
+    pub pads: [HostPad; 4],
+    pub rng: Rng,
+    pub sounds_played: u32,
+    pub music_playing: Option<u32>,
+    next_handle: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HostPlatform {
+    pub const fn new() -> Self {
+        Self {
+            connected: 1,
+            pads: [HostPad::new(); 4],
+            rng: Rng::new(0),
+            sounds_played: 0,
+            music_playing: None,
+            next_handle: 1,
+        }
+    }
+
+    /// Reset input and reseed the RNG
+    pub fn reset(&mut self, connected: u32, seed: u32) {
+        *self = Self::new();
+        self.connected = connected.min(4);
+        self.rng = Rng::new(seed);
+    }
+
+    /// Set the held buttons and stick for a player this tick
+    pub fn set_input(&mut self, player: usize, held: u32, stick_x: f32, stick_y: f32) {
+        let pad = &mut self.pads[player.min(3)];
+        pad.held = held;
+        pad.stick_x = stick_x;
+        pad.stick_y = stick_y;
+    }
+
+    /// Latch this tick's held buttons so the next tick can detect presses
+    pub fn end_tick(&mut self) {
+        for pad in &mut self.pads {
+            pad.prev_held = pad.held;
+        }
+    }
+
+    fn pad(&self, player: u32) -> Option<&HostPad> {
+        if player < self.connected {
+            self.pads.get(player as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Platform for HostPlatform {
+    fn player_count(&self) -> u32 {
+        self.connected
+    }
+
+    fn button_pressed(&self, player: u32, button: u32) -> bool {
+        self.pad(player)
+            .map(|p| (p.held & !p.prev_held) & (1 << button) != 0)
+            .unwrap_or(false)
+    }
+
+    fn button_held(&self, player: u32, button: u32) -> bool {
+        self.pad(player)
+            .map(|p| p.held & (1 << button) != 0)
+            .unwrap_or(false)
+    }
+
+    fn left_stick_x(&self, player: u32) -> f32 {
+        self.pad(player).map(|p| p.stick_x).unwrap_or(0.0)
+    }
+
+    fn left_stick_y(&self, player: u32) -> f32 {
+        self.pad(player).map(|p| p.stick_y).unwrap_or(0.0)
+    }
+
+    fn random_f32(&mut self) -> f32 {
+        self.rng.next_f32()
+    }
+
+    fn random_range(&mut self, min: i32, max: i32) -> i32 {
+        self.rng.range(min, max)
+    }
+
+    fn load_sound(&mut self, _name: &str) -> u32 {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    fn load_music(&mut self, _name: &str) -> u32 {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    fn play_sound(&mut self, _sound: u32, _volume: f32, _pan: f32) {
+        self.sounds_played = self.sounds_played.wrapping_add(1);
+    }
+
+    fn music_play(&mut self, handle: u32, _volume: f32, _looping: bool) {
+        self.music_playing = Some(handle);
+    }
+
+    fn music_stop(&mut self) {
+        self.music_playing = None;
+    }
+
+    fn music_set_volume(&mut self, _volume: f32) {}
+}
+
+// =============================================================================
+// ACTIVE PLATFORM
+// =============================================================================
+
+#[cfg(target_arch = "wasm32")]
+static mut ACTIVE: ZxPlatform = ZxPlatform;
+
+#[cfg(not(target_arch = "wasm32"))]
+static mut ACTIVE: HostPlatform = HostPlatform::new();
+
+fn active() -> &'static mut impl Platform {
+    unsafe { &mut ACTIVE }
+}
+
+/// Host backend, for scripting input from tests and host-side tools
+#[cfg(not(target_arch = "wasm32"))]
+pub fn host() -> &'static mut HostPlatform {
+    unsafe { &mut ACTIVE }
+}
+
+/// Held by tests that drive the simulation, which lives in statics
+#[cfg(test)]
+pub fn test_lock() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    // A failed test poisons the lock, but the next one resets the world anyway
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// =============================================================================
+// CONVENIENCE FUNCTIONS
+// =============================================================================
+
+pub fn player_count() -> u32 {
+    active().player_count()
+}
+
+pub fn button_pressed(player: u32, button: u32) -> bool {
+    active().button_pressed(player, button)
+}
+
+pub fn button_held(player: u32, button: u32) -> bool {
+    active().button_held(player, button)
+}
+
+pub fn left_stick_x(player: u32) -> f32 {
+    active().left_stick_x(player)
+}
+
+pub fn left_stick_y(player: u32) -> f32 {
+    active().left_stick_y(player)
+}
+
+pub fn random_f32() -> f32 {
+    active().random_f32()
+}
+
+pub fn random_range(min: i32, max: i32) -> i32 {
+    active().random_range(min, max)
+}
+
+pub fn load_sound(name: &str) -> u32 {
+    active().load_sound(name)
+}
+
+pub fn load_music(name: &str) -> u32 {
+    active().load_music(name)
+}
+
+pub fn play_sound(sound: u32, volume: f32, pan: f32) {
+    active().play_sound(sound, volume, pan)
+}
+
+pub fn music_play(handle: u32, volume: f32, looping: bool) {
+    active().music_play(handle, volume, looping)
+}
+
+pub fn music_stop() {
+    active().music_stop()
+}
+
+pub fn music_set_volume(volume: f32) {
+    active().music_set_volume(volume)
+}
@@ -3,10 +3,22 @@
 //! Contains Player struct, physics, input handling, and respawn logic.
 
 use crate::audio;
-use crate::combat::{spawn_bullet, BULLETS};
-use crate::ffi::*;
-use crate::game_state::{GamePhase, CONFIG, GAME_STATE};
-use crate::stage::PLATFORMS;
+use crate::bot_profile::bot_profile;
+use crate::combat::{segment_aabb_entry, spawn_bullet};
+use crate::game_state::{
+    are_teammates, can_hurt, match_score, team_of, GamePhase, MatchRule, TEAM_COLORS,
+};
+use crate::hazard;
+use crate::nav::{self, Maneuver, NAV_JUMP_HOLD, NAV_NONE};
+use crate::perception::{perception_delay, seen_frame};
+use crate::platform::{button_held, button_pressed, left_stick_x, left_stick_y};
+use crate::replay;
+use crate::rng::Rng;
+use crate::stage::Collision;
+use crate::stats::{self, KillCause};
+use crate::threat::{self, Response};
+use crate::weapons::Weapon;
+use crate::world::WORLD;
 
 // =============================================================================
 // CONSTANTS
@@ -29,6 +41,8 @@ pub const MOVE_SPEED: f32 = 0.15;
 pub const FRICTION: f32 = 0.85;
 pub const AIR_FRICTION: f32 = 0.95;
 pub const ANALOG_DEADZONE: f32 = 0.2;
+/// Stick axes are whole steps of 1/AXIS_STEPS, so a replay stores each in a byte
+pub const AXIS_STEPS: f32 = 127.0;
 
 // Player dimensions
 pub const PLAYER_WIDTH: f32 = 0.8;
@@ -39,6 +53,7 @@ pub const MAX_AMMO: u32 = 3;
 pub const MELEE_DURATION: u32 = 12; // ticks active
 pub const MELEE_RANGE: f32 = 1.8;
 pub const RESPAWN_DELAY: u32 = 90; // 1.5 seconds
+pub const GHOST_SPEED: f32 = 0.15; // Eliminated players drift around as spectators
 pub const SPAWN_INVULN_FRAMES: u32 = 60; // 1 second
 
 // World bounds (respawn if player falls below this)
@@ -56,6 +71,18 @@ pub const SHOOT_FLASH_DURATION: u32 = 6;
 
 // Melee windup (anticipation frames)
 pub const MELEE_WINDUP_DURATION: u32 = 3;
+// Deflects in the first frames of an active melee are perfect parries
+pub const PERFECT_PARRY_FRAMES: u32 = 2;
+// Stick tilt needed at the start of a melee to slash up or down
+pub const MELEE_DIR_THRESHOLD: f32 = 0.5;
+// Down-slash bounce, a little lower than a full jump
+pub const POGO_FORCE: f32 = 0.42;
+// Pogo lift isn't cut short by releasing jump for this long
+pub const POGO_LIFT_FRAMES: u32 = 12;
+// Landing on the top of an enemy's head kills them (stomp)
+pub const STOMP_HEAD_DEPTH: f32 = 0.35;
+pub const STOMP_FEET_HEIGHT: f32 = 0.2;
+pub const STOMP_BOUNCE: f32 = 0.45;
 
 // Feel polish
 pub const JUMP_BUFFER_FRAMES: u32 = 6;
@@ -64,6 +91,9 @@ pub const DROP_THROUGH_FRAMES: u32 = 10;
 pub const FAST_FALL_THRESHOLD: f32 = -0.75;
 pub const FAST_FALL_MULT: f32 = 1.75;
 
+// Snap distance for resolving solid platform sides
+pub const SIDE_EPSILON: f32 = 0.01;
+
 // Player colors (RGBA)
 pub const PLAYER_COLORS: [u32; 4] = [
     0x00FFFFFF, // Cyan
@@ -74,10 +104,33 @@ pub const PLAYER_COLORS: [u32; 4] = [
 
 pub const MAX_PLAYERS: usize = 4;
 
+/// Display color for a slot, tinted toward its team color in team mode
+pub fn player_color(idx: usize) -> u32 {
+    let base = PLAYER_COLORS[idx];
+    if unsafe { !WORLD.sim.config.team_mode } {
+        return base;
+    }
+    let team = TEAM_COLORS[team_of(idx) as usize];
+    let mix = |shift: u32| {
+        let a = ((base >> shift) & 0xFF) as f32;
+        let b = ((team >> shift) & 0xFF) as f32;
+        ((a * 0.4 + b * 0.6) as u32) << shift
+    };
+    mix(24) | mix(16) | mix(8) | 0xFF
+}
+
 // =============================================================================
 // DATA STRUCTURES
 // =============================================================================
 
+/// Which way a melee swing points, picked when the button is pressed
+#[derive(Clone, Copy, PartialEq)]
+pub enum MeleeDir {
+    Side,
+    Up,
+    Down, // Only in the air; connecting bounces the attacker (pogo)
+}
+
 #[derive(Clone, Copy)]
 pub struct Player {
     // Position and velocity
@@ -89,14 +142,20 @@ pub struct Player {
     // State
     pub on_ground: bool,
     pub facing_right: bool,
+    pub aim_x: f32, // Held direction this tick (aims deflects)
+    pub aim_y: f32,
     pub active: bool,
     pub ready: bool,
     pub is_bot: bool,
 
     // Combat
     pub ammo: u32,
+    pub weapon: Weapon,    // Crate weapon (Pistol = none)
+    pub weapon_shots: u32, // Shots left before falling back to the pistol
     pub melee_timer: u32,  // > 0 means melee is active
     pub melee_windup: u32, // Anticipation frames before melee hitbox activates
+    pub melee_dir: MeleeDir,
+    pub pogo_timer: u32, // > 0 while rising from a pogo or stomp bounce
     pub dead: bool,
     pub respawn_timer: u32,
     pub invuln_timer: u32,
@@ -111,6 +170,12 @@ pub struct Player {
     pub ai_shoot_cooldown: u32,
     pub ai_melee_cooldown: u32,
     pub ai_jump_hold: u32,
+    pub ai_nav_edge: u32,    // Nav graph move in progress (NAV_NONE when none)
+    pub ai_rng: Rng,         // Reaction times and aim jitter (reseeded each round)
+    pub ai_noticed: u32,     // Bullet slots the bot has reacted to (bitmask)
+    pub ai_ignored: u32,     // Noticed bullets its profile chose not to parry
+    pub ai_react_timer: u32, // Ticks until newly seen bullets are noticed
+    pub ai_parry_lead: u32,  // Live swing frames a parry lets pass before meeting a bullet
 
     // Effects
     pub spawn_flash: u32,    // Countdown timer for spawn flash effect
@@ -121,6 +186,9 @@ pub struct Player {
 
     // Score
     pub kills: u32,
+    pub stocks: u32,      // Lives left (Stock rule)
+    pub eliminated: bool, // Out of stocks; spectates as a ghost
+    pub hill_ticks: u32,  // Ticks holding the zone alone (Hill rule)
 }
 
 impl Player {
@@ -132,12 +200,18 @@ impl Player {
             vy: 0.0,
             on_ground: false,
             facing_right: true,
+            aim_x: 0.0,
+            aim_y: 0.0,
             active: false,
             ready: false,
             is_bot: false,
             ammo: MAX_AMMO,
+            weapon: Weapon::Pistol,
+            weapon_shots: 0,
             melee_timer: 0,
             melee_windup: 0,
+            melee_dir: MeleeDir::Side,
+            pogo_timer: 0,
             dead: false,
             respawn_timer: 0,
             invuln_timer: 0,
@@ -148,22 +222,25 @@ impl Player {
             ai_shoot_cooldown: 0,
             ai_melee_cooldown: 0,
             ai_jump_hold: 0,
+            ai_nav_edge: NAV_NONE,
+            ai_rng: Rng::new(0),
+            ai_noticed: 0,
+            ai_ignored: 0,
+            ai_react_timer: 0,
+            ai_parry_lead: 0,
             spawn_flash: 0,
             shoot_flash: 0,
             squash_stretch: 0.0,
             prev_positions: [(0.0, 0.0); TRAIL_COUNT],
             prev_idx: 0,
             kills: 0,
+            stocks: 0,
+            eliminated: false,
+            hill_ticks: 0,
         }
     }
 }
 
-// =============================================================================
-// GLOBAL STATE
-// =============================================================================
-
-pub static mut PLAYERS: [Player; MAX_PLAYERS] = [Player::new(); MAX_PLAYERS];
-
 // =============================================================================
 // HELPER FUNCTIONS
 // =============================================================================
@@ -203,14 +280,45 @@ fn apply_deadzone(v: f32) -> f32 {
     }
 }
 
-#[derive(Clone, Copy)]
-struct Controls {
-    x: f32,
-    y: f32,
-    jump_pressed: bool,
-    jump_held: bool,
-    shoot_pressed: bool,
-    melee_pressed: bool,
+/// Stick axis as a signed step count (see `AXIS_STEPS`)
+pub fn axis_step(v: f32) -> i8 {
+    libm::roundf(v.clamp(-1.0, 1.0) * AXIS_STEPS) as i8
+}
+
+pub fn axis_from_step(step: i8) -> f32 {
+    step as f32 / AXIS_STEPS
+}
+
+/// One player's input for one tick (what replays record)
+#[derive(Clone, Copy, PartialEq)]
+pub struct Controls {
+    pub x: f32,
+    pub y: f32,
+    pub jump_pressed: bool,
+    pub jump_held: bool,
+    pub shoot_pressed: bool,
+    pub melee_pressed: bool,
+}
+
+impl Controls {
+    pub const fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            jump_pressed: false,
+            jump_held: false,
+            shoot_pressed: false,
+            melee_pressed: false,
+        }
+    }
+
+    /// Snap both axes to the stick resolution, so the sim never sees input
+    /// finer than a replay can hold
+    pub fn quantized(mut self) -> Self {
+        self.x = axis_from_step(axis_step(self.x));
+        self.y = axis_from_step(axis_step(self.y));
+        self
+    }
 }
 
 // =============================================================================
@@ -219,33 +327,42 @@ struct Controls {
 
 pub fn spawn_players() {
     unsafe {
-        for i in 0..MAX_PLAYERS {
-            if PLAYERS[i].active {
+        for (i, p) in WORLD.sim.players.iter_mut().enumerate() {
+            if p.active {
                 // Get stage-specific spawn position for this player
                 let (sx, sy) = crate::stage::get_spawn_position(i);
-                let kills = PLAYERS[i].kills;
-                let ready = PLAYERS[i].ready;
-                let is_bot = PLAYERS[i].is_bot;
-                let ai_seed = if PLAYERS[i].ai_seed != 0 {
-                    PLAYERS[i].ai_seed
+                let kills = p.kills;
+                let stocks = p.stocks;
+                let eliminated = p.eliminated;
+                let hill_ticks = p.hill_ticks;
+                let ready = p.ready;
+                let is_bot = p.is_bot;
+                let ai_seed = if p.ai_seed != 0 {
+                    p.ai_seed
                 } else {
-                    // Deterministic but varied per slot.
-                    (i as u32 + 1).wrapping_mul(1_103_515_245) ^ crate::game_state::TICK
+                    // Deterministic (seeded gameplay RNG) but varied per slot and match.
+                    (i as u32 + 1).wrapping_mul(1_103_515_245) ^ WORLD.sim.rng.next_u32()
                 };
-                PLAYERS[i] = Player {
+                *p = Player {
                     x: sx,
                     y: sy,
                     vx: 0.0,
                     vy: 0.0,
                     on_ground: false,
                     facing_right: i % 2 == 0,
+                    aim_x: 0.0,
+                    aim_y: 0.0,
                     active: true,
                     ready,
                     is_bot,
                     ammo: MAX_AMMO,
+                    weapon: Weapon::Pistol,
+                    weapon_shots: 0,
                     melee_timer: 0,
                     melee_windup: 0,
-                    dead: false,
+                    melee_dir: MeleeDir::Side,
+                    pogo_timer: 0,
+                    dead: eliminated, // Eliminated players come back as ghosts
                     respawn_timer: 0,
                     invuln_timer: SPAWN_INVULN_FRAMES,
                     jump_buffer: 0,
@@ -255,17 +372,28 @@ pub fn spawn_players() {
                     ai_shoot_cooldown: 0,
                     ai_melee_cooldown: 0,
                     ai_jump_hold: 0,
+                    ai_nav_edge: NAV_NONE,
+                    ai_rng: Rng::new(ai_seed ^ WORLD.sim.round_number.wrapping_mul(0x9E37_79B9)),
+                    ai_noticed: 0,
+                    ai_ignored: 0,
+                    ai_react_timer: 0,
+                    ai_parry_lead: 0,
                     spawn_flash: 30, // Spawn flash effect (0.5 seconds at 60fps)
                     shoot_flash: 0,
                     squash_stretch: 0.0,
                     prev_positions: [(sx, sy); TRAIL_COUNT],
                     prev_idx: 0,
-                    kills, // Preserve kills across rounds
+                    kills, // Preserve scores/stocks across rounds
+                    stocks,
+                    eliminated,
+                    hill_ticks,
                 };
                 // Play spawn sound with pan based on x position
-                audio::play_spawn(sx / 10.0);
+                if !eliminated {
+                    audio::play_spawn(sx / 10.0);
+                }
             } else {
-                PLAYERS[i].active = false;
+                p.active = false;
             }
         }
     }
@@ -273,17 +401,18 @@ pub fn spawn_players() {
 
 fn check_wall_collision(x: f32, y_min: f32, y_max: f32) -> bool {
     unsafe {
-        for platform in &PLATFORMS {
-            if !platform.active {
+        for platform in &WORLD.sim.platforms {
+            if !platform.active || !platform.has_sides() {
                 continue;
             }
 
-            // Check if x is within platform horizontal bounds
-            // and y range overlaps with platform
+            // Check if x is within platform horizontal bounds and the y
+            // range overlaps the platform (strictly, so the floor under
+            // the player's feet doesn't count as a wall)
             if x >= platform.x
                 && x <= platform.x + platform.width
-                && y_max >= platform.y
-                && y_min <= platform.y + platform.height
+                && y_max > platform.y
+                && y_min < platform.y + platform.height
             {
                 return true;
             }
@@ -292,194 +421,286 @@ fn check_wall_collision(x: f32, y_min: f32, y_max: f32) -> bool {
     }
 }
 
-fn read_human_controls(idx: usize) -> Controls {
+/// True if the player's feet rest on a solid platform (no drop-through)
+fn standing_on_solid(x: f32, y: f32) -> bool {
     unsafe {
-        let stick_x = apply_deadzone(left_stick_x(idx as u32));
-        let stick_y = apply_deadzone(left_stick_y(idx as u32));
-
-        // Also check d-pad for digital input
-        let dpad_h = if button_held(idx as u32, BUTTON_RIGHT) != 0 {
-            1.0
-        } else if button_held(idx as u32, BUTTON_LEFT) != 0 {
-            -1.0
-        } else {
-            0.0
-        };
-        let dpad_v = if button_held(idx as u32, BUTTON_UP) != 0 {
-            1.0
-        } else if button_held(idx as u32, BUTTON_DOWN) != 0 {
-            -1.0
-        } else {
-            0.0
-        };
+        WORLD.sim.platforms.iter().any(|platform| {
+            platform.active
+                && platform.collision == Collision::Solid
+                && abs(y - (platform.y + platform.height)) < SIDE_EPSILON
+                && x < platform.x + platform.width
+                && x + PLAYER_WIDTH > platform.x
+        })
+    }
+}
 
-        // Combine analog and digital
-        let input_x = if abs(stick_x) > abs(dpad_h) {
-            stick_x
-        } else {
-            dpad_h
-        };
-        let input_y = if abs(stick_y) > abs(dpad_v) {
-            stick_y
-        } else {
-            dpad_v
-        };
+fn read_human_controls(idx: usize) -> Controls {
+    let stick_x = apply_deadzone(left_stick_x(idx as u32));
+    let stick_y = apply_deadzone(left_stick_y(idx as u32));
+
+    // Also check d-pad for digital input
+    let dpad_h = if button_held(idx as u32, BUTTON_RIGHT) {
+        1.0
+    } else if button_held(idx as u32, BUTTON_LEFT) {
+        -1.0
+    } else {
+        0.0
+    };
+    let dpad_v = if button_held(idx as u32, BUTTON_UP) {
+        1.0
+    } else if button_held(idx as u32, BUTTON_DOWN) {
+        -1.0
+    } else {
+        0.0
+    };
 
-        Controls {
-            x: input_x,
-            y: input_y,
-            jump_pressed: button_pressed(idx as u32, BUTTON_A) != 0,
-            jump_held: button_held(idx as u32, BUTTON_A) != 0,
-            shoot_pressed: button_pressed(idx as u32, BUTTON_B) != 0,
-            melee_pressed: button_pressed(idx as u32, BUTTON_X) != 0,
-        }
+    // Combine analog and digital
+    let input_x = if abs(stick_x) > abs(dpad_h) {
+        stick_x
+    } else {
+        dpad_h
+    };
+    let input_y = if abs(stick_y) > abs(dpad_v) {
+        stick_y
+    } else {
+        dpad_v
+    };
+
+    Controls {
+        x: input_x,
+        y: input_y,
+        jump_pressed: button_pressed(idx as u32, BUTTON_A),
+        jump_held: button_held(idx as u32, BUTTON_A),
+        shoot_pressed: button_pressed(idx as u32, BUTTON_B),
+        melee_pressed: button_pressed(idx as u32, BUTTON_X),
     }
 }
 
+/// Bots drop an errand (ammo, crate, hill) to fight enemies closer than this
+/// (at balanced aggression; it doubles at full aggression)
+const AI_ENGAGE_DIST: f32 = 3.0;
+/// Ticks between a camper's decisions to hold its platform or move
+const AI_CAMP_WINDOW: u32 = 90;
+/// Per-tick chance to hop while moving, at full jump frequency
+const AI_HOP_CHANCE: f32 = 0.05;
+/// Live swing frame bots aim parries at, the middle of the swing
+const AI_PARRY_AIM: u32 = (MELEE_DURATION - 1) / 2;
+/// Stick y for shooting or slashing down in the air: past the aim and
+/// slash thresholds but short of a fast fall
+const AI_AIR_DOWN: f32 = -0.6;
+
 fn ai_controls(idx: usize) -> Controls {
     unsafe {
-        let p = &mut PLAYERS[idx];
+        let p = &mut WORLD.sim.players[idx];
 
         // Cooldowns / timers
         p.ai_shoot_cooldown = p.ai_shoot_cooldown.saturating_sub(1);
         p.ai_melee_cooldown = p.ai_melee_cooldown.saturating_sub(1);
         p.ai_jump_hold = p.ai_jump_hold.saturating_sub(1);
 
-        let difficulty = CONFIG.bot_difficulty.min(2);
-        let shoot_cd = match difficulty {
-            0 => 40,
-            1 => 25,
-            _ => 15,
-        };
-        let melee_cd = match difficulty {
-            0 => 30,
-            1 => 20,
-            _ => 12,
-        };
-
-        // Pick nearest target
+        // Difficulty sets how well the bot plays, its profile how it plays
+        let difficulty = WORLD.sim.config.bot_difficulty.min(2);
+        let profile = bot_profile(WORLD.sim.config.bot_profiles[idx]);
+        let cooldown_scale = 1.5 - profile.aggression; // 1.0 when balanced
+        let shoot_cd = (match difficulty {
+            0 => 40.0,
+            1 => 25.0,
+            _ => 15.0,
+        } * cooldown_scale) as u32;
+        let melee_cd = (match difficulty {
+            0 => 30.0,
+            1 => 20.0,
+            _ => 12.0,
+        } * cooldown_scale) as u32;
+
+        // Others are seen as they were `delay` ticks ago, led along their
+        // velocity (only sideways for players, whose arcs don't extrapolate)
+        let delay = perception_delay(difficulty);
+        let lead = delay as f32;
+        let seen = seen_frame(delay);
+
+        // Pick nearest target, passing over ones still spawn protected
         let px = p.x + PLAYER_WIDTH * 0.5;
         let py = p.y + PLAYER_HEIGHT * 0.5;
-        let mut target_idx: Option<usize> = None;
+        let mut target: Option<(f32, f32)> = None;
+        let mut target_invuln = 0;
         let mut best_dist_sq = 1.0e12_f32;
-        for (i, other) in PLAYERS.iter().enumerate() {
-            if i == idx || !other.active || other.dead {
+        for (i, other) in seen.players.iter().enumerate() {
+            if i == idx || !other.alive || are_teammates(i, idx) {
                 continue;
             }
-            let ox = other.x + PLAYER_WIDTH * 0.5;
+            let ox = other.x + other.vx * lead + PLAYER_WIDTH * 0.5;
             let oy = other.y + PLAYER_HEIGHT * 0.5;
             let dx = ox - px;
             let dy = oy - py;
             let d = dx * dx + dy * dy;
-            if d < best_dist_sq {
+            let invuln = other.invuln.saturating_sub(delay); // Protection left by now
+            if target.is_none() || (invuln > 0, d) < (target_invuln > 0, best_dist_sq) {
                 best_dist_sq = d;
-                target_idx = Some(i);
+                target = Some((ox, oy));
+                target_invuln = invuln;
             }
         }
 
+        // Newly seen enemy bullets only register after a reaction time, and
+        // then the profile decides whether to parry them
+        let mut visible = 0u32;
+        for (i, b) in seen.bullets.iter().enumerate() {
+            if b.active && b.owner != idx as u32 && can_hurt(b.team, idx) {
+                visible |= 1 << i;
+            }
+        }
+        p.ai_noticed &= visible;
+        p.ai_ignored &= visible;
+        let unnoticed = visible & !(p.ai_noticed | p.ai_ignored);
+        if unnoticed != 0 {
+            if p.ai_react_timer == 0 {
+                p.ai_react_timer = reaction_ticks(p, difficulty) + 1;
+            }
+            p.ai_react_timer -= 1;
+            if p.ai_react_timer == 0 {
+                if p.ai_rng.next_f32() < profile.parry {
+                    p.ai_noticed |= unnoticed;
+                    p.ai_parry_lead = parry_lead(p, difficulty);
+                } else {
+                    p.ai_ignored |= unnoticed;
+                }
+            }
+        }
+
+        // Errands: restock from spent shells when low, grab a weapon crate,
+        // then contest the hill; only turn to fight when an enemy gets close
+        let mut errand: Option<(f32, f32)> = None;
+        let engage_dist = AI_ENGAGE_DIST * 2.0 * profile.aggression;
+        if best_dist_sq > engage_dist * engage_dist {
+            let pistol = p.weapon == Weapon::Pistol;
+            let low = p.ammo == 0 || (p.ammo as f32) < profile.ammo * MAX_AMMO as f32;
+            if pistol && low {
+                errand = crate::pickups::nearest_shell(px, py);
+            }
+            if errand.is_none() && pistol {
+                errand = crate::weapons::nearest_crate(px, py);
+            }
+            if errand.is_none() && WORLD.sim.hill.active {
+                errand = Some(crate::stage::hill_center());
+            }
+        }
+        let engaging = errand.is_none();
+        let goal = errand.or(target);
+
         // Default: idle
         let mut input_x = 0.0;
         let mut input_y = 0.0;
         let mut jump_pressed = false;
         let mut melee_pressed = false;
         let mut shoot_pressed = false;
+        let mut aim_x = 0.0;
+        let mut aim_y = 0.0;
+        let mut nav_jump = false;
+
+        // Known bullets come first: parry the ones it means to, and get out
+        // of the way of the rest. Shots from above get an up-slash; in the
+        // air, shots from below get a down-slash to pogo off them.
+        let response = threat::respond(p, &seen, delay);
+        let mut melee_dir = MeleeDir::Side;
+        let mut parry_face = 0.0;
+        if let Some(Response::Parry(bdx, bdy)) = response {
+            melee_pressed = true;
+            melee_dir = vertical_slash(p, bdx, bdy);
+            parry_face = aim_axis(bdx, 0.1);
+            p.ai_melee_cooldown = melee_cd;
+        }
+        let dodge = match response {
+            Some(Response::Dodge(m)) => Some(m),
+            _ => None,
+        };
 
-        if let Some(ti) = target_idx {
-            let t = &PLAYERS[ti];
-            let tx = t.x + PLAYER_WIDTH * 0.5;
-            let ty = t.y + PLAYER_HEIGHT * 0.5;
+        if let Some((tx, ty)) = goal {
             let dx = tx - px;
             let dy = ty - py;
 
             // Aim toward target (8-way snap happens in spawn_bullet).
-            input_x = if abs(dx) > 0.25 {
-                if dx > 0.0 {
-                    1.0
-                } else {
-                    -1.0
-                }
-            } else {
-                0.0
-            };
-            input_y = if abs(dy) > 0.35 {
-                if dy > 0.0 {
-                    1.0
-                } else {
-                    -1.0
-                }
+            input_x = aim_axis(dx, 0.25);
+            input_y = aim_axis(dy, 0.35);
+
+            // Movement: approach, but back off inside the profile's range now
+            // and then to feel less robotic. Never follow anything out to
+            // where the overtime walls will be.
+            let mode = (WORLD.sim.tick / 45).wrapping_add(p.ai_seed) % 4;
+            let away = if dx > 0.0 { -1.0 } else { 1.0 };
+            let want_away = engaging
+                && abs(dx) < profile.range
+                && (mode == 1 || mode == 2)
+                && nav::retreat_is_safe(p, away);
+            let move_tx = hazard::safe_goal_x(tx);
+            let move_dir = if want_away {
+                away
             } else {
-                0.0
+                aim_axis(move_tx - px, 0.35)
             };
-
-            // Movement: approach, but add a little strafe to feel less robotic.
-            let mode = (crate::game_state::TICK / 45).wrapping_add(p.ai_seed) % 4;
-            let prefer_distance = match difficulty {
-                0 => 2.8,
-                1 => 2.3,
-                _ => 1.8,
-            };
-            let want_away = abs(dx) < prefer_distance && (mode == 1 || mode == 2);
-            let move_dir = if want_away {
-                if dx > 0.0 {
-                    -1.0
-                } else {
-                    1.0
-                }
-            } else if abs(dx) > 0.35 {
-                if dx > 0.0 {
-                    1.0
-                } else {
-                    -1.0
+            // Campers hold their platform for stretches instead of chasing
+            let window = (WORLD.sim.tick / AI_CAMP_WINDOW).wrapping_add(p.ai_seed);
+            let camp_roll = (window.wrapping_mul(2_654_435_761) >> 16) % 100;
+            let camping = engaging && p.on_ground && (camp_roll as f32) < profile.camping * 100.0;
+
+            // Other platforms are reached along the nav graph; on our own
+            // platform, close in directly
+            aim_x = input_x;
+            aim_y = input_y;
+            if camping {
+                input_x = if want_away { move_dir } else { 0.0 };
+            } else if let Some(step) = nav::route_input(p, (move_tx, ty)) {
+                input_x = step.x;
+                input_y = if step.drop { -1.0 } else { 0.0 };
+                nav_jump = step.jump || step.drop;
+                jump_pressed = nav_jump;
+                if step.jump {
+                    p.ai_jump_hold = NAV_JUMP_HOLD;
                 }
             } else {
-                0.0
-            };
-            if input_x == 0.0 {
                 input_x = move_dir;
-            }
 
-            // Jump to chase verticality.
-            if p.on_ground && dy > 1.0 && abs(dx) < 5.0 {
-                jump_pressed = true;
-                p.ai_jump_hold = 8;
+                // Jump to chase verticality, and hop around as the profile likes
+                let hop = profile.jump > 0.0
+                    && input_x != 0.0
+                    && p.ai_rng.next_f32() < profile.jump * AI_HOP_CHANCE;
+                if p.on_ground
+                    && ((dy > 1.0 && abs(dx) < 5.0) || hop)
+                    && nav::jump_is_safe(p, input_x)
+                {
+                    jump_pressed = true;
+                    p.ai_jump_hold = 8;
+                }
             }
 
-            // Defensive parry: if an enemy bullet is close, swing.
-            if p.ai_melee_cooldown == 0 {
-                let mut bullet_threat = false;
-                for b in &BULLETS {
-                    if !b.active || b.owner == idx as u32 {
-                        continue;
-                    }
-                    let ddx = b.x - px;
-                    let ddy = b.y - py;
-                    let dist_sq = ddx * ddx + ddy * ddy;
-                    let r = MELEE_RANGE * 1.15;
-                    if dist_sq < r * r {
-                        bullet_threat = true;
-                        break;
-                    }
-                }
-                if bullet_threat {
+            // Offensive melee when close: beside (from the ground, so the dash
+            // and clashes happen over a floor), overhead, or below while
+            // airborne. Not while the target's spawn protection would absorb it.
+            if engaging
+                && dodge.is_none()
+                && !melee_pressed
+                && p.ai_melee_cooldown == 0
+                && target_invuln <= MELEE_WINDUP_DURATION
+            {
+                let dir = vertical_slash(p, dx, dy);
+                if dir != MeleeDir::Side || (p.on_ground && abs(dx) < 1.7 && abs(dy) < 1.2) {
                     melee_pressed = true;
+                    melee_dir = dir;
                     p.ai_melee_cooldown = melee_cd;
                 }
             }
 
-            // Offensive melee when close.
-            if !melee_pressed && p.ai_melee_cooldown == 0 && abs(dx) < 1.7 && abs(dy) < 1.2 {
-                melee_pressed = true;
-                p.ai_melee_cooldown = melee_cd;
-            }
-
             // Shoot when not in melee and target is reasonably aligned.
-            if p.ai_shoot_cooldown == 0
-                && p.ammo > 0
+            if engaging
+                && p.ai_shoot_cooldown == 0
+                && (p.ammo > 0 || p.weapon != Weapon::Pistol)
                 && p.melee_timer == 0
                 && p.melee_windup == 0
-                && abs(dx) < 10.0
+                && abs(dx) < 10.0 - 6.0 * profile.ammo
                 && abs(dy) < 6.0
                 && !melee_pressed
+                && !nav_jump
+                && dodge.is_none()
             {
                 // Easy bots whiff more by requiring clearer alignment.
                 let aim_ok = match difficulty {
@@ -488,10 +709,86 @@ fn ai_controls(idx: usize) -> Controls {
                     _ => true,
                 };
                 if aim_ok {
-                    shoot_pressed = true;
-                    p.ai_shoot_cooldown = shoot_cd;
+                    // Hands aren't perfect: aim at a point near the target
+                    let jitter = aim_jitter(difficulty);
+                    let jx = (p.ai_rng.next_f32() * 2.0 - 1.0) * jitter;
+                    let jy = (p.ai_rng.next_f32() * 2.0 - 1.0) * jitter;
+                    let shot = (aim_axis(dx + jx, 0.25), aim_axis(dy + jy, 0.35));
+
+                    // Don't waste ammo on a platform in the way, or on spawn
+                    // protection that outlasts the shot's flight
+                    let reach = libm::sqrtf(dx * dx + dy * dy) - PLAYER_WIDTH * 0.5;
+                    let flight = reach / p.weapon.speed();
+                    if target_invuln as f32 <= flight
+                        && threat::shot_is_clear(p, shot, p.weapon, reach)
+                    {
+                        shoot_pressed = true;
+                        p.ai_shoot_cooldown = shoot_cd;
+                        (aim_x, aim_y) = shot;
+                    }
                 }
             }
+        } else if !p.on_ground {
+            // Nothing left to go after: still see the jump or drop through
+            if let Some(step) = nav::route_input(p, (px, py)) {
+                input_x = step.x;
+            }
+        }
+
+        // Closing overtime walls: head back to where it's safe, jumping if
+        // the way in is off a ledge
+        let escape = hazard::wall_escape(p.x);
+        if escape != 0.0 && dodge.is_none() {
+            input_x = escape;
+            input_y = 0.0;
+            nav_jump = p.on_ground && !nav::walk_is_safe(p, escape) && nav::jump_is_safe(p, escape);
+            jump_pressed = nav_jump;
+            if nav_jump {
+                p.ai_jump_hold = NAV_JUMP_HOLD;
+            }
+        }
+
+        // Dodges override the plan for as long as they're needed
+        if let Some(m) = dodge {
+            input_x = m.input_x();
+            input_y = if m == Maneuver::Drop { -1.0 } else { 0.0 };
+            nav_jump = matches!(m, Maneuver::Jump(_) | Maneuver::Drop);
+            jump_pressed = nav_jump;
+            if nav_jump && m != Maneuver::Drop {
+                p.ai_jump_hold = NAV_JUMP_HOLD;
+            }
+        }
+
+        // The stick picks the swing direction on the press tick, and a
+        // parry turns to face the bullet
+        if melee_pressed {
+            input_y = match melee_dir {
+                MeleeDir::Side => 0.0,
+                MeleeDir::Up => 1.0,
+                MeleeDir::Down => -1.0,
+            };
+        }
+        if matches!(response, Some(Response::Parry(..))) {
+            aim_x = parry_face;
+        }
+
+        // Shots and side slashes face the target for their tick
+        if (shoot_pressed || melee_pressed) && !nav_jump {
+            input_x = aim_x;
+            if shoot_pressed {
+                input_y = aim_y;
+            }
+        }
+
+        // A full down tilt in the air would also fast-fall the bot short of
+        // the landing its route counted on
+        if !p.on_ground && (shoot_pressed || melee_pressed) {
+            input_y = input_y.max(AI_AIR_DOWN);
+        }
+
+        // Never run off into the pit or up to a wall
+        if !nav::walk_is_safe(p, input_x) {
+            input_x = 0.0;
         }
 
         Controls {
@@ -505,38 +802,102 @@ fn ai_controls(idx: usize) -> Controls {
     }
 }
 
+/// Full stick toward `d` along one axis, or neutral within `threshold`
+fn aim_axis(d: f32, threshold: f32) -> f32 {
+    if abs(d) > threshold {
+        if d > 0.0 {
+            1.0
+        } else {
+            -1.0
+        }
+    } else {
+        0.0
+    }
+}
+
+/// Live swing frame a bot sets out to meet a bullet on: mid-swing, off by
+/// up to the difficulty's timing error either way. Early frames (perfect
+/// parries) only come from a late swing.
+fn parry_lead(p: &mut Player, difficulty: u32) -> u32 {
+    let error = match difficulty {
+        0 => 4, // Frames 1-9
+        1 => 3, // 2-8
+        _ => 2, // 3-7
+    };
+    AI_PARRY_AIM - error
+        + p.ai_rng.range(0, error as i32 + 1) as u32
+        + p.ai_rng.range(0, error as i32 + 1) as u32
+}
+
+/// Ticks a bot takes to notice something new: the sum of two rolls, so
+/// reactions cluster around the middle of the difficulty's range
+fn reaction_ticks(p: &mut Player, difficulty: u32) -> u32 {
+    let (min, roll) = match difficulty {
+        0 => (10, 9), // 10-26 ticks
+        1 => (6, 6),  // 6-16
+        _ => (2, 4),  // 2-8
+    };
+    min + p.ai_rng.range(0, roll) as u32 + p.ai_rng.range(0, roll) as u32
+}
+
+/// Largest offset from the target a bot's shot is aimed at
+fn aim_jitter(difficulty: u32) -> f32 {
+    match difficulty {
+        0 => 0.9,
+        1 => 0.5,
+        _ => 0.25,
+    }
+}
+
+/// Vertical slash a bot should use on something at (dx, dy) from its center
+fn vertical_slash(p: &Player, dx: f32, dy: f32) -> MeleeDir {
+    let reach = PLAYER_HEIGHT * 0.5 + MELEE_RANGE;
+    if abs(dx) > PLAYER_WIDTH || abs(dy) < PLAYER_HEIGHT * 0.75 || abs(dy) > reach {
+        MeleeDir::Side
+    } else if dy > 0.0 {
+        MeleeDir::Up
+    } else if !p.on_ground {
+        MeleeDir::Down
+    } else {
+        MeleeDir::Side
+    }
+}
+
 fn read_controls(idx: usize) -> Controls {
     unsafe {
-        if PLAYERS[idx].is_bot {
+        // Bots still think during playback so their internal state matches
+        let live = if WORLD.sim.players[idx].is_bot {
             ai_controls(idx)
         } else {
             read_human_controls(idx)
         }
+        .quantized();
+
+        if let Some(recorded) = replay::playback_controls(idx) {
+            return recorded;
+        }
+        replay::record_controls(idx, &live);
+        live
     }
 }
 
 fn choose_safe_respawn_position(player_idx: usize) -> (f32, f32) {
     unsafe {
-        let stage = (GAME_STATE.current_stage as usize).min(2);
+        let spawns = crate::stage::spawn_count();
 
-        let preferred = ((crate::game_state::TICK / 30) as usize + player_idx) % 4;
+        let preferred = ((WORLD.sim.tick / 30) as usize + player_idx) % spawns;
         let mut best_score = -1.0_f32;
-        let mut best = (
-            crate::stage::SPAWN_POINTS[stage][0],
-            crate::stage::SPAWN_POINTS[stage][1],
-        );
+        let mut best = crate::stage::spawn_point(0);
 
-        for k in 0..4 {
-            let si = (preferred + k) % 4;
-            let sx = crate::stage::SPAWN_POINTS[stage][si * 2];
-            let sy = crate::stage::SPAWN_POINTS[stage][si * 2 + 1];
+        for k in 0..spawns {
+            let (sx, sy) = crate::stage::spawn_point(preferred + k);
 
             let scx = sx + PLAYER_WIDTH * 0.5;
             let scy = sy + PLAYER_HEIGHT * 0.5;
 
             // Closest living opponent
             let mut min_player = 1.0e12_f32;
-            for (i, other) in PLAYERS.iter().enumerate() {
+            for (i, other) in WORLD.sim.players.iter().enumerate() {
                 if i == player_idx || !other.active || other.dead {
                     continue;
                 }
@@ -552,7 +913,7 @@ fn choose_safe_respawn_position(player_idx: usize) -> (f32, f32) {
 
             // Closest active bullet
             let mut min_bullet = 1.0e12_f32;
-            for b in &BULLETS {
+            for b in &WORLD.sim.bullets {
                 if !b.active {
                     continue;
                 }
@@ -580,14 +941,34 @@ fn choose_safe_respawn_position(player_idx: usize) -> (f32, f32) {
     }
 }
 
+/// Free-flying spectator for an eliminated player (no collision, can't act)
+fn update_ghost(idx: usize) {
+    unsafe {
+        // Bots just hover where they spawned
+        if WORLD.sim.players[idx].is_bot {
+            return;
+        }
+        let c = read_controls(idx);
+        let p = &mut WORLD.sim.players[idx];
+        p.x = (p.x + c.x * GHOST_SPEED).clamp(
+            WORLD.sim.game.arena_left,
+            WORLD.sim.game.arena_right - PLAYER_WIDTH,
+        );
+        p.y = (p.y + c.y * GHOST_SPEED).clamp(DEATH_Y, 8.0);
+        if c.x != 0.0 {
+            p.facing_right = c.x > 0.0;
+        }
+    }
+}
+
 fn overtime_killer_for(victim_idx: usize) -> u32 {
     unsafe {
-        let vx = PLAYERS[victim_idx].x + PLAYER_WIDTH * 0.5;
-        let vy = PLAYERS[victim_idx].y + PLAYER_HEIGHT * 0.5;
+        let vx = WORLD.sim.players[victim_idx].x + PLAYER_WIDTH * 0.5;
+        let vy = WORLD.sim.players[victim_idx].y + PLAYER_HEIGHT * 0.5;
         let mut best_idx = victim_idx as u32;
         let mut best_dist_sq = 1.0e12_f32;
-        for (i, other) in PLAYERS.iter().enumerate() {
-            if i == victim_idx || !other.active || other.dead {
+        for (i, other) in WORLD.sim.players.iter().enumerate() {
+            if i == victim_idx || !other.active || other.dead || are_teammates(i, victim_idx) {
                 continue;
             }
             let ox = other.x + PLAYER_WIDTH * 0.5;
@@ -606,11 +987,17 @@ fn overtime_killer_for(victim_idx: usize) -> u32 {
 
 pub fn update_player(idx: usize) {
     unsafe {
-        let p = &mut PLAYERS[idx];
+        let p = &mut WORLD.sim.players[idx];
         if !p.active {
             return;
         }
 
+        // Out of stocks: no respawn, just spectate
+        if p.eliminated {
+            update_ghost(idx);
+            return;
+        }
+
         // Handle respawn
         if p.dead {
             if p.respawn_timer > 0 {
@@ -619,8 +1006,12 @@ pub fn update_player(idx: usize) {
                 // Respawn
                 p.dead = false;
                 p.ammo = MAX_AMMO;
+                p.weapon = Weapon::Pistol;
+                p.weapon_shots = 0;
                 p.melee_timer = 0;
                 p.melee_windup = 0;
+                p.melee_dir = MeleeDir::Side;
+                p.pogo_timer = 0;
                 p.spawn_flash = 30; // Spawn flash effect (0.5 seconds at 60fps)
                 p.shoot_flash = 0;
                 p.squash_stretch = 0.0;
@@ -629,6 +1020,10 @@ pub fn update_player(idx: usize) {
                 p.coyote_timer = 0;
                 p.drop_timer = 0;
                 p.ai_jump_hold = 0;
+                p.ai_nav_edge = NAV_NONE;
+                p.ai_noticed = 0;
+                p.ai_ignored = 0;
+                p.ai_react_timer = 0;
 
                 // Safe respawn position (avoid bullets/players) for spawn-camp protection.
                 let (spawn_x, spawn_y) = choose_safe_respawn_position(idx);
@@ -656,6 +1051,8 @@ pub fn update_player(idx: usize) {
         let c = read_controls(idx);
         let input_x = c.x;
         let input_y = c.y;
+        p.aim_x = input_x;
+        p.aim_y = input_y;
 
         // Horizontal movement
         let accel = if p.on_ground {
@@ -691,8 +1088,8 @@ pub fn update_player(idx: usize) {
             p.jump_buffer = p.jump_buffer.saturating_sub(1);
         }
 
-        // Drop-through (down + jump)
-        if p.on_ground && c.jump_pressed && input_y < -0.6 {
+        // Drop-through (down + jump), not possible from a solid floor
+        if p.on_ground && c.jump_pressed && input_y < -0.6 && !standing_on_solid(p.x, p.y) {
             p.drop_timer = DROP_THROUGH_FRAMES;
             p.jump_buffer = 0;
             p.on_ground = false;
@@ -750,8 +1147,9 @@ pub fn update_player(idx: usize) {
             }
         }
 
-        // Variable jump height
-        if !c.jump_held && p.vy > 0.0 {
+        // Variable jump height (pogo bounces keep their lift)
+        p.pogo_timer = p.pogo_timer.saturating_sub(1);
+        if !c.jump_held && p.vy > 0.0 && p.pogo_timer == 0 {
             p.vy *= 0.5;
         }
 
@@ -762,9 +1160,17 @@ pub fn update_player(idx: usize) {
         }
 
         // Shoot
-        if c.shoot_pressed && p.ammo > 0 && p.melee_timer == 0 && p.melee_windup == 0 {
-            spawn_bullet(idx, input_x, input_y);
-            p.ammo -= 1;
+        let can_fire = p.ammo > 0 || p.weapon != Weapon::Pistol;
+        if c.shoot_pressed && can_fire && p.melee_timer == 0 && p.melee_windup == 0 {
+            spawn_bullet(idx, input_x, input_y, p.weapon);
+            if p.weapon == Weapon::Pistol {
+                p.ammo -= 1;
+            } else {
+                p.weapon_shots -= 1;
+                if p.weapon_shots == 0 {
+                    p.weapon = Weapon::Pistol;
+                }
+            }
             p.shoot_flash = SHOOT_FLASH_DURATION; // Trigger muzzle flash
                                                   // Play shoot sound with pan based on x position (-10 to 10 -> -1 to 1)
             audio::play_shoot(p.x / 10.0);
@@ -773,6 +1179,13 @@ pub fn update_player(idx: usize) {
         // Melee (with windup anticipation)
         if c.melee_pressed && p.melee_timer == 0 && p.melee_windup == 0 {
             p.melee_windup = MELEE_WINDUP_DURATION; // Start windup phase
+            p.melee_dir = if input_y > MELEE_DIR_THRESHOLD {
+                MeleeDir::Up
+            } else if input_y < -MELEE_DIR_THRESHOLD && !p.on_ground {
+                MeleeDir::Down
+            } else {
+                MeleeDir::Side
+            };
         }
 
         // Handle melee windup -> active transition
@@ -781,8 +1194,10 @@ pub fn update_player(idx: usize) {
             if p.melee_windup == 0 {
                 // Windup complete, start active melee
                 p.melee_timer = MELEE_DURATION;
-                // Melee gives a small dash in facing direction
-                p.vx += if p.facing_right { 0.15 } else { -0.15 };
+                // Side slashes give a small dash in facing direction
+                if p.melee_dir == MeleeDir::Side {
+                    p.vx += if p.facing_right { 0.15 } else { -0.15 };
+                }
             }
         }
 
@@ -812,20 +1227,44 @@ pub fn update_player(idx: usize) {
         p.prev_idx = (p.prev_idx + 1) % TRAIL_COUNT;
 
         // Apply velocity (fixed timestep, no delta_time needed)
+        let start = (p.x, p.y);
         let mut new_x = p.x + p.vx;
-        let new_y = p.y + p.vy;
+        let mut new_y = p.y + p.vy;
 
         // Track if player was grounded before collision check (for landing dust)
         let was_grounded = p.on_ground;
 
-        // Platform collision
-        p.on_ground = false;
+        // Platform sides (solid and wall platforms). Resolved on x first, at
+        // the old height, so running into a wall doesn't stop a fall.
+        for platform in &WORLD.sim.platforms {
+            if !platform.active || !platform.has_sides() {
+                continue;
+            }
 
-        for platform in &PLATFORMS {
-            if !platform.active {
+            let plx = platform.x;
+            let plw = platform.width;
+            let rect = (plx, platform.y, plw, platform.height);
+            if !aabb_overlap((new_x, p.y, PLAYER_WIDTH, PLAYER_HEIGHT), rect) {
                 continue;
             }
-            if p.drop_timer > 0 {
+
+            let from_left = p.x + PLAYER_WIDTH <= plx + SIDE_EPSILON;
+            let from_right = p.x >= plx + plw - SIDE_EPSILON;
+            // Already inside (e.g. a mover slid into us): eject to the nearer side
+            let nearer_left = p.x + PLAYER_WIDTH * 0.5 < plx + plw * 0.5;
+            if from_left || (!from_right && nearer_left) {
+                new_x = plx - PLAYER_WIDTH;
+            } else {
+                new_x = plx + plw;
+            }
+            p.vx = 0.0;
+        }
+
+        // Floors and ceilings
+        p.on_ground = false;
+
+        for platform in &WORLD.sim.platforms {
+            if !platform.active {
                 continue;
             }
 
@@ -841,18 +1280,28 @@ pub fn update_player(idx: usize) {
             let plw = platform.width;
             let plh = platform.height;
 
-            if aabb_overlap((px, py, pw, ph), (plx, ply, plw, plh)) {
+            if !aabb_overlap((px, py, pw, ph), (plx, ply, plw, plh)) {
+                continue;
+            }
+
+            // Drop-through only skips one-way floors
+            let can_land = platform.has_floor()
+                && (p.drop_timer == 0 || platform.collision == Collision::Solid);
+
+            if can_land && p.vy <= 0.0 && p.y >= ply + plh - 0.2 {
                 // Landing from above
-                if p.vy <= 0.0 && p.y >= ply + plh - 0.2 {
-                    p.y = ply + plh;
-                    p.vy = 0.0;
-                    p.on_ground = true;
-
-                    // Move with platform if it's moving
-                    if platform.moving {
-                        new_x += platform.move_speed;
-                    }
+                p.y = ply + plh;
+                p.vy = 0.0;
+                p.on_ground = true;
+
+                // Move with platform if it's moving
+                if platform.moving {
+                    new_x += platform.move_speed;
                 }
+            } else if platform.has_ceiling() && p.vy > 0.0 && p.y + ph <= ply + 0.2 {
+                // Head bump
+                new_y = ply - ph;
+                p.vy = 0.0;
             }
         }
 
@@ -872,8 +1321,8 @@ pub fn update_player(idx: usize) {
         p.drop_timer = p.drop_timer.saturating_sub(1);
 
         // Level bounds (dynamic during overtime)
-        let left = GAME_STATE.arena_left;
-        let right = GAME_STATE.arena_right - PLAYER_WIDTH;
+        let left = WORLD.sim.game.arena_left;
+        let right = WORLD.sim.game.arena_right - PLAYER_WIDTH;
         let mut hit_wall = false;
         if p.x < left {
             p.x = left;
@@ -884,22 +1333,71 @@ pub fn update_player(idx: usize) {
         }
 
         // Overtime walls are lethal (awards point to closest opponent to keep matches moving).
-        if GAME_STATE.overtime && hit_wall {
+        if WORLD.sim.game.overtime && hit_wall {
             let killer = overtime_killer_for(idx);
-            kill_player(idx, killer);
+            kill_player(idx, killer, KillCause::OvertimeWall);
             return;
         }
 
         // Fall death (universal - all stages)
         if p.y < DEATH_Y {
-            kill_player(idx, idx as u32); // Self-kill (no points)
+            kill_player(idx, idx as u32, KillCause::Fall); // Self-kill (no points)
+            return;
         }
+
+        check_stomp(idx, start);
     }
 }
 
-pub fn kill_player(victim_idx: usize, killer_owner: u32) {
+/// Falling feet-first onto an enemy's head kills them and bounces the stomper.
+/// The feet are swept from `start`, so a fast fall can't skip a head.
+fn check_stomp(idx: usize, start: (f32, f32)) {
     unsafe {
-        let victim = &mut PLAYERS[victim_idx];
+        let p = &WORLD.sim.players[idx];
+        let delta = (p.x - start.0, p.y - start.1);
+        if delta.1 >= 0.0 {
+            return;
+        }
+        let team = team_of(idx);
+
+        for (j, other) in WORLD.sim.players.iter().enumerate() {
+            if j == idx || !other.active || other.dead || other.invuln_timer > 0 {
+                continue;
+            }
+            if !can_hurt(team, j) {
+                continue;
+            }
+            // Head zone grown by the feet box, so the feet's corner can be
+            // swept as a point
+            let head = (
+                other.x - PLAYER_WIDTH,
+                other.y + PLAYER_HEIGHT - STOMP_HEAD_DEPTH - STOMP_FEET_HEIGHT,
+                PLAYER_WIDTH * 2.0,
+                STOMP_HEAD_DEPTH + STOMP_FEET_HEIGHT,
+            );
+            if segment_aabb_entry(start, delta, head).is_none() {
+                continue;
+            }
+
+            let (hx, hy) = (other.x + PLAYER_WIDTH / 2.0, other.y + PLAYER_HEIGHT);
+            audio::play_stomp(hx / 10.0);
+            crate::particles::spawn_stomp_burst(hx, hy, player_color(j));
+            crate::game_state::trigger_hit_freeze(5);
+            kill_player(j, idx as u32, KillCause::Stomp);
+
+            let p = &mut WORLD.sim.players[idx];
+            p.vy = STOMP_BOUNCE;
+            p.pogo_timer = POGO_LIFT_FRAMES;
+            p.on_ground = false;
+            p.squash_stretch = 1.0;
+            return;
+        }
+    }
+}
+
+pub fn kill_player(victim_idx: usize, killer_owner: u32, cause: KillCause) {
+    unsafe {
+        let victim = &mut WORLD.sim.players[victim_idx];
         if victim.dead {
             return;
         }
@@ -913,38 +1411,151 @@ pub fn kill_player(victim_idx: usize, killer_owner: u32) {
         // Spawn death particles at victim's center position with their color
         let center_x = victim.x + PLAYER_WIDTH / 2.0;
         let center_y = victim.y + PLAYER_HEIGHT / 2.0;
-        crate::particles::spawn_death_particles(center_x, center_y, PLAYER_COLORS[victim_idx]);
+        crate::particles::spawn_death_particles(center_x, center_y, player_color(victim_idx));
 
         victim.dead = true;
         victim.respawn_timer = RESPAWN_DELAY;
         victim.invuln_timer = 0;
+        stats::record_death(cause);
 
-        // Award kill (if not self-kill)
-        if killer_owner != victim_idx as u32 {
-            let killer = &mut PLAYERS[killer_owner as usize];
-            killer.kills += 1;
+        // Lose a stock; the last one knocks the player out of the match
+        let rule = WORLD.sim.config.rule;
+        if rule == MatchRule::Stock {
+            victim.stocks = victim.stocks.saturating_sub(1);
+            victim.eliminated = victim.stocks == 0;
+        }
 
-            // Check for match win
-            let kills_to_win = CONFIG.kills_to_win.max(1);
-            if killer.kills >= kills_to_win {
-                GAME_STATE.winner_idx = killer_owner.min(3);
-                GAME_STATE.final_ko_timer = 75;
-                GAME_STATE.round_end_timer = 0;
-                GAME_STATE.phase = GamePhase::FinalKo;
+        // Award kill (if not self-kill or a team kill)
+        let killer_idx = killer_owner as usize;
+        if killer_idx != victim_idx && !are_teammates(killer_idx, victim_idx) {
+            WORLD.sim.players[killer_idx].kills += 1;
 
-                // Stop stage music; victory fanfare plays after the slow-mo beat.
-                audio::stop_music();
+            // Check for match win (team totals in team mode)
+            let kills_to_win = WORLD.sim.config.kills_to_win.max(1);
+            if rule == MatchRule::Kills && match_score(killer_idx) >= kills_to_win {
+                start_final_ko(killer_idx);
+            }
+        }
 
-                // Stronger final hit impact.
-                crate::game_state::trigger_hit_freeze(12);
-                crate::game_state::trigger_shake(1.0);
+        // Stock rule: the match ends when one player/team is left
+        if rule == MatchRule::Stock {
+            if let Some(survivor) = last_standing() {
+                start_final_ko(survivor);
             }
         }
 
         // Brief pause on kill
-        if GAME_STATE.phase == GamePhase::Playing {
+        if WORLD.sim.game.phase == GamePhase::Playing {
             crate::game_state::start_transition_out();
-            GAME_STATE.round_end_timer = 30; // Half second
+            WORLD.sim.game.round_end_timer = 30; // Half second
+        }
+    }
+}
+
+/// Bounce off whatever an airborne down-slash just hit
+pub fn pogo(p: &mut Player) {
+    p.vy = POGO_FORCE;
+    p.pogo_timer = POGO_LIFT_FRAMES;
+    p.on_ground = false;
+    p.squash_stretch = 1.0;
+    crate::particles::spawn_landing_dust(p.x + PLAYER_WIDTH / 2.0, p.y);
+    audio::play_jump(p.x / 10.0);
+}
+
+/// Whether a down-slash connecting now should pogo the attacker
+pub fn can_pogo(p: &Player) -> bool {
+    p.melee_timer > 0 && p.melee_dir == MeleeDir::Down && !p.on_ground
+}
+
+/// Whether a player's melee is still in its perfect-parry frames
+pub fn in_perfect_parry(p: &Player) -> bool {
+    p.melee_timer > 0 && p.melee_timer + PERFECT_PARRY_FRAMES >= MELEE_DURATION
+}
+
+/// Enter the match-winning slow-mo with `winner_idx` as the winner
+pub fn start_final_ko(winner_idx: usize) {
+    unsafe {
+        WORLD.sim.game.winner_idx = winner_idx.min(3) as u32;
+        WORLD.sim.game.final_ko_timer = 75;
+        WORLD.sim.game.round_end_timer = 0;
+        WORLD.sim.game.phase = GamePhase::FinalKo;
+
+        // Stop stage music; victory fanfare plays after the slow-mo beat.
+        audio::stop_music();
+
+        // Stronger final hit impact.
+        crate::game_state::trigger_hit_freeze(12);
+        crate::game_state::trigger_shake(1.0);
+    }
+}
+
+/// A player from the only team with stocks left, once just one remains
+fn last_standing() -> Option<usize> {
+    unsafe {
+        let mut teams_left = 0u32;
+        let mut survivor = None;
+        for (i, p) in WORLD.sim.players.iter().enumerate() {
+            if p.active && !p.eliminated {
+                teams_left |= 1 << team_of(i);
+                survivor.get_or_insert(i);
+            }
+        }
+        if teams_left.count_ones() <= 1 {
+            survivor
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform;
+    use crate::world::World;
+
+    #[test]
+    fn perfect_parry_is_the_first_two_live_frames() {
+        // A swing counts down on the tick it goes live, so bullets meet it
+        // with MELEE_DURATION - 1 down to 1 left
+        let mut p = Player::new();
+        let perfect: Vec<u32> = (0..MELEE_DURATION)
+            .filter(|&t| {
+                p.melee_timer = t;
+                in_perfect_parry(&p)
+            })
+            .collect();
+        assert_eq!(perfect, [MELEE_DURATION - 2, MELEE_DURATION - 1]);
+        assert_eq!(perfect, [10, 11]);
+    }
+
+    #[test]
+    fn fast_falls_stomp_heads_they_would_skip() {
+        let _lock = platform::test_lock();
+        // Over a head at x = 0, then beside it
+        for (x, stomped) in [(0.1, true), (PLAYER_WIDTH + 0.05, false)] {
+            unsafe {
+                WORLD = World::new();
+                platform::host().reset(1, 3);
+                WORLD.sim.players[1].active = true;
+
+                // Falls far enough in one tick to go from above the head
+                // zone to below it
+                let p = &mut WORLD.sim.players[0];
+                p.active = true;
+                p.x = x;
+                p.y = PLAYER_HEIGHT + 0.3;
+                p.vy = -0.9;
+                update_player(0);
+
+                assert!(WORLD.sim.players[0].y < PLAYER_HEIGHT - STOMP_HEAD_DEPTH);
+                assert_eq!(WORLD.sim.players[1].dead, stomped);
+                assert_eq!(
+                    WORLD.sim.stats.deaths_by_cause[KillCause::Stomp as usize],
+                    stomped as u32
+                );
+                assert_eq!(WORLD.sim.players[0].vy == STOMP_BOUNCE, stomped);
+            }
         }
     }
 }
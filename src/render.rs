@@ -2,20 +2,22 @@
 //!
 //! Contains all rendering code: EPU setup, stage/player/bullet rendering, and UI.
 
-use crate::combat::BULLETS;
+use crate::bot_profile::bot_profile_name;
+use crate::checksum;
 use crate::ffi::*;
 use crate::game_state::{
-    GamePhase, PausePage, TransitionPhase, CONFIG, DEFLECT_PLAYER, DEFLECT_POPUP_TICKS,
-    EFFECT_LIGHTS, GAME_STATE, IMPACT_FLASH, LOBBY_INDEX, MATCH_END_TICK, OPTIONS, PAUSE_INDEX,
-    PAUSE_PAGE, ROUND_NUMBER, STAGE_SELECT_RANDOM, STAGE_SELECT_ROTATE, TICK, TRANSITION_PHASE,
-    TRANSITION_PROGRESS,
+    hill_target_ticks, match_score, player_score, team_of, team_score, GamePhase, MatchRule,
+    PausePage, TransitionPhase, NUM_TEAMS, OPTIONS, STAGE_SELECT_RANDOM, STAGE_SELECT_ROTATE,
+    TEAM_COLORS, TEAM_NAMES,
 };
-use crate::particles::PARTICLES;
+use crate::pickups::SHELL_SIZE;
 use crate::player::{
-    abs, MELEE_DURATION, MELEE_WINDUP_DURATION, PLAYERS, PLAYER_COLORS, PLAYER_HEIGHT,
-    PLAYER_WIDTH, SPAWN_INVULN_FRAMES, TRAIL_COUNT, TRAIL_VELOCITY_THRESHOLD,
+    abs, player_color, MeleeDir, MAX_PLAYERS, MELEE_DURATION, MELEE_WINDUP_DURATION, PLAYER_COLORS,
+    PLAYER_HEIGHT, PLAYER_WIDTH, SPAWN_INVULN_FRAMES, TRAIL_COUNT, TRAIL_VELOCITY_THRESHOLD,
 };
-use crate::stage::PLATFORMS;
+use crate::stage::{current_stage_def, hill_rect, stage_name, Background};
+use crate::weapons::{crate_pos, Weapon, CRATE_SIZE};
+use crate::world::WORLD;
 
 // =============================================================================
 // CONSTANTS
@@ -25,6 +27,9 @@ use crate::stage::PLATFORMS;
 #[allow(dead_code)]
 const BILLBOARD_CYLINDRICAL_Y: u32 = 2;
 
+// Desync banner is a developer aid; release builds stay clean
+const SHOW_DESYNC_OVERLAY: bool = cfg!(debug_assertions);
+
 // =============================================================================
 // MESH HANDLES
 // =============================================================================
@@ -90,6 +95,18 @@ fn with_alpha(color: u32, alpha: u32) -> u32 {
     (color & 0xFFFFFF00) | (alpha & 0xFF)
 }
 
+/// Small HUD bar filled to `value / max`
+fn draw_progress_bar(x: f32, y: f32, value: u32, max: u32, color: u32) {
+    const WIDTH: f32 = 60.0;
+    let frac = (value as f32 / max.max(1) as f32).min(1.0);
+    unsafe {
+        set_color(0x40404080);
+        draw_rect(x, y, WIDTH, 12.0);
+        set_color(color);
+        draw_rect(x, y, WIDTH * frac, 12.0);
+    }
+}
+
 /// Ease out bounce for animations
 fn ease_out_bounce(t: f32) -> f32 {
     if t < 0.5 {
@@ -101,15 +118,6 @@ fn ease_out_bounce(t: f32) -> f32 {
     }
 }
 
-fn stage_name(stage: u32) -> &'static str {
-    match stage {
-        0 => "GRID ARENA",
-        1 => "SCATTER FIELD",
-        2 => "RING VOID",
-        _ => "ARENA",
-    }
-}
-
 fn stage_select_label(sel: u32) -> &'static str {
     if sel == STAGE_SELECT_RANDOM {
         "RANDOM"
@@ -184,25 +192,25 @@ fn setup_epu_grid_arena() {
 
         // Layer 1: Synthwave grid floor
         env_lines(
-            1,            // layer
-            0,            // variant (0=Floor)
-            2,            // line_type (2=Grid)
-            20,           // thickness (0-255)
-            0.75,         // spacing
-            45.0,         // fade_distance
-            96,           // parallax (also selects depth slices)
-            0x00FFFFFF,   // color_primary (cyan)
-            0x40FFFFFF,   // color_accent (cyan glow)
-            4,            // accent_every (every 4th line)
-            TICK % 65536, // phase (scroll animation)
-            0,            // profile (0=Grid)
-            24,           // warp
-            0,            // wobble
-            128,          // glow
-            0.0,          // axis_x
-            0.0,          // axis_y
-            1.0,          // axis_z
-            0,            // seed (auto)
+            1,                      // layer
+            0,                      // variant (0=Floor)
+            2,                      // line_type (2=Grid)
+            20,                     // thickness (0-255)
+            0.75,                   // spacing
+            45.0,                   // fade_distance
+            96,                     // parallax (also selects depth slices)
+            0x00FFFFFF,             // color_primary (cyan)
+            0x40FFFFFF,             // color_accent (cyan glow)
+            4,                      // accent_every (every 4th line)
+            WORLD.sim.tick % 65536, // phase (scroll animation)
+            0,                      // profile (0=Grid)
+            24,                     // warp
+            0,                      // wobble
+            128,                    // glow
+            0.0,                    // axis_x
+            0.0,                    // axis_y
+            1.0,                    // axis_z
+            0,                      // seed (auto)
         );
     }
 }
@@ -232,25 +240,25 @@ fn setup_epu_scatter_field() {
 
         // Layer 1: Falling particles (Cells family 0, variant 1 = rain)
         env_cells(
-            1,            // layer
-            0,            // family (0=Particles)
-            1,            // variant (1=Rain)
-            200,          // density
-            2,            // size_min
-            10,           // size_max
-            200,          // intensity
-            220,          // shape
-            96,           // motion
-            140,          // parallax
-            120,          // height_bias
-            60,           // clustering
-            0xFFFFFFFF,   // color_a
-            0x808080FF,   // color_b
-            0.0,          // axis_x
-            0.0,          // axis_y
-            1.0,          // axis_z
-            TICK % 65536, // phase
-            0,            // seed (auto)
+            1,                      // layer
+            0,                      // family (0=Particles)
+            1,                      // variant (1=Rain)
+            200,                    // density
+            2,                      // size_min
+            10,                     // size_max
+            200,                    // intensity
+            220,                    // shape
+            96,                     // motion
+            140,                    // parallax
+            120,                    // height_bias
+            60,                     // clustering
+            0xFFFFFFFF,             // color_a
+            0x808080FF,             // color_b
+            0.0,                    // axis_x
+            0.0,                    // axis_y
+            1.0,                    // axis_z
+            WORLD.sim.tick % 65536, // phase
+            0,                      // seed (auto)
         );
     }
 }
@@ -263,8 +271,8 @@ fn setup_epu_ring_void() {
         env_gradient(
             0,          // layer
             0x000000FF, // zenith (black)
-            0x0a001aFF, // sky_horizon (very dark purple)
-            0x0a001aFF, // ground_horizon
+            0x0a001aff, // sky_horizon (very dark purple)
+            0x0a001aff, // ground_horizon
             0x000000FF, // nadir (black)
             0.0,        // rotation
             0.0,        // shift
@@ -280,24 +288,24 @@ fn setup_epu_ring_void() {
 
         // Layer 1: Pulsing rings
         env_rings(
-            1,                    // layer
-            0,                    // family (0=Portal)
-            8,                    // ring_count
-            40,                   // thickness (0-255)
-            0xFF00FF80,           // color_a (magenta)
-            0x8000FF40,           // color_b (purple)
-            0xFFFFFFFF,           // center_color (white)
-            100,                  // center_falloff
-            30.0,                 // spiral_twist (degrees)
-            0.0,                  // axis_x
-            0.0,                  // axis_y
-            1.0,                  // axis_z (facing camera)
-            TICK % 65536,         // phase (rotation)
-            (TICK * 137) % 65536, // wobble
-            32,                   // noise
-            24,                   // dash
-            160,                  // glow
-            41,                   // seed
+            1,                              // layer
+            0,                              // family (0=Portal)
+            8,                              // ring_count
+            40,                             // thickness (0-255)
+            0xFF00FF80,                     // color_a (magenta)
+            0x8000FF40,                     // color_b (purple)
+            0xFFFFFFFF,                     // center_color (white)
+            100,                            // center_falloff
+            30.0,                           // spiral_twist (degrees)
+            0.0,                            // axis_x
+            0.0,                            // axis_y
+            1.0,                            // axis_z (facing camera)
+            WORLD.sim.tick % 65536,         // phase (rotation)
+            (WORLD.sim.tick * 137) % 65536, // wobble
+            32,                             // noise
+            24,                             // dash
+            160,                            // glow
+            41,                             // seed
         );
     }
 }
@@ -309,18 +317,17 @@ fn setup_epu_ring_void() {
 pub fn render_stage() {
     unsafe {
         // Configure EPU based on stage
-        match GAME_STATE.current_stage {
-            0 => setup_epu_grid_arena(),
-            1 => setup_epu_scatter_field(),
-            2 => setup_epu_ring_void(),
-            _ => setup_epu_grid_arena(),
+        match current_stage_def().background {
+            Background::Grid => setup_epu_grid_arena(),
+            Background::Sunset => setup_epu_scatter_field(),
+            Background::Portal => setup_epu_ring_void(),
         }
 
         // Draw EPU layers
         draw_env();
 
         // Draw platforms with 3D depth
-        for platform in &PLATFORMS {
+        for platform in &WORLD.sim.platforms {
             if !platform.active {
                 continue;
             }
@@ -336,12 +343,26 @@ pub fn render_stage() {
             push_scale(platform.width, platform.height, depth);
             draw_mesh(CUBE_MESH);
 
-            // Top surface highlight
-            set_color(0x505080FF);
-            push_identity();
-            push_translate(px, py + platform.height * 0.4, 0.0);
-            push_scale(platform.width * 0.95, platform.height * 0.2, depth * 1.01);
-            draw_mesh(CUBE_MESH);
+            // Top surface highlight (anything that can be stood on)
+            if platform.has_floor() {
+                set_color(0x505080FF);
+                push_identity();
+                push_translate(px, py + platform.height * 0.4, 0.0);
+                push_scale(platform.width * 0.95, platform.height * 0.2, depth * 1.01);
+                draw_mesh(CUBE_MESH);
+            }
+
+            // Side strips on blocking sides (solid and wall platforms)
+            if platform.has_sides() {
+                let strip = (platform.width * 0.1).min(0.15);
+                set_color(0x5090B0FF);
+                for side in [-1.0, 1.0] {
+                    push_identity();
+                    push_translate(px + side * (platform.width - strip) * 0.5, py, 0.0);
+                    push_scale(strip, platform.height * 0.95, depth * 1.01);
+                    draw_mesh(CUBE_MESH);
+                }
+            }
 
             // Edge glow for moving platforms
             if platform.moving {
@@ -353,10 +374,41 @@ pub fn render_stage() {
             }
         }
 
+        // King of the Hill zone: white when free, holder's color when held,
+        // flashing red when contested, blinking just before it moves
+        if WORLD.sim.hill.active {
+            let hill = &WORLD.sim.hill;
+            let (x, y, w, h) = hill_rect();
+            let color = if hill.contested {
+                if (WORLD.sim.tick / 8).is_multiple_of(2) {
+                    0xFF4040FF
+                } else {
+                    0xFFFFFFFF
+                }
+            } else if hill.held {
+                player_color(hill.holder as usize)
+            } else {
+                0xFFFFFFFF
+            };
+            let moving_soon = hill.move_timer < 120 && (WORLD.sim.tick / 10).is_multiple_of(2);
+            if !moving_soon {
+                set_color(with_alpha(color, 0x30));
+                push_identity();
+                push_translate(x + w * 0.5, y + h * 0.5, -0.3);
+                push_scale(w, h, 0.6);
+                draw_mesh(CUBE_MESH);
+            }
+            set_color(with_alpha(color, 0xC0));
+            push_identity();
+            push_translate(x + w * 0.5, y + 0.04, 0.05);
+            push_scale(w, 0.08, 0.65);
+            draw_mesh(CUBE_MESH);
+        }
+
         // Overtime: lethal neon walls close in.
-        if GAME_STATE.overtime {
-            let left = GAME_STATE.arena_left;
-            let right = GAME_STATE.arena_right;
+        if WORLD.sim.game.overtime {
+            let left = WORLD.sim.game.arena_left;
+            let right = WORLD.sim.game.arena_right;
             let wall_w = 0.25;
             let wall_h = 18.0;
             let wall_y = 2.0;
@@ -394,7 +446,19 @@ pub fn render_stage() {
 
 pub fn render_players() {
     unsafe {
-        for (i, player) in PLAYERS.iter().enumerate() {
+        for (i, player) in WORLD.sim.players.iter().enumerate() {
+            // Eliminated players spectate as a faint ghost
+            if player.active && player.eliminated {
+                set_color(with_alpha(player_color(i), 60));
+                push_identity();
+                push_translate(
+                    player.x + PLAYER_WIDTH / 2.0,
+                    player.y + PLAYER_HEIGHT / 2.0,
+                    -0.3,
+                );
+                draw_mesh(CAPSULE_MESH);
+                continue;
+            }
             if !player.active || player.dead {
                 continue;
             }
@@ -420,7 +484,7 @@ pub fn render_players() {
 
                     // Fade based on trail index (older = more transparent)
                     let alpha = ((TRAIL_COUNT - t) as f32 / TRAIL_COUNT as f32 * 80.0) as u32;
-                    let trail_color = with_alpha(dim_color(PLAYER_COLORS[i], 0.6), alpha);
+                    let trail_color = with_alpha(dim_color(player_color(i), 0.6), alpha);
 
                     set_color(trail_color);
                     push_identity();
@@ -460,7 +524,7 @@ pub fn render_players() {
                     1.0
                 };
 
-                set_color(PLAYER_COLORS[i]);
+                set_color(player_color(i));
                 push_identity();
                 push_translate(center_x, center_y, 0.0);
 
@@ -478,7 +542,7 @@ pub fn render_players() {
                 draw_mesh(CAPSULE_MESH);
 
                 // Player head (sphere) - slightly lighter color
-                let head_color = brighten_color(PLAYER_COLORS[i]);
+                let head_color = brighten_color(player_color(i));
                 set_color(head_color);
                 push_identity();
                 // Adjust head position for squash/stretch
@@ -521,17 +585,24 @@ pub fn render_players() {
                 draw_mesh(SPHERE_MESH);
             }
 
+            // Swing direction: sideways along facing, or straight up/down
+            let facing = if player.facing_right { 1.0 } else { -1.0 };
+            let (swing_x, swing_y, swing_angle) = match player.melee_dir {
+                MeleeDir::Side => (facing, 0.0, 0.0),
+                MeleeDir::Up => (0.0, 1.0, 1.571),
+                MeleeDir::Down => (0.0, -1.0, -1.571),
+            };
+
             // --- Melee windup indicator ---
             if player.melee_windup > 0 {
                 let progress = 1.0 - (player.melee_windup as f32 / MELEE_WINDUP_DURATION as f32);
                 let alpha = ((1.0 - progress) * 150.0) as u32;
-                let facing = if player.facing_right { 1.0 } else { -1.0 };
 
                 // Draw charging arc
                 set_color(0xFFFFFF00 | alpha);
                 push_identity();
-                push_translate(center_x - facing * 0.3, center_y, 0.15);
-                push_rotate_z(-0.5 * facing);
+                push_translate(center_x - swing_x * 0.3, center_y - swing_y * 0.3, 0.15);
+                push_rotate_z(swing_angle - 0.5 * facing);
                 push_scale(0.8, 0.15, 0.1);
                 draw_mesh(CUBE_MESH);
             }
@@ -546,14 +617,18 @@ pub fn render_players() {
                 let end_angle: f32 = 0.785; // +45 degrees
                 let current_angle = start_angle + progress * (end_angle - start_angle);
 
-                // Facing direction multiplier
-                let facing = if player.facing_right { 1.0 } else { -1.0 };
+                // Vertical slashes sweep over/under the body instead of in front
+                let reach = if player.melee_dir == MeleeDir::Side {
+                    0.5
+                } else {
+                    0.9
+                };
 
                 // Multiple slash lines for thickness
                 for offset in [-0.1_f32, 0.0, 0.1].iter() {
                     push_identity();
-                    push_translate(center_x + facing * 0.5, center_y, 0.2);
-                    push_rotate_z(current_angle * facing + offset * facing);
+                    push_translate(center_x + swing_x * reach, center_y + swing_y * reach, 0.2);
+                    push_rotate_z(swing_angle + current_angle * facing + offset * facing);
                     push_scale(1.5, 0.1, 0.1); // Long thin slash
                     set_color(0xFFFFFF00 | slash_alpha);
                     draw_mesh(CUBE_MESH);
@@ -564,7 +639,7 @@ pub fn render_players() {
             if player.spawn_flash > 0 {
                 let flash_progress = player.spawn_flash as f32 / 30.0; // 1.0 to 0.0
                 let flash_alpha = (flash_progress * 200.0) as u32;
-                let flash_color = (PLAYER_COLORS[i] & 0xFFFFFF00) | flash_alpha;
+                let flash_color = (player_color(i) & 0xFFFFFF00) | flash_alpha;
 
                 // Draw expanding ring around player
                 push_identity();
@@ -578,7 +653,7 @@ pub fn render_players() {
             // Invulnerability aura (subtle cyan shimmer)
             if player.invuln_timer > 0 {
                 let t = player.invuln_timer as f32 / SPAWN_INVULN_FRAMES.max(1) as f32;
-                let pulse = libm::sinf(TICK as f32 * 0.25) * 0.08 + 1.0;
+                let pulse = libm::sinf(WORLD.sim.tick as f32 * 0.25) * 0.08 + 1.0;
                 let alpha = (t * 100.0) as u32;
                 set_color(with_alpha(0x00FFFFFF, alpha));
                 push_identity();
@@ -611,24 +686,99 @@ pub fn render_players() {
 
 pub fn render_bullets() {
     unsafe {
-        for bullet in &BULLETS {
+        for bullet in &WORLD.sim.bullets {
             if !bullet.active {
                 continue;
             }
+            let color = bullet.kind.color();
+
+            match bullet.kind {
+                Weapon::Laser => {
+                    // Long beam stretched along the direction of travel
+                    set_color(color);
+                    push_identity();
+                    push_translate(bullet.x - bullet.vx * 2.0, bullet.y - bullet.vy * 2.0, 0.15);
+                    let speed = Weapon::Laser.speed();
+                    let len = 0.2 + abs(bullet.vx) / speed * 0.8;
+                    let tall = 0.2 + abs(bullet.vy) / speed * 0.8;
+                    push_scale(len, tall, 0.2);
+                    draw_mesh(CUBE_MESH);
+                }
+                Weapon::Grenade => {
+                    // Blinks faster as the fuse runs down
+                    let fast = bullet.lifetime < 45;
+                    let blink = (bullet.lifetime / if fast { 4 } else { 10 }) % 2 == 0;
+                    set_color(if blink { 0xFFFFFFFF } else { color });
+                    push_identity();
+                    push_translate(bullet.x, bullet.y, 0.15);
+                    push_scale(0.35, 0.35, 0.35);
+                    draw_mesh(SPHERE_MESH);
+                }
+                _ => {
+                    // Bright bullet with glow effect
+                    set_color(color);
+                    push_identity();
+                    push_translate(bullet.x, bullet.y, 0.15);
+                    push_scale(1.5, 1.5, 1.5); // Scale up the small bullet mesh
+                    draw_mesh(BULLET_MESH);
+
+                    // Subtle glow/trail behind bullet
+                    set_color(with_alpha(color, 0x60));
+                    push_identity();
+                    push_translate(bullet.x - bullet.vx * 0.5, bullet.y - bullet.vy * 0.5, 0.1);
+                    push_scale(1.0, 1.0, 1.0);
+                    draw_mesh(BULLET_MESH);
+                }
+            }
+        }
+    }
+}
+
+// =============================================================================
+// PICKUP RENDERING
+// =============================================================================
+
+/// Spent shells (small brass blocks that blink before vanishing) and weapon crates
+pub fn render_pickups() {
+    unsafe {
+        let spots = current_stage_def().crate_spot_count;
+        for (spot, c) in WORLD.sim.crates.iter().enumerate().take(spots) {
+            if !c.active {
+                continue;
+            }
+            let (x, y) = crate_pos(spot);
+            let bob = libm::sinf(WORLD.sim.tick as f32 * 0.08 + spot as f32) * 0.05;
+            let (cx, cy) = (x + CRATE_SIZE * 0.5, y + CRATE_SIZE * 0.5 + bob);
+
+            // Dark box with a glowing core in the weapon's color
+            set_color(0x404048FF);
+            push_identity();
+            push_translate(cx, cy, 0.0);
+            push_scale(CRATE_SIZE, CRATE_SIZE, CRATE_SIZE);
+            draw_mesh(CUBE_MESH);
 
-            // Bright yellow bullet with glow effect
-            set_color(0xFFFF00FF);
+            set_color(c.weapon.color());
             push_identity();
-            push_translate(bullet.x, bullet.y, 0.15);
-            push_scale(1.5, 1.5, 1.5); // Scale up the small bullet mesh
-            draw_mesh(BULLET_MESH);
+            push_translate(cx, cy, 0.0);
+            push_scale(CRATE_SIZE * 1.02, CRATE_SIZE * 0.3, CRATE_SIZE * 1.02);
+            draw_mesh(CUBE_MESH);
+        }
+
+        for shell in &WORLD.sim.pickups {
+            if !shell.active {
+                continue;
+            }
 
-            // Subtle glow/trail behind bullet
-            set_color(0xFFFF0060);
+            // Blink during the last two seconds
+            if shell.lifetime < 120 && (shell.lifetime / 6) % 2 == 0 {
+                continue;
+            }
+
+            set_color(0xFFC040FF);
             push_identity();
-            push_translate(bullet.x - bullet.vx * 0.5, bullet.y - bullet.vy * 0.5, 0.1);
-            push_scale(1.0, 1.0, 1.0);
-            draw_mesh(BULLET_MESH);
+            push_translate(shell.x + SHELL_SIZE * 0.5, shell.y + SHELL_SIZE * 0.3, 0.0);
+            push_scale(SHELL_SIZE, SHELL_SIZE * 0.6, SHELL_SIZE);
+            draw_mesh(CUBE_MESH);
         }
     }
 }
@@ -640,7 +790,7 @@ pub fn render_bullets() {
 /// Render all active particles
 pub fn render_particles() {
     unsafe {
-        for p in &PARTICLES {
+        for p in &WORLD.fx.particles {
             if p.active {
                 // Calculate alpha based on remaining lifetime
                 let alpha = (p.lifetime as f32 / p.max_lifetime as f32 * 255.0) as u32;
@@ -664,7 +814,7 @@ pub fn render_particles() {
 /// Apply active effect lights to the scene
 pub fn apply_effect_lights() {
     unsafe {
-        for (idx, light) in EFFECT_LIGHTS.iter().enumerate() {
+        for (idx, light) in WORLD.fx.effect_lights.iter().enumerate() {
             if light.active && idx < 4 {
                 // Set point light
                 light_set_point(idx as u32, light.x, light.y, light.z);
@@ -694,7 +844,7 @@ fn render_title() {
         draw_rect(200.0, 150.0, 560.0, 280.0);
 
         // Animated title - pulse/breathe effect
-        let pulse = libm::sinf(TICK as f32 * 0.1) * 0.1 + 1.0; // 1.0 +/- 0.1
+        let pulse = libm::sinf(WORLD.sim.tick as f32 * 0.1) * 0.1 + 1.0; // 1.0 +/- 0.1
         let title_size = 64.0 * pulse;
 
         // Title glow effect - draw multiple times with decreasing alpha
@@ -715,7 +865,7 @@ fn render_title() {
         draw_text_str("NEON DUEL", 320.0, 200.0, title_size);
 
         // Subtitle with slight pulse
-        let subtitle_pulse = libm::sinf(TICK as f32 * 0.08 + 1.0) * 0.05 + 1.0;
+        let subtitle_pulse = libm::sinf(WORLD.sim.tick as f32 * 0.08 + 1.0) * 0.05 + 1.0;
         let subtitle_size = 24.0 * subtitle_pulse;
 
         // Subtitle glow
@@ -735,7 +885,7 @@ fn render_title() {
         draw_text_str(players_str, 420.0, 320.0, 20.0);
 
         // Instructions with animated fade
-        let blink_alpha = ((libm::sinf(TICK as f32 * 0.15) * 0.3 + 0.7) * 255.0) as u32;
+        let blink_alpha = ((libm::sinf(WORLD.sim.tick as f32 * 0.15) * 0.3 + 0.7) * 255.0) as u32;
         set_color(0x00FF0000 | blink_alpha);
         draw_text_str("Press A or START to begin", 340.0, 380.0, 18.0);
 
@@ -749,10 +899,10 @@ fn render_title() {
         );
 
         // Animated character previews - bouncing player silhouettes
-        let bounce1 = libm::sinf(TICK as f32 * 0.12) * 10.0;
-        let bounce2 = libm::sinf(TICK as f32 * 0.12 + 2.0) * 10.0;
-        let bounce3 = libm::sinf(TICK as f32 * 0.12 + 4.0) * 10.0;
-        let bounce4 = libm::sinf(TICK as f32 * 0.12 + 6.0) * 10.0;
+        let bounce1 = libm::sinf(WORLD.sim.tick as f32 * 0.12) * 10.0;
+        let bounce2 = libm::sinf(WORLD.sim.tick as f32 * 0.12 + 2.0) * 10.0;
+        let bounce3 = libm::sinf(WORLD.sim.tick as f32 * 0.12 + 4.0) * 10.0;
+        let bounce4 = libm::sinf(WORLD.sim.tick as f32 * 0.12 + 6.0) * 10.0;
 
         // Draw small colored circles to represent players
         set_color(PLAYER_COLORS[0]);
@@ -782,7 +932,7 @@ fn render_lobby() {
         // Player slots
         let connected = player_count().min(4) as usize;
         let mut y = 155.0;
-        for i in 0..4 {
+        for i in 0..MAX_PLAYERS {
             let label = match i {
                 0 => "P1",
                 1 => "P2",
@@ -790,20 +940,27 @@ fn render_lobby() {
                 _ => "P4",
             };
 
+            // CPU slots double as bot profile rows (after the settings)
+            let cpu_slot = i >= connected && WORLD.sim.config.fill_bots;
+            if i > 0 && WORLD.sim.lobby_index == crate::LOBBY_SETTING_ROWS + i as u32 - 1 {
+                set_color(0x00FFFF30);
+                draw_rect(161.0, y - 2.0, 280.0, 26.0);
+            }
+
             // Color swatch
-            set_color(PLAYER_COLORS[i]);
+            set_color(player_color(i));
             draw_rect(175.0, y + 4.0, 18.0, 18.0);
 
             set_color(0xFFFFFFFF);
             draw_text_str(label, 205.0, y, 20.0);
 
             let (status, color) = if i < connected {
-                if PLAYERS[i].ready {
+                if WORLD.sim.players[i].ready {
                     ("READY", 0x00FF00FF)
                 } else {
                     ("PRESS A", 0xAAAAAAFF)
                 }
-            } else if CONFIG.fill_bots {
+            } else if WORLD.sim.config.fill_bots {
                 ("CPU", 0x00FFFFFF)
             } else {
                 ("---", 0x666666FF)
@@ -812,6 +969,24 @@ fn render_lobby() {
             set_color(color);
             draw_text_str(status, 280.0, y, 20.0);
 
+            // Team tag (CPUs are balanced across teams at match start)
+            if WORLD.sim.config.team_mode && i < connected {
+                let team = WORLD.sim.config.teams[i] as usize;
+                set_color(TEAM_COLORS[team]);
+                draw_text_str(TEAM_NAMES[team], 375.0, y, 16.0);
+            }
+
+            // Bot profile (P1 scrolls past the match settings to change it)
+            if cpu_slot {
+                set_color(0xFF00FFFF);
+                draw_text_str(
+                    bot_profile_name(WORLD.sim.config.bot_profiles[i]),
+                    345.0,
+                    y + 2.0,
+                    16.0,
+                );
+            }
+
             y += 34.0;
         }
 
@@ -821,7 +996,7 @@ fn render_lobby() {
 
         // Helper: highlight row
         let highlight = |idx: u32, y: f32| {
-            if LOBBY_INDEX == idx {
+            if WORLD.sim.lobby_index == idx {
                 set_color(0x00FFFF30);
                 draw_rect(settings_x - 14.0, y - 2.0, 330.0, 26.0);
             }
@@ -833,39 +1008,50 @@ fn render_lobby() {
         draw_text_str("STAGE", settings_x, sy, 18.0);
         set_color(0x00FFFFFF);
         draw_text_str(
-            stage_select_label(CONFIG.stage_select),
+            stage_select_label(WORLD.sim.config.stage_select),
             settings_x + 120.0,
             sy,
             18.0,
         );
         sy += 32.0;
 
-        // Kills
+        // Win rule
         highlight(1, sy);
         set_color(0xFFFFFFFF);
-        draw_text_str("KILLS", settings_x, sy, 18.0);
+        draw_text_str("RULE", settings_x, sy, 18.0);
+        set_color(0xFFFF00FF);
+        let (rule_name, label, value) = match WORLD.sim.config.rule {
+            MatchRule::Kills => ("KILLS", "KILLS", WORLD.sim.config.kills_to_win),
+            MatchRule::Stock => ("STOCK", "LIVES", WORLD.sim.config.stocks),
+            MatchRule::Hill => ("KING OF THE HILL", "HOLD", WORLD.sim.config.hill_seconds),
+        };
+        draw_text_str(rule_name, settings_x + 120.0, sy, 18.0);
+        sy += 32.0;
+
+        // Kills / lives / hold time
+        highlight(2, sy);
+        set_color(0xFFFFFFFF);
+        draw_text_str(label, settings_x, sy, 18.0);
         let mut buf = [0u8; 10];
         set_color(0xFFFF00FF);
-        draw_text_str(
-            u32_to_str(CONFIG.kills_to_win, &mut buf),
-            settings_x + 120.0,
-            sy,
-            18.0,
-        );
+        draw_text_str(u32_to_str(value, &mut buf), settings_x + 120.0, sy, 18.0);
+        if WORLD.sim.config.rule == MatchRule::Hill {
+            draw_text_str("s", settings_x + 150.0, sy, 18.0);
+        }
         sy += 32.0;
 
         // Time
-        highlight(2, sy);
+        highlight(3, sy);
         set_color(0xFFFFFFFF);
         draw_text_str("TIME", settings_x, sy, 18.0);
-        if CONFIG.round_time_seconds == 0 {
+        if WORLD.sim.config.round_time_seconds == 0 {
             set_color(0xAAAAAAFF);
             draw_text_str("INFINITE", settings_x + 120.0, sy, 18.0);
         } else {
             let mut tbuf = [0u8; 10];
             set_color(0xAAAAAAFF);
             draw_text_str(
-                u32_to_str(CONFIG.round_time_seconds, &mut tbuf),
+                u32_to_str(WORLD.sim.config.round_time_seconds, &mut tbuf),
                 settings_x + 120.0,
                 sy,
                 18.0,
@@ -875,16 +1061,20 @@ fn render_lobby() {
         sy += 32.0;
 
         // CPUs
-        highlight(3, sy);
+        highlight(4, sy);
         set_color(0xFFFFFFFF);
         draw_text_str("FILL CPU", settings_x, sy, 18.0);
-        set_color(if CONFIG.fill_bots {
+        set_color(if WORLD.sim.config.fill_bots {
             0x00FF00FF
         } else {
             0xFF0000FF
         });
         draw_text_str(
-            if CONFIG.fill_bots { "ON" } else { "OFF" },
+            if WORLD.sim.config.fill_bots {
+                "ON"
+            } else {
+                "OFF"
+            },
             settings_x + 120.0,
             sy,
             18.0,
@@ -892,29 +1082,83 @@ fn render_lobby() {
         sy += 32.0;
 
         // CPU difficulty
-        highlight(4, sy);
+        highlight(5, sy);
         set_color(0xFFFFFFFF);
         draw_text_str("CPU", settings_x, sy, 18.0);
-        let diff = match CONFIG.bot_difficulty {
+        let diff = match WORLD.sim.config.bot_difficulty {
             0 => "EASY",
             1 => "NORMAL",
             _ => "HARD",
         };
         set_color(0xFF00FFFF);
         draw_text_str(diff, settings_x + 120.0, sy, 18.0);
+        sy += 32.0;
+
+        // Teams
+        highlight(6, sy);
+        set_color(0xFFFFFFFF);
+        draw_text_str("MODE", settings_x, sy, 18.0);
+        set_color(0x00FFFFFF);
+        draw_text_str(
+            if WORLD.sim.config.team_mode {
+                "TEAMS"
+            } else {
+                "FREE FOR ALL"
+            },
+            settings_x + 120.0,
+            sy,
+            18.0,
+        );
+        sy += 32.0;
+
+        // Friendly fire (greyed out outside team mode)
+        highlight(7, sy);
+        set_color(if WORLD.sim.config.team_mode {
+            0xFFFFFFFF
+        } else {
+            0x666666FF
+        });
+        draw_text_str("FRIENDLY FIRE", settings_x, sy, 18.0);
+        set_color(if !WORLD.sim.config.team_mode {
+            0x666666FF
+        } else if WORLD.sim.config.friendly_fire {
+            0x00FF00FF
+        } else {
+            0xFF0000FF
+        });
+        draw_text_str(
+            if WORLD.sim.config.friendly_fire {
+                "ON"
+            } else {
+                "OFF"
+            },
+            settings_x + 200.0,
+            sy,
+            18.0,
+        );
 
         // Footer instructions
         set_color(0x808080FF);
         draw_text_str(
-            "Players: A to ready | P1: D-Pad to change | START: begin | B: title",
+            if WORLD.sim.config.team_mode {
+                "A: ready | X: switch team | P1: D-Pad to change | START: begin"
+            } else {
+                "Players: A to ready | P1: D-Pad to change | START: begin | B: title"
+            },
             175.0,
             430.0,
             14.0,
         );
 
         // Eligibility hint
-        let ready_humans = PLAYERS.iter().take(connected).filter(|p| p.ready).count() as u32;
-        let cpu_fill = if CONFIG.fill_bots {
+        let ready_humans = WORLD
+            .sim
+            .players
+            .iter()
+            .take(connected)
+            .filter(|p| p.ready)
+            .count() as u32;
+        let cpu_fill = if WORLD.sim.config.fill_bots {
             (4 - connected) as u32
         } else {
             0
@@ -943,7 +1187,7 @@ fn render_pause() {
         let base_x = 330.0;
         let mut y = 200.0;
 
-        match PAUSE_PAGE {
+        match WORLD.sim.pause_page {
             PausePage::Main => {
                 let items = [
                     "RESUME",
@@ -953,7 +1197,7 @@ fn render_pause() {
                     "OPTIONS",
                 ];
                 for (i, item) in items.iter().enumerate() {
-                    if PAUSE_INDEX == i as u32 {
+                    if WORLD.sim.pause_index == i as u32 {
                         set_color(0x00FFFF30);
                         draw_rect(base_x - 10.0, y - 3.0, 320.0, 26.0);
                     }
@@ -964,7 +1208,7 @@ fn render_pause() {
             }
             PausePage::Options => {
                 // Music volume
-                if PAUSE_INDEX == 0 {
+                if WORLD.sim.pause_index == 0 {
                     set_color(0x00FFFF30);
                     draw_rect(base_x - 10.0, y - 3.0, 320.0, 26.0);
                 }
@@ -977,7 +1221,7 @@ fn render_pause() {
                 y += 32.0;
 
                 // SFX volume
-                if PAUSE_INDEX == 1 {
+                if WORLD.sim.pause_index == 1 {
                     set_color(0x00FFFF30);
                     draw_rect(base_x - 10.0, y - 3.0, 320.0, 26.0);
                 }
@@ -990,7 +1234,7 @@ fn render_pause() {
                 y += 32.0;
 
                 // Shake
-                if PAUSE_INDEX == 2 {
+                if WORLD.sim.pause_index == 2 {
                     set_color(0x00FFFF30);
                     draw_rect(base_x - 10.0, y - 3.0, 320.0, 26.0);
                 }
@@ -1010,7 +1254,7 @@ fn render_pause() {
                 y += 32.0;
 
                 // Flash
-                if PAUSE_INDEX == 3 {
+                if WORLD.sim.pause_index == 3 {
                     set_color(0x00FFFF30);
                     draw_rect(base_x - 10.0, y - 3.0, 320.0, 26.0);
                 }
@@ -1030,7 +1274,7 @@ fn render_pause() {
                 y += 32.0;
 
                 // Back
-                if PAUSE_INDEX == 4 {
+                if WORLD.sim.pause_index == 4 {
                     set_color(0x00FFFF30);
                     draw_rect(base_x - 10.0, y - 3.0, 320.0, 26.0);
                 }
@@ -1047,13 +1291,13 @@ fn render_pause() {
 pub fn render_ui() {
     unsafe {
         // Impact flash overlay (drawn first, covers everything)
-        if IMPACT_FLASH > 0 {
-            let flash_alpha = (IMPACT_FLASH as f32 / 3.0 * 150.0) as u32;
+        if WORLD.fx.impact_flash > 0 {
+            let flash_alpha = (WORLD.fx.impact_flash as f32 / 3.0 * 150.0) as u32;
             set_color(0xFFFFFF00 | flash_alpha);
             draw_rect(0.0, 0.0, 960.0, 540.0);
         }
 
-        match GAME_STATE.phase {
+        match WORLD.sim.game.phase {
             GamePhase::Title => {
                 render_title();
                 return;
@@ -1070,25 +1314,67 @@ pub fn render_ui() {
         }
 
         // Demo watermark
-        if GAME_STATE.demo_mode {
+        if WORLD.sim.game.demo_mode {
             set_color(0x00FFFFFF);
             draw_text_str("DEMO", 885.0, 12.0, 16.0);
         }
 
-        // Score display
-        let active_count = PLAYERS.iter().filter(|p| p.active).count() as u32;
+        // Score display (team totals first in team mode)
+        let team_mode = WORLD.sim.config.team_mode;
+        let rule = WORLD.sim.config.rule;
+        let team_rows = if team_mode { NUM_TEAMS } else { 0 };
+        let active_count = WORLD.sim.players.iter().filter(|p| p.active).count() as u32;
         set_color(0x000000AA);
-        draw_rect(10.0, 10.0, 260.0, 30.0 + (active_count as f32 * 28.0));
+        draw_rect(
+            10.0,
+            10.0,
+            260.0,
+            30.0 + ((active_count + team_rows) as f32 * 28.0),
+        );
 
-        let win_kills = CONFIG.kills_to_win.max(1);
+        let win_kills = WORLD.sim.config.kills_to_win.max(1);
+        let hill_target = hill_target_ticks();
         let mut y = 40.0;
-        for (i, player) in PLAYERS.iter().enumerate() {
+        for team in 0..team_rows {
+            let color = TEAM_COLORS[team as usize];
+            set_color(color);
+            draw_rect(18.0, y + 4.0, 12.0, 12.0);
+            draw_text_str(TEAM_NAMES[team as usize], 35.0, y, 16.0);
+            let mut kbuf = [0u8; 10];
+            match rule {
+                MatchRule::Kills => {
+                    draw_text_str(u32_to_str(team_score(team), &mut kbuf), 125.0, y, 16.0);
+                    set_color(0x808080FF);
+                    draw_text_str("/", 145.0, y, 16.0);
+                    let mut wbuf = [0u8; 10];
+                    draw_text_str(u32_to_str(win_kills, &mut wbuf), 155.0, y, 16.0);
+                }
+                MatchRule::Stock => {
+                    // Lives left across the team
+                    let lives: u32 = WORLD
+                        .sim
+                        .players
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, p)| p.active && team_of(*i) == team)
+                        .map(|(_, p)| p.stocks)
+                        .sum();
+                    draw_text_str("LIVES", 70.0, y, 14.0);
+                    draw_text_str(u32_to_str(lives, &mut kbuf), 125.0, y, 16.0);
+                }
+                MatchRule::Hill => {
+                    draw_progress_bar(125.0, y + 4.0, team_score(team), hill_target, color);
+                }
+            }
+            y += 28.0;
+        }
+        for (i, player) in WORLD.sim.players.iter().enumerate() {
             if !player.active {
                 continue;
             }
 
             // Color swatch + label
-            set_color(PLAYER_COLORS[i]);
+            set_color(player_color(i));
             draw_rect(18.0, y + 4.0, 12.0, 12.0);
             set_color(0xFFFFFFFF);
             let label = match i {
@@ -1104,21 +1390,53 @@ pub fn render_ui() {
                 draw_text_str("CPU", 68.0, y, 14.0);
             }
 
-            // Kills / goal
-            let mut kbuf = [0u8; 10];
-            set_color(PLAYER_COLORS[i]);
-            draw_text_str(u32_to_str(player.kills, &mut kbuf), 125.0, y, 16.0);
-            set_color(0x808080FF);
-            draw_text_str("/", 145.0, y, 16.0);
-            let mut wbuf = [0u8; 10];
-            draw_text_str(u32_to_str(win_kills, &mut wbuf), 155.0, y, 16.0);
+            match rule {
+                MatchRule::Kills => {
+                    // Kills / goal (the goal sits on the team rows in team mode)
+                    let mut kbuf = [0u8; 10];
+                    set_color(player_color(i));
+                    draw_text_str(u32_to_str(player.kills, &mut kbuf), 125.0, y, 16.0);
+                    if !team_mode {
+                        set_color(0x808080FF);
+                        draw_text_str("/", 145.0, y, 16.0);
+                        let mut wbuf = [0u8; 10];
+                        draw_text_str(u32_to_str(win_kills, &mut wbuf), 155.0, y, 16.0);
+                    }
+                }
+                MatchRule::Stock => {
+                    // Stock icons (lost stocks stay as dim outlines)
+                    let max_stocks = WORLD.sim.config.stocks.clamp(1, 5);
+                    for k in 0..max_stocks {
+                        let color = if k < player.stocks {
+                            player_color(i)
+                        } else {
+                            0x40404080
+                        };
+                        set_color(color);
+                        draw_rect(125.0 + k as f32 * 12.0, y + 5.0, 8.0, 10.0);
+                    }
+                }
+                MatchRule::Hill => {
+                    // Hold progress (own share of the team total in team mode)
+                    draw_progress_bar(
+                        125.0,
+                        y + 4.0,
+                        player_score(i),
+                        hill_target,
+                        player_color(i),
+                    );
+                }
+            }
 
             // Status
-            if player.dead {
+            if player.eliminated {
+                set_color(0x808080FF);
+                draw_text_str("OUT", 190.0, y, 14.0);
+            } else if player.dead {
                 set_color(0xFF4040FF);
                 draw_text_str("DEAD", 190.0, y, 14.0);
                 if player.respawn_timer > 0 {
-                    let secs = (player.respawn_timer + 59) / 60;
+                    let secs = player.respawn_timer.div_ceil(60);
                     let mut sbuf = [0u8; 10];
                     set_color(0xCCCCCCFF);
                     draw_text_str(u32_to_str(secs, &mut sbuf), 232.0, y, 14.0);
@@ -1126,6 +1444,9 @@ pub fn render_ui() {
             } else if player.invuln_timer > 0 {
                 set_color(0x00FFFFAA);
                 draw_text_str("SAFE", 190.0, y, 14.0);
+            } else if player.weapon != Weapon::Pistol {
+                set_color(player.weapon.color());
+                draw_text_str(player.weapon.name(), 190.0, y, 14.0);
             }
 
             y += 28.0;
@@ -1135,18 +1456,27 @@ pub fn render_ui() {
         set_color(0xAAAAAAFF);
         draw_text_str("ROUND", 420.0, 20.0, 18.0);
         let mut rbuf = [0u8; 10];
-        draw_text_str(u32_to_str(ROUND_NUMBER, &mut rbuf), 485.0, 20.0, 18.0);
+        draw_text_str(
+            u32_to_str(WORLD.sim.round_number, &mut rbuf),
+            485.0,
+            20.0,
+            18.0,
+        );
         set_color(0x808080FF);
-        draw_text_str(stage_name(GAME_STATE.current_stage), 410.0, 42.0, 14.0);
+        draw_text_str(stage_name(WORLD.sim.game.current_stage), 410.0, 42.0, 14.0);
 
         // Timer / overtime
-        if CONFIG.round_time_seconds > 0 {
-            if GAME_STATE.overtime {
-                let alpha = if (TICK / 15) % 2 == 0 { 255 } else { 180 };
+        if WORLD.sim.config.round_time_seconds > 0 {
+            if WORLD.sim.game.overtime {
+                let alpha = if (WORLD.sim.tick / 15).is_multiple_of(2) {
+                    255
+                } else {
+                    180
+                };
                 set_color(with_alpha(0xFF4040FF, alpha));
                 draw_text_str("OVERTIME", 820.0, 20.0, 18.0);
             } else {
-                let total_secs = (GAME_STATE.round_time_left + 59) / 60;
+                let total_secs = WORLD.sim.game.round_time_left.div_ceil(60);
                 let mut tbuf = [0u8; 6];
                 set_color(0xFFFFFFFF);
                 draw_text_str(mmss_to_str(total_secs, &mut tbuf), 840.0, 20.0, 18.0);
@@ -1154,35 +1484,44 @@ pub fn render_ui() {
         }
 
         // Deflect popup
-        if DEFLECT_POPUP_TICKS > 0 {
-            let a = (DEFLECT_POPUP_TICKS.min(20) * 12).min(220);
-            set_color(with_alpha(0x00FFFFFF, a));
-            let who = match DEFLECT_PLAYER {
+        if WORLD.fx.deflect_popup_ticks > 0 {
+            let a = (WORLD.fx.deflect_popup_ticks.min(20) * 12).min(220);
+            let color = if WORLD.fx.deflect_perfect {
+                0xFFD040FF
+            } else {
+                0x00FFFFFF
+            };
+            set_color(with_alpha(color, a));
+            let who = match WORLD.fx.deflect_player {
                 0 => "P1",
                 1 => "P2",
                 2 => "P3",
                 _ => "P4",
             };
             draw_text_str(who, 420.0, 105.0, 18.0);
-            draw_text_str("DEFLECT!", 450.0, 105.0, 18.0);
+            if WORLD.fx.deflect_perfect {
+                draw_text_str("PERFECT PARRY!", 450.0, 105.0, 18.0);
+            } else {
+                draw_text_str("DEFLECT!", 450.0, 105.0, 18.0);
+            }
         }
 
         // Final KO overlay
-        if GAME_STATE.phase == GamePhase::FinalKo {
-            let pulse = libm::sinf(TICK as f32 * 0.25) * 0.15 + 1.0;
+        if WORLD.sim.game.phase == GamePhase::FinalKo {
+            let pulse = libm::sinf(WORLD.sim.tick as f32 * 0.25) * 0.15 + 1.0;
             set_color(0xFF4040FF);
             draw_text_str("FINAL KO", 390.0, 80.0, 26.0 * pulse);
         }
 
         // Off-screen indicators (approximate mapping for vertical escapes)
         {
-            let left = GAME_STATE.arena_left;
-            let right = GAME_STATE.arena_right;
+            let left = WORLD.sim.game.arena_left;
+            let right = WORLD.sim.game.arena_right;
             let y_min = -8.0;
             let y_max = 8.0;
             let w = (right - left).max(0.001);
 
-            for (i, p) in PLAYERS.iter().enumerate() {
+            for (i, p) in WORLD.sim.players.iter().enumerate() {
                 if !p.active || p.dead {
                     continue;
                 }
@@ -1192,19 +1531,19 @@ pub fn render_ui() {
                 let sx = nx * 960.0;
 
                 if cy > y_max {
-                    set_color(PLAYER_COLORS[i]);
+                    set_color(player_color(i));
                     draw_rect(sx - 10.0, 8.0, 20.0, 8.0);
                 } else if cy < y_min {
-                    set_color(PLAYER_COLORS[i]);
+                    set_color(player_color(i));
                     draw_rect(sx - 10.0, 524.0, 20.0, 8.0);
                 }
             }
         }
 
         // Countdown with animation
-        if GAME_STATE.phase == GamePhase::Countdown {
-            let seconds = (GAME_STATE.countdown / 60) + 1;
-            let frame_in_second = GAME_STATE.countdown % 60;
+        if WORLD.sim.game.phase == GamePhase::Countdown {
+            let seconds = (WORLD.sim.game.countdown / 60) + 1;
+            let frame_in_second = WORLD.sim.game.countdown % 60;
 
             // Calculate animation progress (0.0 at start of second, 1.0 at end)
             let progress = 1.0 - (frame_in_second as f32 / 60.0);
@@ -1267,20 +1606,23 @@ pub fn render_ui() {
         }
 
         // Match end with polished animation
-        if GAME_STATE.phase == GamePhase::MatchEnd {
+        if WORLD.sim.game.phase == GamePhase::MatchEnd {
             // Animation progress based on match end tick
-            let anim_tick = MATCH_END_TICK;
+            let anim_tick = WORLD.sim.match_end_tick;
 
             // Background fade in
             let bg_alpha = ((anim_tick as f32 / 30.0).min(1.0) * 221.0) as u32;
-            set_color(0x00000000 | bg_alpha);
+            set_color(bg_alpha);
             draw_rect(0.0, 0.0, 960.0, 540.0);
 
             // Find winner (prefer stored winner_idx, but fall back to scanning)
-            let win_kills = CONFIG.kills_to_win.max(1);
-            let mut winner_idx = GAME_STATE.winner_idx as usize;
-            for (i, player) in PLAYERS.iter().enumerate() {
-                if player.active && player.kills >= win_kills {
+            let win_kills = WORLD.sim.config.kills_to_win.max(1);
+            let mut winner_idx = WORLD.sim.game.winner_idx as usize;
+            for (i, player) in WORLD.sim.players.iter().enumerate() {
+                if WORLD.sim.config.rule == MatchRule::Kills
+                    && player.active
+                    && match_score(i) >= win_kills
+                {
                     winner_idx = i;
                     break;
                 }
@@ -1299,18 +1641,30 @@ pub fn render_ui() {
 
             // Winner color with pulsing
             let pulse = libm::sinf(anim_tick as f32 * 0.15) * 0.2 + 0.8;
-            let winner_color = PLAYER_COLORS[winner_idx];
+            let winner_team = team_of(winner_idx) as usize;
+            let winner_color = if WORLD.sim.config.team_mode {
+                TEAM_COLORS[winner_team]
+            } else {
+                PLAYER_COLORS[winner_idx]
+            };
             let r = (((winner_color >> 24) & 0xFF) as f32 * pulse) as u32;
             let g = (((winner_color >> 16) & 0xFF) as f32 * pulse) as u32;
             let b = (((winner_color >> 8) & 0xFF) as f32 * pulse) as u32;
             let pulsing_color = (r << 24) | (g << 16) | (b << 8) | 0xFF;
 
             // Winner text with glow
-            let winner_text = match winner_idx {
-                0 => "PLAYER 1 WINS!",
-                1 => "PLAYER 2 WINS!",
-                2 => "PLAYER 3 WINS!",
-                _ => "PLAYER 4 WINS!",
+            let winner_text = if WORLD.sim.config.team_mode {
+                match winner_team {
+                    0 => "RED TEAM WINS!",
+                    _ => "BLUE TEAM WINS!",
+                }
+            } else {
+                match winner_idx {
+                    0 => "PLAYER 1 WINS!",
+                    1 => "PLAYER 2 WINS!",
+                    2 => "PLAYER 3 WINS!",
+                    _ => "PLAYER 4 WINS!",
+                }
             };
 
             // Glow layers
@@ -1331,7 +1685,11 @@ pub fn render_ui() {
 
             if anim_tick > 45 {
                 // Blinking prompt
-                let blink_alpha = if (anim_tick / 30) % 2 == 0 { 255 } else { 180 };
+                let blink_alpha = if (anim_tick / 30).is_multiple_of(2) {
+                    255
+                } else {
+                    180
+                };
                 set_color(0xCCCCCC00 | blink_alpha);
                 draw_text_str(
                     "START: rematch    B: lobby",
@@ -1370,10 +1728,32 @@ pub fn render_ui() {
         }
 
         // Stage transition overlay
-        if TRANSITION_PHASE != TransitionPhase::None {
-            let alpha = (TRANSITION_PROGRESS * 255.0) as u32;
-            set_color(0x00000000 | alpha);
+        if WORLD.fx.transition_phase != TransitionPhase::None {
+            let alpha = (WORLD.fx.transition_progress * 255.0) as u32;
+            set_color(alpha);
             draw_rect(0.0, 0.0, 960.0, 540.0);
         }
     }
 }
+
+/// Debug banner naming the first diverging tick and subsystem
+pub fn render_desync_overlay() {
+    if !SHOW_DESYNC_OVERLAY {
+        return;
+    }
+    let Some(desync) = checksum::first_desync() else {
+        return;
+    };
+
+    unsafe {
+        set_color(0x000000CC);
+        draw_rect(300.0, 500.0, 360.0, 30.0);
+        set_color(0xFF4040FF);
+        draw_text_str("DESYNC", 310.0, 507.0, 16.0);
+        let mut tbuf = [0u8; 10];
+        set_color(0xFFFFFFFF);
+        draw_text_str("TICK", 390.0, 507.0, 16.0);
+        draw_text_str(u32_to_str(desync.tick, &mut tbuf), 440.0, 507.0, 16.0);
+        draw_text_str(desync.subsystem.name(), 540.0, 507.0, 16.0);
+    }
+}
@@ -0,0 +1,858 @@
+//! Input recording and replays
+//!
+//! The simulation is a fixed-timestep, deterministic function of its inputs,
+//! so a replay only needs the match setup plus every player's `Controls` for
+//! each tick. Playback feeds the recorded controls back through `update()`
+//! and checks the final world checksum against the one stored in the file.
+//!
+//! Binary layout (little-endian):
+//! - header: "NDRP", version, seed, start tick, config, participants
+//! - frames: run length (1-255), flags, then encoded controls per player bit
+//! - footer: 0 (end marker), final tick, final checksum parts
+//!
+//! Every match is recorded into a fixed buffer here, where the last one
+//! stays until the next match starts; playback copies the replay in. Host
+//! tools save and load the bytes as `.ndrp` files. Nothing here allocates.
+
+use crate::bot_profile::NUM_BOT_PROFILES;
+use crate::checksum::{self, Checksum, SUBSYSTEM_COUNT};
+use crate::game_state::{GameConfig, GamePhase, MatchRule};
+use crate::player::{axis_from_step, axis_step, Controls, Player, MAX_PLAYERS};
+use crate::rng;
+use crate::world::WORLD;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+const MAGIC: [u8; 4] = *b"NDRP";
+pub const REPLAY_VERSION: u8 = 9;
+/// Largest replay. Bot matches take about 1.2 bytes a tick; four analog
+/// sticks that never rest or hit full tilt take 13, which still fits about
+/// five and a half minutes.
+pub const REPLAY_CAPACITY: usize = 256 * 1024;
+
+/// Largest encoded frame: flags + 4 players x (flags + two axis steps)
+const MAX_FRAME_BYTES: usize = 1 + MAX_PLAYERS * 3;
+
+// Frame flags (low bits are the player mask)
+const FRAME_HELD: u8 = 0x80; // Sim was paused this tick; only the tick counter moves
+
+// Controls flags
+const CTRL_JUMP_PRESSED: u8 = 0x01;
+const CTRL_JUMP_HELD: u8 = 0x02;
+const CTRL_SHOOT: u8 = 0x04;
+const CTRL_MELEE: u8 = 0x08;
+
+// Axis encodings (2 bits each); digital values cost no extra bytes
+const AXIS_ZERO: u8 = 0;
+const AXIS_POS: u8 = 1;
+const AXIS_NEG: u8 = 2;
+const AXIS_RAW: u8 = 3;
+
+// =============================================================================
+// DATA STRUCTURES
+// =============================================================================
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplayError {
+    BadMagic,
+    UnsupportedVersion,
+    BadConfig,
+    Truncated,
+    BufferFull, // Recording ran out of room, or a replay is over REPLAY_CAPACITY
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReplayMode {
+    Off,
+    Armed, // Will start recording at the next match start
+    Recording,
+    Playing,
+    Finished, // Recording complete, or playback reached the footer
+}
+
+/// One seat in the recorded match
+#[derive(Clone, Copy)]
+pub struct ReplayParticipant {
+    pub active: bool,
+    pub is_bot: bool,
+    pub ai_seed: u32,
+}
+
+/// Everything needed to rebuild the match start
+#[derive(Clone, Copy)]
+pub struct ReplayHeader {
+    pub seed: u32,
+    pub start_tick: u32,
+    pub config: GameConfig,
+    pub participants: [ReplayParticipant; MAX_PLAYERS],
+}
+
+impl ReplayHeader {
+    /// Snapshot the live world at match start
+    pub fn capture() -> Self {
+        unsafe {
+            let mut participants = [ReplayParticipant {
+                active: false,
+                is_bot: false,
+                ai_seed: 0,
+            }; MAX_PLAYERS];
+            for (slot, p) in participants.iter_mut().zip(WORLD.sim.players.iter()) {
+                slot.active = p.active;
+                slot.is_bot = p.is_bot;
+                slot.ai_seed = p.ai_seed;
+            }
+            Self {
+                seed: WORLD.sim.rng.state(),
+                start_tick: WORLD.sim.tick,
+                config: WORLD.sim.config,
+                participants,
+            }
+        }
+    }
+
+    /// Put the world back into the recorded match-start state
+    pub fn apply(&self) {
+        unsafe {
+            WORLD.sim.config = self.config;
+            WORLD.sim.tick = self.start_tick;
+            WORLD.sim.game.demo_mode = false;
+            for (p, slot) in WORLD.sim.players.iter_mut().zip(self.participants.iter()) {
+                *p = Player::new();
+                p.active = slot.active;
+                p.ready = slot.active;
+                p.is_bot = slot.is_bot;
+                p.ai_seed = slot.ai_seed;
+            }
+            rng::seed_gameplay(self.seed);
+        }
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.bytes(&MAGIC);
+        w.u8(REPLAY_VERSION);
+        w.u32(self.seed);
+        w.u32(self.start_tick);
+        w.u8(self.config.stage_select as u8);
+        w.u8(self.config.kills_to_win as u8);
+        w.u16(self.config.round_time_seconds as u16);
+        w.u8(self.config.fill_bots as u8);
+        w.u8(self.config.bot_difficulty as u8);
+        w.u8(self.config.team_mode as u8 | (self.config.friendly_fire as u8) << 1);
+        w.u8(self.config.rule as u8);
+        w.u8(self.config.stocks as u8);
+        w.u8(self.config.hill_seconds as u8);
+        for (i, slot) in self.participants.iter().enumerate() {
+            w.u8(slot.active as u8 | (slot.is_bot as u8) << 1);
+            w.u8(self.config.teams[i] as u8);
+            w.u8(self.config.bot_profiles[i] as u8);
+            w.u32(slot.ai_seed);
+        }
+    }
+
+    fn read(r: &mut Reader) -> Result<Self, ReplayError> {
+        if r.bytes(4)? != MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        if r.u8()? != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion);
+        }
+        let seed = r.u32()?;
+        let start_tick = r.u32()?;
+        let mut config = GameConfig::new();
+        config.stage_select = r.u8()? as u32;
+        config.kills_to_win = r.u8()? as u32;
+        config.round_time_seconds = r.u16()? as u32;
+        config.fill_bots = r.u8()? != 0;
+        config.bot_difficulty = r.u8()? as u32;
+        let team_flags = r.u8()?;
+        config.team_mode = team_flags & 1 != 0;
+        config.friendly_fire = team_flags & 2 != 0;
+        config.rule = *MatchRule::ALL
+            .get(r.u8()? as usize)
+            .ok_or(ReplayError::BadConfig)?;
+        config.stocks = r.u8()? as u32;
+        config.hill_seconds = r.u8()? as u32;
+
+        let mut participants = [ReplayParticipant {
+            active: false,
+            is_bot: false,
+            ai_seed: 0,
+        }; MAX_PLAYERS];
+        for (i, slot) in participants.iter_mut().enumerate() {
+            let flags = r.u8()?;
+            slot.active = flags & 1 != 0;
+            slot.is_bot = flags & 2 != 0;
+            config.teams[i] = r.u8()? as u32;
+            config.bot_profiles[i] = r.u8()? as u32;
+            if config.bot_profiles[i] >= NUM_BOT_PROFILES {
+                return Err(ReplayError::BadConfig);
+            }
+            slot.ai_seed = r.u32()?;
+        }
+
+        Ok(Self {
+            seed,
+            start_tick,
+            config,
+            participants,
+        })
+    }
+}
+
+/// Recorded end state, used to verify playback
+#[derive(Clone, Copy)]
+struct Footer {
+    tick: u32,
+    checksum: Checksum,
+}
+
+/// One tick of input
+#[derive(Clone, Copy)]
+struct Frame {
+    held: bool,
+    mask: u8,
+    controls: [Controls; MAX_PLAYERS],
+}
+
+impl Frame {
+    const fn new() -> Self {
+        Self {
+            held: false,
+            mask: 0,
+            controls: [Controls::new(); MAX_PLAYERS],
+        }
+    }
+}
+
+// =============================================================================
+// ENCODING
+// =============================================================================
+
+/// Axes arrive quantized (`Controls::quantized`), so every other value is
+/// stored exactly as its step count
+fn axis_mode(v: f32) -> u8 {
+    if v.to_bits() == 0.0f32.to_bits() {
+        AXIS_ZERO
+    } else if v.to_bits() == 1.0f32.to_bits() {
+        AXIS_POS
+    } else if v.to_bits() == (-1.0f32).to_bits() {
+        AXIS_NEG
+    } else {
+        AXIS_RAW
+    }
+}
+
+fn axis_value(mode: u8, r: &mut Reader) -> Result<f32, ReplayError> {
+    Ok(match mode {
+        AXIS_ZERO => 0.0,
+        AXIS_POS => 1.0,
+        AXIS_NEG => -1.0,
+        _ => axis_from_step(r.u8()? as i8),
+    })
+}
+
+/// Encode a frame into `out`, returning the byte count
+fn encode_frame(frame: &Frame, out: &mut [u8; MAX_FRAME_BYTES]) -> usize {
+    let mut len = 0;
+    let mut push = |bytes: &[u8]| {
+        out[len..len + bytes.len()].copy_from_slice(bytes);
+        len += bytes.len();
+    };
+
+    push(&[frame.mask | if frame.held { FRAME_HELD } else { 0 }]);
+    for (i, c) in frame.controls.iter().enumerate() {
+        if frame.mask & (1 << i) == 0 {
+            continue;
+        }
+        let x_mode = axis_mode(c.x);
+        let y_mode = axis_mode(c.y);
+        let mut flags = x_mode << 4 | y_mode << 6;
+        if c.jump_pressed {
+            flags |= CTRL_JUMP_PRESSED;
+        }
+        if c.jump_held {
+            flags |= CTRL_JUMP_HELD;
+        }
+        if c.shoot_pressed {
+            flags |= CTRL_SHOOT;
+        }
+        if c.melee_pressed {
+            flags |= CTRL_MELEE;
+        }
+        push(&[flags]);
+        if x_mode == AXIS_RAW {
+            push(&[axis_step(c.x) as u8]);
+        }
+        if y_mode == AXIS_RAW {
+            push(&[axis_step(c.y) as u8]);
+        }
+    }
+    len
+}
+
+fn decode_frame(r: &mut Reader) -> Result<Frame, ReplayError> {
+    let mut frame = Frame::new();
+    let flags = r.u8()?;
+    frame.held = flags & FRAME_HELD != 0;
+    frame.mask = flags & !FRAME_HELD;
+    for (i, c) in frame.controls.iter_mut().enumerate() {
+        if frame.mask & (1 << i) == 0 {
+            continue;
+        }
+        let flags = r.u8()?;
+        c.x = axis_value((flags >> 4) & 3, r)?;
+        c.y = axis_value((flags >> 6) & 3, r)?;
+        c.jump_pressed = flags & CTRL_JUMP_PRESSED != 0;
+        c.jump_held = flags & CTRL_JUMP_HELD != 0;
+        c.shoot_pressed = flags & CTRL_SHOOT != 0;
+        c.melee_pressed = flags & CTRL_MELEE != 0;
+    }
+    Ok(frame)
+}
+
+// =============================================================================
+// WRITER
+// =============================================================================
+
+struct Writer {
+    buf: &'static mut [u8],
+    len: usize,
+    overflow: bool,
+    run_pos: Option<usize>, // Offset of the current frame's run-length byte
+    last: [u8; MAX_FRAME_BYTES],
+    last_len: usize,
+}
+
+impl Writer {
+    fn new(buf: &'static mut [u8]) -> Self {
+        Self {
+            buf,
+            len: 0,
+            overflow: false,
+            run_pos: None,
+            last: [0; MAX_FRAME_BYTES],
+            last_len: 0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.len = 0;
+        self.overflow = false;
+        self.run_pos = None;
+        self.last_len = 0;
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        if self.len + bytes.len() > self.buf.len() {
+            self.overflow = true;
+            return;
+        }
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    /// Append a frame, extending the previous run when identical
+    fn frame(&mut self, frame: &Frame) {
+        let mut encoded = [0u8; MAX_FRAME_BYTES];
+        let len = encode_frame(frame, &mut encoded);
+
+        if let Some(pos) = self.run_pos {
+            if self.buf[pos] < u8::MAX && encoded[..len] == self.last[..self.last_len] {
+                self.buf[pos] += 1;
+                return;
+            }
+        }
+
+        self.run_pos = Some(self.len);
+        self.u8(1);
+        self.bytes(&encoded[..len]);
+        self.last = encoded;
+        self.last_len = len;
+    }
+
+    fn footer(&mut self, footer: &Footer) {
+        self.u8(0);
+        self.u32(footer.tick);
+        for part in footer.checksum.parts {
+            self.u32(part);
+        }
+    }
+}
+
+// =============================================================================
+// READER
+// =============================================================================
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], ReplayError> {
+        let data = self.data;
+        let out = data
+            .get(self.pos..self.pos + n)
+            .ok_or(ReplayError::Truncated)?;
+        self.pos += n;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn footer(&mut self) -> Result<Footer, ReplayError> {
+        let tick = self.u32()?;
+        let mut checksum = Checksum::new();
+        for part in checksum.parts.iter_mut().take(SUBSYSTEM_COUNT) {
+            *part = self.u32()?;
+        }
+        Ok(Footer { tick, checksum })
+    }
+}
+
+/// Decoded playback position
+struct Playback {
+    reader: Reader<'static>,
+    frame: Frame,
+    run_left: u32,
+    footer: Option<Footer>,
+}
+
+impl Playback {
+    /// Step to the next recorded tick (or the footer)
+    fn advance(&mut self) -> Result<(), ReplayError> {
+        if self.run_left > 1 {
+            self.run_left -= 1;
+            return Ok(());
+        }
+        let run = self.reader.u8()?;
+        if run == 0 {
+            self.run_left = 0;
+            self.footer = Some(self.reader.footer()?);
+        } else {
+            self.run_left = run as u32;
+            self.frame = decode_frame(&mut self.reader)?;
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// GLOBAL STATE
+// =============================================================================
+
+struct ReplayState {
+    mode: ReplayMode,
+    writer: Option<Writer>,
+    playback: Option<Playback>,
+    pending: Frame,  // Controls read so far this tick (recording)
+    last_held: bool, // Previous tick ended paused (its resume tick is held too)
+    start_tick: u32, // Tick the header was captured on (its remainder isn't replayed)
+    error: Option<ReplayError>,
+}
+
+/// Bytes of the recording being written, or the replay being played
+static mut DATA: [u8; REPLAY_CAPACITY] = [0; REPLAY_CAPACITY];
+
+static mut REPLAY: ReplayState = ReplayState {
+    mode: ReplayMode::Off,
+    writer: None,
+    playback: None,
+    pending: Frame::new(),
+    last_held: false,
+    start_tick: 0,
+    error: None,
+};
+
+// =============================================================================
+// RECORDING
+// =============================================================================
+
+/// Record the next match (every match start arms this)
+pub fn arm_recording() {
+    unsafe {
+        REPLAY.mode = ReplayMode::Armed;
+        REPLAY.playback = None;
+        if REPLAY.writer.is_none() {
+            REPLAY.writer = Some(Writer::new(&mut DATA));
+        }
+        REPLAY.error = None;
+    }
+}
+
+/// Match (re)start hook: writes the header if a recording is armed
+pub fn begin_recording() {
+    unsafe {
+        if !matches!(REPLAY.mode, ReplayMode::Armed | ReplayMode::Recording) {
+            return;
+        }
+        let Some(writer) = REPLAY.writer.as_mut() else {
+            return;
+        };
+        writer.reset();
+        ReplayHeader::capture().write(writer);
+        REPLAY.pending = Frame::new();
+        REPLAY.last_held = false;
+        REPLAY.start_tick = WORLD.sim.tick;
+        REPLAY.mode = ReplayMode::Recording;
+    }
+}
+
+/// Remember the controls a player used this tick
+pub fn record_controls(idx: usize, controls: &Controls) {
+    unsafe {
+        if REPLAY.mode != ReplayMode::Recording || idx >= MAX_PLAYERS {
+            return;
+        }
+        REPLAY.pending.mask |= 1 << idx;
+        REPLAY.pending.controls[idx] = *controls;
+    }
+}
+
+/// Abandon an in-progress recording or playback (round restart, lobby).
+/// A finished recording is kept so it can still be saved.
+pub fn stop() {
+    unsafe {
+        match REPLAY.mode {
+            ReplayMode::Recording => {
+                REPLAY.mode = ReplayMode::Off;
+                REPLAY.writer = None;
+            }
+            ReplayMode::Playing => {
+                REPLAY.mode = ReplayMode::Off;
+                REPLAY.playback = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The completed replay bytes, once the recorded match has ended
+pub fn recorded() -> Option<&'static [u8]> {
+    unsafe {
+        if REPLAY.mode != ReplayMode::Finished {
+            return None;
+        }
+        REPLAY.writer.as_ref().map(|w| &w.buf[..w.len])
+    }
+}
+
+// =============================================================================
+// PLAYBACK
+// =============================================================================
+
+/// Parse a replay's header without starting playback
+pub fn read_header(data: &[u8]) -> Result<ReplayHeader, ReplayError> {
+    ReplayHeader::read(&mut Reader { data, pos: 0 })
+}
+
+/// Load a replay for playback; the caller applies the header and starts the
+/// match (see `crate::play_replay`). Whatever was recorded is dropped.
+pub fn start_playback(data: &[u8]) -> Result<ReplayHeader, ReplayError> {
+    read_header(data)?;
+    if data.len() > REPLAY_CAPACITY {
+        return Err(ReplayError::BufferFull);
+    }
+
+    unsafe {
+        REPLAY.mode = ReplayMode::Off;
+        REPLAY.writer = None;
+        REPLAY.playback = None;
+        DATA[..data.len()].copy_from_slice(data);
+
+        let mut reader = Reader {
+            data: &DATA[..data.len()],
+            pos: 0,
+        };
+        let header = ReplayHeader::read(&mut reader)?;
+        let mut playback = Playback {
+            reader,
+            frame: Frame::new(),
+            run_left: 0,
+            footer: None,
+        };
+        playback.advance()?;
+
+        REPLAY.mode = ReplayMode::Playing;
+        REPLAY.playback = Some(playback);
+        REPLAY.error = None;
+        Ok(header)
+    }
+}
+
+pub fn is_playing() -> bool {
+    unsafe { REPLAY.mode == ReplayMode::Playing }
+}
+
+/// True if the upcoming tick was spent paused in the recording
+pub fn playback_holds_tick() -> bool {
+    unsafe {
+        REPLAY.mode == ReplayMode::Playing
+            && REPLAY
+                .playback
+                .as_ref()
+                .is_some_and(|pb| pb.footer.is_none() && pb.frame.held)
+    }
+}
+
+/// Recorded controls for a player this tick (None when not playing back)
+pub fn playback_controls(idx: usize) -> Option<Controls> {
+    unsafe {
+        if REPLAY.mode != ReplayMode::Playing {
+            return None;
+        }
+        let pb = REPLAY.playback.as_ref()?;
+        if pb.footer.is_some() || pb.frame.mask & (1 << idx) == 0 {
+            // Out of recorded input: the sim has already diverged
+            return Some(Controls::new());
+        }
+        Some(pb.frame.controls[idx])
+    }
+}
+
+// =============================================================================
+// TICK HOOK
+// =============================================================================
+
+/// End-of-tick bookkeeping (call after the checksum is recorded)
+pub fn end_tick() {
+    unsafe {
+        match REPLAY.mode {
+            ReplayMode::Recording => {
+                // Playback starts the match outside of update(), so the rest
+                // of the tick that started it has no frame
+                if WORLD.sim.tick == REPLAY.start_tick {
+                    return;
+                }
+
+                let paused = WORLD.sim.game.phase == GamePhase::Paused;
+                let mut frame = REPLAY.pending;
+                frame.held = paused || REPLAY.last_held;
+                REPLAY.last_held = paused;
+                REPLAY.pending = Frame::new();
+
+                let Some(writer) = REPLAY.writer.as_mut() else {
+                    return;
+                };
+                writer.frame(&frame);
+
+                if WORLD.sim.game.phase == GamePhase::MatchEnd {
+                    writer.footer(&Footer {
+                        tick: WORLD.sim.tick,
+                        checksum: checksum::current(),
+                    });
+                    REPLAY.mode = ReplayMode::Finished;
+                }
+                if writer.overflow {
+                    REPLAY.error = Some(ReplayError::BufferFull);
+                    REPLAY.mode = ReplayMode::Off;
+                }
+            }
+            ReplayMode::Playing => {
+                let Some(pb) = REPLAY.playback.as_mut() else {
+                    return;
+                };
+                if let Err(e) = pb.advance() {
+                    REPLAY.error = Some(e);
+                    REPLAY.mode = ReplayMode::Finished;
+                    return;
+                }
+                if let Some(footer) = pb.footer {
+                    // Verify against the recording; a mismatch shows up as a desync
+                    checksum::report_remote(footer.tick, &footer.checksum);
+                    REPLAY.mode = ReplayMode::Finished;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn mode() -> ReplayMode {
+    unsafe { REPLAY.mode }
+}
+
+pub fn last_error() -> Option<ReplayError> {
+    unsafe { REPLAY.error }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform;
+    use crate::player::{BUTTON_A, BUTTON_B, BUTTON_START};
+    use crate::world::World;
+
+    /// Run the sim until the replay module is done with the match
+    fn run_to_finish() {
+        for _ in 0..60 * 60 * 15 {
+            if mode() == ReplayMode::Finished {
+                return;
+            }
+            crate::update();
+        }
+        panic!("match never finished");
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut frame = Frame::new();
+        frame.held = true;
+        frame.mask = 0b1011;
+        frame.controls[0].x = 1.0;
+        frame.controls[0].jump_pressed = true;
+        frame.controls[1].x = -0.0;
+        frame.controls[1].y = -0.37;
+        frame.controls[1].shoot_pressed = true;
+        frame.controls[3].y = -1.0;
+        frame.controls[3].jump_held = true;
+        frame.controls[3].melee_pressed = true;
+        // As the sim hands them over
+        for c in &mut frame.controls {
+            *c = c.quantized();
+        }
+
+        let mut bytes = [0u8; MAX_FRAME_BYTES];
+        let len = encode_frame(&frame, &mut bytes);
+        let mut reader = Reader {
+            data: &bytes[..len],
+            pos: 0,
+        };
+        let decoded = decode_frame(&mut reader).unwrap();
+
+        assert_eq!(reader.pos, len);
+        assert!(decoded.held);
+        assert_eq!(decoded.mask, frame.mask);
+        for (a, b) in decoded.controls.iter().zip(frame.controls.iter()) {
+            assert!(a == b);
+            assert_eq!(a.x.to_bits(), b.x.to_bits());
+            assert_eq!(a.y.to_bits(), b.y.to_bits());
+        }
+    }
+
+    #[test]
+    fn bot_match_replays_without_desync() {
+        let _lock = platform::test_lock();
+        unsafe {
+            crate::init();
+            WORLD = World::new();
+            platform::host().reset(0, 7);
+            WORLD.sim.config.stage_select = 2;
+            WORLD.sim.config.bot_difficulty = 2;
+            WORLD.sim.config.bot_profiles = [1, 2, 0, 0];
+            crate::start_bot_match(2);
+            run_to_finish();
+            assert_eq!(last_error(), None);
+
+            let data = recorded().expect("match start arms a recording").to_vec();
+            let end_tick = WORLD.sim.tick;
+            let end_checksum = checksum::current();
+
+            // Playback must not lean on anything the recording run left behind
+            WORLD = World::new();
+            platform::host().reset(0, 99);
+            crate::play_replay(&data).unwrap();
+            run_to_finish();
+
+            assert_eq!(last_error(), None);
+            assert_eq!(checksum::first_desync(), None);
+            assert_eq!(WORLD.sim.tick, end_tick);
+            assert_eq!(checksum::current(), end_checksum);
+        }
+    }
+
+    #[test]
+    fn analog_match_replays_without_desync() {
+        let _lock = platform::test_lock();
+        let press = |held: u32| {
+            platform::host().set_input(0, held, 0.0, 0.0);
+            crate::update();
+            platform::host().end_tick();
+        };
+        unsafe {
+            WORLD = World::new();
+            platform::host().reset(1, 5);
+            crate::init();
+            WORLD.sim.config.stage_select = 0;
+            WORLD.sim.config.kills_to_win = 2;
+
+            // P1 joins in the lobby; a CPU fills the other slots
+            press(1 << BUTTON_A);
+            press(0);
+            press(1 << BUTTON_A);
+            press(0);
+            press(1 << BUTTON_START);
+            let start_tick = WORLD.sim.tick;
+
+            // Sweep the stick through values that are neither 0 nor full tilt
+            let mut t = 0u32;
+            while mode() == ReplayMode::Recording {
+                let x = (t * 37 % 200) as f32 / 100.0 - 0.995;
+                let y = (t * 53 % 180) as f32 / 100.0 - 0.9;
+                let held = if t % 40 < 3 { 1 << BUTTON_B } else { 0 };
+                platform::host().set_input(0, held, x, y);
+                crate::update();
+                platform::host().end_tick();
+                t += 1;
+                assert!(t < 60 * 60 * 15, "match never finished");
+            }
+            assert_eq!(last_error(), None);
+
+            // One byte per analog axis, not a raw f32
+            let data = recorded().expect("lobby start arms a recording").to_vec();
+            let ticks = (WORLD.sim.tick - start_tick) as usize;
+            let players = WORLD.sim.players.iter().filter(|p| p.active).count();
+            assert!(data.len() < 200 + ticks * (2 + players * 3));
+            let end_checksum = checksum::current();
+
+            WORLD = World::new();
+            platform::host().reset(1, 99);
+            crate::play_replay(&data).unwrap();
+            run_to_finish();
+
+            assert_eq!(last_error(), None);
+            assert_eq!(checksum::first_desync(), None);
+            assert_eq!(checksum::current(), end_checksum);
+        }
+    }
+
+    #[test]
+    fn oversized_and_damaged_replays_are_rejected() {
+        let _lock = platform::test_lock();
+        let mut data = vec![0u8; REPLAY_CAPACITY + 1];
+        assert_eq!(start_playback(&data).err(), Some(ReplayError::BadMagic));
+        data[..4].copy_from_slice(&MAGIC);
+        data[4] = REPLAY_VERSION;
+        assert_eq!(start_playback(&data).err(), Some(ReplayError::BufferFull));
+        assert_eq!(
+            start_playback(&data[..20]).err(),
+            Some(ReplayError::Truncated)
+        );
+    }
+}
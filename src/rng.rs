@@ -0,0 +1,76 @@
+//! Deterministic random number generators
+//!
+//! Gameplay draws from `WORLD.sim.rng`, which is seeded once per match and
+//! rolled back with the rest of the simulation. Particles and screen shake
+//! draw from `WORLD.fx.rng`, so visual-only changes (or toggling screen
+//! shake) can never shift the gameplay sequence.
+
+use crate::world::WORLD;
+
+// =============================================================================
+// RNG
+// =============================================================================
+
+/// xorshift32: tiny, fast, and identical on every target
+#[derive(Clone, Copy, PartialEq)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    /// Seed 0 is remapped (xorshift would stay stuck at zero)
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0x2545_F491 } else { seed },
+        }
+    }
+
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Random float in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        // 24 bits of mantissa keeps the result strictly below 1.0
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Random integer in [min, max)
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+        let span = (max - min) as u32;
+        min + (self.next_u32() % span) as i32
+    }
+}
+
+// =============================================================================
+// CONVENIENCE FUNCTIONS
+// =============================================================================
+
+/// Reseed the gameplay stream (match start / replay playback)
+pub fn seed_gameplay(seed: u32) {
+    unsafe {
+        WORLD.sim.rng = Rng::new(seed);
+    }
+}
+
+/// Gameplay random integer in [min, max)
+pub fn game_range(min: i32, max: i32) -> i32 {
+    unsafe { WORLD.sim.rng.range(min, max) }
+}
+
+/// Cosmetic random float in [0, 1) (particles, shake)
+pub fn fx_f32() -> f32 {
+    unsafe { WORLD.fx.rng.next_f32() }
+}
@@ -1,19 +1,62 @@
 //! Stage management
 //!
-//! Contains Platform struct, stage layouts, and EPU configuration.
+//! Stage layouts live in `assets/stages/*.stage` and are parsed by a
+//! `const fn` at compile time, so a malformed stage file fails the build.
+//! Adding a stage means dropping in a file and listing it in `STAGE_SOURCES`.
+//!
+//! Format: one directive per line, `#` starts a comment.
+//!
+//! ```text
+//! name        GRID ARENA        # display name (rest of line)
+//! background  grid              # grid | sunset | portal
+//! music       grid              # grid | scatter | ring
+//! arena       -10 10            # left/right wall x
+//! pit         -5.0              # pit surface y, or `none`
+//! platform    x y width height [collision]
+//! mover       x y width height speed min max [collision]
+//! spawn       x y               # one per player slot, in order
+//! crate       x y               # weapon crate spot (optional)
+//! ```
+//!
+//! `collision` is `oneway` (default), `solid` or `wall`; see `Collision`.
 
-use crate::game_state::GAME_STATE;
+use crate::game_state::MatchRule;
+use crate::player::MAX_PLAYERS;
+use crate::world::WORLD;
 
 // =============================================================================
 // CONSTANTS
 // =============================================================================
 
 pub const MAX_PLATFORMS: usize = 16;
+pub const MAX_SPAWNS: usize = 8;
+pub const MAX_CRATE_SPOTS: usize = 4;
+
+/// Embedded stage files, in stage-select order
+const STAGE_SOURCES: [&str; 3] = [
+    include_str!("../assets/stages/grid_arena.stage"),
+    include_str!("../assets/stages/scatter_field.stage"),
+    include_str!("../assets/stages/ring_void.stage"),
+];
+
+/// Number of stages in the game
+pub const NUM_STAGES: u32 = STAGE_SOURCES.len() as u32;
+
+/// Every built-in stage, parsed at compile time
+pub static STAGES: [StageDef; NUM_STAGES as usize] = parse_stages(&STAGE_SOURCES);
 
 // =============================================================================
 // DATA STRUCTURES
 // =============================================================================
 
+/// How a platform blocks players (bullets stop on every kind)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Collision {
+    OneWay, // Land from above; jump up through it, drop through it
+    Solid,  // Floor, ceiling and walls; no drop-through
+    Wall,   // Sides only: blocks and allows wall jumps, can't be stood on
+}
+
 #[derive(Clone, Copy)]
 pub struct Platform {
     pub x: f32,
@@ -21,10 +64,11 @@ pub struct Platform {
     pub width: f32,
     pub height: f32,
     pub active: bool,
-    pub moving: bool, // For Stage 3 moving platform
+    pub moving: bool, // Slides along x between move_min and move_max
     pub move_speed: f32,
     pub move_min: f32,
     pub move_max: f32,
+    pub collision: Collision,
 }
 
 impl Platform {
@@ -39,276 +83,465 @@ impl Platform {
             move_speed: 0.0,
             move_min: 0.0,
             move_max: 0.0,
+            collision: Collision::OneWay,
         }
     }
+
+    /// Players can land on top
+    pub fn has_floor(&self) -> bool {
+        self.collision != Collision::Wall
+    }
+
+    /// Sides block movement and can be wall-jumped off
+    pub fn has_sides(&self) -> bool {
+        self.collision != Collision::OneWay
+    }
+
+    /// Underside blocks jumps
+    pub fn has_ceiling(&self) -> bool {
+        self.collision == Collision::Solid
+    }
 }
 
-// =============================================================================
-// GLOBAL STATE
-// =============================================================================
+/// EPU background preset (configured in render.rs)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Background {
+    Grid,
+    Sunset,
+    Portal,
+}
+
+/// Stage music track (loaded in audio.rs)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MusicTrack {
+    Grid,
+    Scatter,
+    Ring,
+}
+
+/// A parsed stage file
+#[derive(Clone, Copy)]
+pub struct StageDef {
+    pub name: &'static str,
+    pub background: Background,
+    pub music: MusicTrack,
+    pub arena_left: f32,
+    pub arena_right: f32,
+    pub pit_y: Option<f32>,
+    pub platforms: [Platform; MAX_PLATFORMS],
+    pub platform_count: usize,
+    pub spawns: [(f32, f32); MAX_SPAWNS],
+    pub spawn_count: usize,
+    pub crate_spots: [(f32, f32); MAX_CRATE_SPOTS], // Bottom-left, like spawns
+    pub crate_spot_count: usize,
+}
+
+impl StageDef {
+    pub const fn new() -> Self {
+        Self {
+            name: "",
+            background: Background::Grid,
+            music: MusicTrack::Grid,
+            arena_left: -10.0,
+            arena_right: 10.0,
+            pit_y: None,
+            platforms: [Platform::new(); MAX_PLATFORMS],
+            platform_count: 0,
+            spawns: [(0.0, 0.0); MAX_SPAWNS],
+            spawn_count: 0,
+            crate_spots: [(0.0, 0.0); MAX_CRATE_SPOTS],
+            crate_spot_count: 0,
+        }
+    }
+
+    pub fn platforms(&self) -> &[Platform] {
+        &self.platforms[..self.platform_count]
+    }
 
-pub static mut PLATFORMS: [Platform; MAX_PLATFORMS] = [Platform::new(); MAX_PLATFORMS];
-pub static mut HAS_PIT: bool = false;
-pub static mut PIT_Y: f32 = -10.0;
+    pub fn spawns(&self) -> &[(f32, f32)] {
+        &self.spawns[..self.spawn_count]
+    }
+
+    pub fn crate_spots(&self) -> &[(f32, f32)] {
+        &self.crate_spots[..self.crate_spot_count]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StageErrorKind {
+    UnknownDirective,
+    BadNumber,
+    UnknownBackground,
+    UnknownMusic,
+    UnknownCollision,
+    TrailingInput,
+    TooManyPlatforms,
+    TooManySpawns,
+    TooManyCrates,
+    BadArena,
+    MissingName,
+    NotEnoughSpawns,
+}
+
+impl StageErrorKind {
+    pub fn message(self) -> &'static str {
+        match self {
+            StageErrorKind::UnknownDirective => "unknown directive",
+            StageErrorKind::BadNumber => "expected a number",
+            StageErrorKind::UnknownBackground => "unknown background (grid, sunset, portal)",
+            StageErrorKind::UnknownMusic => "unknown music track (grid, scatter, ring)",
+            StageErrorKind::UnknownCollision => "unknown collision (oneway, solid, wall)",
+            StageErrorKind::TrailingInput => "unexpected extra values",
+            StageErrorKind::TooManyPlatforms => "too many platforms",
+            StageErrorKind::TooManySpawns => "too many spawn points",
+            StageErrorKind::TooManyCrates => "too many crate spots",
+            StageErrorKind::BadArena => "arena left must be less than right",
+            StageErrorKind::MissingName => "missing `name`",
+            StageErrorKind::NotEnoughSpawns => "needs a spawn point for every player slot",
+        }
+    }
+}
+
+/// Parse failure; `line` is 1-based, 0 means the file as a whole
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StageError {
+    pub line: u32,
+    pub kind: StageErrorKind,
+}
 
 // =============================================================================
-// STAGE SETUP
+// PARSING
 // =============================================================================
 
-fn setup_stage_grid_arena() {
-    unsafe {
-        HAS_PIT = false;
+/// Byte cursor over a stage or bot profile file (index-based so it works in
+/// `const fn`)
+pub(crate) struct Cursor {
+    src: &'static [u8],
+    pos: usize,
+    pub(crate) line: u32,
+}
 
-        // Clear platforms
-        for p in &mut PLATFORMS {
-            p.active = false;
+const fn is_space(c: u8) -> bool {
+    c == b' ' || c == b'\t' || c == b'\r'
+}
+
+impl Cursor {
+    pub(crate) const fn new(src: &'static str) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos: 0,
+            line: 1,
         }
+    }
 
-        // Ground
-        PLATFORMS[0] = Platform {
-            x: -10.0,
-            y: -2.0,
-            width: 20.0,
-            height: 0.5,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
+    pub(crate) const fn at_end(&self) -> bool {
+        self.pos >= self.src.len()
+    }
 
-        // Middle platforms (symmetrical)
-        PLATFORMS[1] = Platform {
-            x: -7.0,
-            y: 1.0,
-            width: 4.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[2] = Platform {
-            x: 3.0,
-            y: 1.0,
-            width: 4.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
+    const fn peek(&self) -> u8 {
+        if self.at_end() {
+            b'\n'
+        } else {
+            self.src[self.pos]
+        }
+    }
 
-        // Top platform
-        PLATFORMS[3] = Platform {
-            x: -3.0,
-            y: 4.0,
-            width: 6.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
+    const fn skip_spaces(&mut self) {
+        while !self.at_end() && is_space(self.peek()) {
+            self.pos += 1;
+        }
     }
-}
 
-fn setup_stage_scatter_field() {
-    unsafe {
-        HAS_PIT = true;
-        PIT_Y = -5.0;
+    /// True if only whitespace or a comment remains on this line
+    pub(crate) const fn at_line_end(&mut self) -> bool {
+        self.skip_spaces();
+        self.peek() == b'\n' || self.peek() == b'#'
+    }
 
-        // Clear platforms
-        for p in &mut PLATFORMS {
-            p.active = false;
+    pub(crate) const fn next_line(&mut self) {
+        while !self.at_end() && self.peek() != b'\n' {
+            self.pos += 1;
         }
+        self.pos += 1;
+        self.line += 1;
+    }
 
-        // Asymmetric platforms - no ground, pit below
-        PLATFORMS[0] = Platform {
-            x: -9.0,
-            y: 0.0,
-            width: 4.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[1] = Platform {
-            x: -3.0,
-            y: -1.0,
-            width: 3.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[2] = Platform {
-            x: 2.0,
-            y: 0.5,
-            width: 3.5,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[3] = Platform {
-            x: 6.0,
-            y: -0.5,
-            width: 3.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
+    /// Next whitespace-delimited token as a (start, end) byte range
+    pub(crate) const fn word(&mut self) -> (usize, usize) {
+        self.skip_spaces();
+        let start = self.pos;
+        while !self.at_end()
+            && !is_space(self.peek())
+            && self.peek() != b'\n'
+            && self.peek() != b'#'
+        {
+            self.pos += 1;
+        }
+        (start, self.pos)
+    }
 
-        // Upper platforms
-        PLATFORMS[4] = Platform {
-            x: -6.0,
-            y: 3.0,
-            width: 3.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[5] = Platform {
-            x: 0.0,
-            y: 4.0,
-            width: 4.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[6] = Platform {
-            x: 5.0,
-            y: 2.5,
-            width: 3.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
+    pub(crate) const fn word_is(&self, word: (usize, usize), lit: &[u8]) -> bool {
+        if word.1 - word.0 != lit.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < lit.len() {
+            if self.src[word.0 + i] != lit[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Decimal number: optional sign, digits, optional fraction
+    pub(crate) const fn number(&mut self) -> Result<f32, StageErrorKind> {
+        let (start, end) = self.word();
+        let mut i = start;
+        let negative = i < end && self.src[i] == b'-';
+        if i < end && (self.src[i] == b'-' || self.src[i] == b'+') {
+            i += 1;
+        }
+
+        // Collect the digits as one integer and scale once at the end so the
+        // result rounds the same way as the equivalent float literal
+        let mut mantissa: u64 = 0;
+        let mut scale: f64 = 1.0;
+        let mut digits = 0;
+        let mut in_fraction = false;
+        while i < end {
+            let c = self.src[i];
+            if c == b'.' && !in_fraction {
+                in_fraction = true;
+            } else if c.is_ascii_digit() && digits < 18 {
+                mantissa = mantissa * 10 + (c - b'0') as u64;
+                digits += 1;
+                if in_fraction {
+                    scale *= 10.0;
+                }
+            } else {
+                return Err(StageErrorKind::BadNumber);
+            }
+            i += 1;
+        }
+        if digits == 0 {
+            return Err(StageErrorKind::BadNumber);
+        }
+
+        let value = (mantissa as f64 / scale) as f32;
+        Ok(if negative { -value } else { value })
+    }
+
+    /// Rest of the line, minus any comment and trailing whitespace
+    pub(crate) const fn rest_of_line(&mut self) -> &'static str {
+        self.skip_spaces();
+        let start = self.pos;
+        let mut end = start;
+        while !self.at_end() && self.peek() != b'\n' && self.peek() != b'#' {
+            if !is_space(self.peek()) {
+                end = self.pos + 1;
+            }
+            self.pos += 1;
+        }
+        let (_, tail) = self.src.split_at(start);
+        let (bytes, _) = tail.split_at(end - start);
+        match core::str::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(_) => "",
+        }
     }
 }
 
-fn setup_stage_ring_void() {
-    unsafe {
-        HAS_PIT = true;
-        PIT_Y = -6.0;
+/// Early-return a `StageError` (no `?` or closures in `const fn`)
+macro_rules! fail {
+    ($line:expr, $kind:expr) => {
+        return Err(StageError {
+            line: $line,
+            kind: $kind,
+        })
+    };
+}
 
-        // Clear platforms
-        for p in &mut PLATFORMS {
-            p.active = false;
+/// Unwrap a number or fail with the cursor's current line
+macro_rules! number {
+    ($cursor:expr) => {
+        match $cursor.number() {
+            Ok(v) => v,
+            Err(kind) => fail!($cursor.line, kind),
         }
+    };
+}
 
-        // Floating platforms with gaps
-        PLATFORMS[0] = Platform {
-            x: -8.0,
-            y: 0.0,
-            width: 3.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[1] = Platform {
-            x: 5.0,
-            y: 0.0,
-            width: 3.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
+/// Parse one stage file (usable at compile time and by host tools)
+pub const fn parse_stage(src: &'static str) -> Result<StageDef, StageError> {
+    let mut c = Cursor::new(src);
+    let mut stage = StageDef::new();
 
-        // Moving platform in center
-        PLATFORMS[2] = Platform {
-            x: -1.5,
-            y: 1.0,
-            width: 3.0,
-            height: 0.4,
-            active: true,
-            moving: true,
-            move_speed: 0.02,
-            move_min: -4.0,
-            move_max: 4.0,
-        };
+    while !c.at_end() {
+        if c.at_line_end() {
+            c.next_line();
+            continue;
+        }
 
-        // Upper corners
-        PLATFORMS[3] = Platform {
-            x: -7.0,
-            y: 3.5,
-            width: 2.5,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
-        PLATFORMS[4] = Platform {
-            x: 4.5,
-            y: 3.5,
-            width: 2.5,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
-        };
+        let line = c.line;
+        let directive = c.word();
 
-        // Top center
-        PLATFORMS[5] = Platform {
-            x: -2.0,
-            y: 5.0,
-            width: 4.0,
-            height: 0.4,
-            active: true,
-            moving: false,
-            move_speed: 0.0,
-            move_min: 0.0,
-            move_max: 0.0,
+        if c.word_is(directive, b"name") {
+            stage.name = c.rest_of_line();
+        } else if c.word_is(directive, b"background") {
+            let w = c.word();
+            stage.background = if c.word_is(w, b"grid") {
+                Background::Grid
+            } else if c.word_is(w, b"sunset") {
+                Background::Sunset
+            } else if c.word_is(w, b"portal") {
+                Background::Portal
+            } else {
+                fail!(line, StageErrorKind::UnknownBackground)
+            };
+        } else if c.word_is(directive, b"music") {
+            let w = c.word();
+            stage.music = if c.word_is(w, b"grid") {
+                MusicTrack::Grid
+            } else if c.word_is(w, b"scatter") {
+                MusicTrack::Scatter
+            } else if c.word_is(w, b"ring") {
+                MusicTrack::Ring
+            } else {
+                fail!(line, StageErrorKind::UnknownMusic)
+            };
+        } else if c.word_is(directive, b"arena") {
+            stage.arena_left = number!(c);
+            stage.arena_right = number!(c);
+            if stage.arena_left >= stage.arena_right {
+                fail!(line, StageErrorKind::BadArena);
+            }
+        } else if c.word_is(directive, b"pit") {
+            let save = c.pos;
+            let w = c.word();
+            if c.word_is(w, b"none") {
+                stage.pit_y = None;
+            } else {
+                c.pos = save;
+                stage.pit_y = Some(number!(c));
+            }
+        } else if c.word_is(directive, b"platform") || c.word_is(directive, b"mover") {
+            if stage.platform_count >= MAX_PLATFORMS {
+                fail!(line, StageErrorKind::TooManyPlatforms);
+            }
+            let mut p = Platform::new();
+            p.x = number!(c);
+            p.y = number!(c);
+            p.width = number!(c);
+            p.height = number!(c);
+            p.active = true;
+            if c.word_is(directive, b"mover") {
+                p.moving = true;
+                p.move_speed = number!(c);
+                p.move_min = number!(c);
+                p.move_max = number!(c);
+            }
+            if !c.at_line_end() {
+                let w = c.word();
+                p.collision = if c.word_is(w, b"oneway") {
+                    Collision::OneWay
+                } else if c.word_is(w, b"solid") {
+                    Collision::Solid
+                } else if c.word_is(w, b"wall") {
+                    Collision::Wall
+                } else {
+                    fail!(line, StageErrorKind::UnknownCollision)
+                };
+            }
+            stage.platforms[stage.platform_count] = p;
+            stage.platform_count += 1;
+        } else if c.word_is(directive, b"spawn") {
+            if stage.spawn_count >= MAX_SPAWNS {
+                fail!(line, StageErrorKind::TooManySpawns);
+            }
+            let x = number!(c);
+            let y = number!(c);
+            stage.spawns[stage.spawn_count] = (x, y);
+            stage.spawn_count += 1;
+        } else if c.word_is(directive, b"crate") {
+            if stage.crate_spot_count >= MAX_CRATE_SPOTS {
+                fail!(line, StageErrorKind::TooManyCrates);
+            }
+            let x = number!(c);
+            let y = number!(c);
+            stage.crate_spots[stage.crate_spot_count] = (x, y);
+            stage.crate_spot_count += 1;
+        } else {
+            fail!(line, StageErrorKind::UnknownDirective);
+        }
+
+        if !c.at_line_end() {
+            fail!(line, StageErrorKind::TrailingInput);
+        }
+        c.next_line();
+    }
+
+    if stage.name.is_empty() {
+        fail!(0, StageErrorKind::MissingName);
+    }
+    if stage.spawn_count < MAX_PLAYERS {
+        fail!(0, StageErrorKind::NotEnoughSpawns);
+    }
+    Ok(stage)
+}
+
+const fn parse_stages<const N: usize>(sources: &[&'static str; N]) -> [StageDef; N] {
+    let mut stages = [StageDef::new(); N];
+    let mut i = 0;
+    while i < N {
+        stages[i] = match parse_stage(sources[i]) {
+            Ok(stage) => stage,
+            Err(_) => panic!("invalid stage file in assets/stages (run stage_check for details)"),
         };
+        i += 1;
     }
+    stages
+}
+
+// =============================================================================
+// STAGE SETUP
+// =============================================================================
+
+/// Definition for a stage index (out of range falls back to the first stage)
+pub fn stage_def(stage: u32) -> &'static StageDef {
+    STAGES.get(stage as usize).unwrap_or(&STAGES[0])
+}
+
+/// Definition for the stage currently being played
+pub fn current_stage_def() -> &'static StageDef {
+    unsafe { stage_def(WORLD.sim.game.current_stage) }
+}
+
+/// Display name (also used by host tools)
+pub fn stage_name(stage: u32) -> &'static str {
+    stage_def(stage).name
 }
 
 pub fn setup_current_stage() {
     unsafe {
-        match GAME_STATE.current_stage {
-            0 => setup_stage_grid_arena(),
-            1 => setup_stage_scatter_field(),
-            2 => setup_stage_ring_void(),
-            _ => setup_stage_grid_arena(),
+        let def = current_stage_def();
+
+        WORLD.sim.has_pit = def.pit_y.is_some();
+        if let Some(pit_y) = def.pit_y {
+            WORLD.sim.pit_y = pit_y;
         }
+
+        // Clear platforms, then copy the layout in
+        for p in &mut WORLD.sim.platforms {
+            p.active = false;
+        }
+        WORLD.sim.platforms[..def.platform_count].copy_from_slice(def.platforms());
     }
 }
 
 pub fn update_platforms() {
     unsafe {
-        for platform in &mut PLATFORMS {
+        for platform in &mut WORLD.sim.platforms {
             if !platform.active || !platform.moving {
                 continue;
             }
@@ -326,37 +559,269 @@ pub fn update_platforms() {
     }
 }
 
+// =============================================================================
+// HILL ZONE
+// =============================================================================
+
+pub const HILL_MAX_WIDTH: f32 = 3.0;
+pub const HILL_HEIGHT: f32 = 2.0;
+pub const HILL_MOVE_TICKS: u32 = 60 * 15; // Zone hops to another platform
+
+/// King of the Hill capture zone, riding on top of a platform
+#[derive(Clone, Copy)]
+pub struct Hill {
+    pub active: bool,
+    pub platform: usize, // Index into WORLD.sim.platforms
+    pub stage: u32,      // Stage the platform index belongs to
+    pub move_timer: u32,
+    pub held: bool,      // Exactly one team inside this tick
+    pub contested: bool, // More than one team inside; nobody scores
+    pub holder: u32,     // Scoring slot (valid while `held`)
+}
+
+impl Hill {
+    pub const fn new() -> Self {
+        Self {
+            active: false,
+            platform: 0,
+            stage: 0,
+            move_timer: 0,
+            held: false,
+            contested: false,
+            holder: 0,
+        }
+    }
+}
+
+/// Zone rect (x, y, w, h) standing on its platform (follows movers)
+pub fn hill_rect() -> (f32, f32, f32, f32) {
+    unsafe {
+        let p = &WORLD.sim.platforms[WORLD.sim.hill.platform];
+        let w = p.width.min(HILL_MAX_WIDTH);
+        (p.x + (p.width - w) * 0.5, p.y + p.height, w, HILL_HEIGHT)
+    }
+}
+
+/// Zone center, for bots and effects
+pub fn hill_center() -> (f32, f32) {
+    let (x, y, w, h) = hill_rect();
+    (x + w * 0.5, y + h * 0.5)
+}
+
+fn can_host_hill(p: &Platform) -> bool {
+    p.active && p.has_floor()
+}
+
+/// Hop the zone to a random standable platform other than the current one
+fn move_hill() {
+    unsafe {
+        let hill = &mut WORLD.sim.hill;
+        let current = if hill.active {
+            Some(hill.platform)
+        } else {
+            None
+        };
+        let count = WORLD
+            .sim
+            .platforms
+            .iter()
+            .enumerate()
+            .filter(|&(i, p)| can_host_hill(p) && Some(i) != current)
+            .count();
+        if count == 0 {
+            return;
+        }
+        let pick = crate::rng::game_range(0, count as i32).max(0) as usize;
+        if let Some((i, _)) = WORLD
+            .sim
+            .platforms
+            .iter()
+            .enumerate()
+            .filter(|&(i, p)| can_host_hill(p) && Some(i) != current)
+            .nth(pick)
+        {
+            hill.platform = i;
+            hill.active = true;
+        }
+        hill.move_timer = HILL_MOVE_TICKS;
+    }
+}
+
+/// Place the zone for a new round (it stays put unless the stage changed)
+pub fn setup_hill() {
+    unsafe {
+        let hill = &mut WORLD.sim.hill;
+        hill.held = false;
+        hill.contested = false;
+        if WORLD.sim.config.rule != MatchRule::Hill {
+            hill.active = false;
+            return;
+        }
+        let stage = WORLD.sim.game.current_stage;
+        if !hill.active
+            || hill.stage != stage
+            || !can_host_hill(&WORLD.sim.platforms[hill.platform])
+        {
+            hill.active = false;
+            move_hill();
+        }
+        WORLD.sim.hill.stage = stage;
+    }
+}
+
+/// Count down to the next hop (once per gameplay tick)
+pub fn update_hill() {
+    unsafe {
+        if !WORLD.sim.hill.active {
+            return;
+        }
+        WORLD.sim.hill.move_timer = WORLD.sim.hill.move_timer.saturating_sub(1);
+        if WORLD.sim.hill.move_timer == 0 {
+            move_hill();
+        }
+    }
+}
+
 // =============================================================================
 // SPAWN POINTS
 // =============================================================================
 
-/// Spawn points per stage (up to 4 players)
-/// Format: [(x, y), (x, y), (x, y), (x, y)] stored as flat array [x0, y0, x1, y1, ...]
-/// Positions are chosen to be:
-/// - On or above platforms (not in mid-air over pits)
-/// - Symmetrically spread when possible
-/// - Far enough apart to prevent spawn camping
-pub const SPAWN_POINTS: [[f32; 8]; 3] = [
-    // Stage 0: Grid Arena
-    // - Players 0,1 on left/right middle platforms (y=1.0 + platform height ~1.4)
-    // - Players 2,3 on top platform spread apart (y=4.0 + platform height ~4.4)
-    [-5.0, 1.5, 5.0, 1.5, -2.0, 4.5, 2.0, 4.5],
-    // Stage 1: Scatter Field
-    // - Players 0,1 on lower left/right platforms
-    // - Players 2,3 on upper platforms
-    [-7.0, 0.5, 3.5, 1.0, -4.5, 3.5, 1.5, 4.5],
-    // Stage 2: Ring Void
-    // - Players 0,1 on left/right floating platforms (y=0.0 + height ~0.4)
-    // - Players 2,3 on upper corner platforms (y=3.5 + height ~3.9)
-    [-6.5, 0.5, 6.0, 0.5, -5.5, 4.0, 5.5, 4.0],
-];
+/// Spawn point `slot` on the current stage
+pub fn spawn_point(slot: usize) -> (f32, f32) {
+    let def = current_stage_def();
+    def.spawns[slot % def.spawn_count]
+}
+
+/// Number of spawn points on the current stage
+pub fn spawn_count() -> usize {
+    current_stage_def().spawn_count
+}
 
 /// Get spawn position for player on current stage (used for initial spawn)
 pub fn get_spawn_position(player_idx: usize) -> (f32, f32) {
-    unsafe {
-        let stage = GAME_STATE.current_stage as usize;
-        let stage = stage.min(2); // Clamp to valid stage range
-        let idx = player_idx.min(3) * 2; // 2 floats per position, max 4 players
-        (SPAWN_POINTS[stage][idx], SPAWN_POINTS[stage][idx + 1])
+    spawn_point(player_idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_err(src: &'static str) -> (u32, StageErrorKind) {
+        let e = parse_stage(src).err().expect("should fail to parse");
+        (e.line, e.kind)
+    }
+
+    #[test]
+    fn parses_every_directive() {
+        let stage = parse_stage(
+            "# Test stage\n\
+             \n\
+             name        TEST  STAGE   # trailing comment\n\
+             background  sunset\n\
+             music       ring\n\
+             arena       -12.5 +12.5\n\
+             pit         -6.0\n\
+             platform    -10.0 -2.0 20.0 0.5 solid\n\
+             mover       -1.5 1.0 3.0 0.4 0.02 -4.0 4.0\n\
+             platform    4.0 3.0 0.5 2.0 wall\n\
+             spawn       -6.5 0.5\n\
+             spawn       6.0 0.5\n\
+             spawn       -5.5 4.0\n\
+             spawn       5.5 4.0\n\
+             crate       -0.35 5.4\n",
+        )
+        .unwrap();
+
+        assert_eq!(stage.name, "TEST  STAGE");
+        assert_eq!(stage.background, Background::Sunset);
+        assert_eq!(stage.music, MusicTrack::Ring);
+        assert_eq!((stage.arena_left, stage.arena_right), (-12.5, 12.5));
+        assert_eq!(stage.pit_y, Some(-6.0));
+
+        let [floor, mover, wall] = stage.platforms() else {
+            panic!("expected three platforms");
+        };
+        assert_eq!(
+            (floor.x, floor.y, floor.width, floor.height),
+            (-10.0, -2.0, 20.0, 0.5)
+        );
+        assert_eq!(floor.collision, Collision::Solid);
+        assert!(floor.active && !floor.moving);
+        assert!(mover.moving);
+        assert_eq!(
+            (mover.move_speed, mover.move_min, mover.move_max),
+            (0.02, -4.0, 4.0)
+        );
+        assert_eq!(mover.collision, Collision::OneWay);
+        assert_eq!(wall.collision, Collision::Wall);
+
+        assert_eq!(
+            stage.spawns(),
+            [(-6.5, 0.5), (6.0, 0.5), (-5.5, 4.0), (5.5, 4.0)]
+        );
+        assert_eq!(stage.crate_spots(), [(-0.35, 5.4)]);
+    }
+
+    #[test]
+    fn pit_none_and_defaults() {
+        let stage =
+            parse_stage("name X\npit none\nspawn -1 0\nspawn 1 0\nspawn -2 3\nspawn 2 3").unwrap();
+        assert_eq!(stage.pit_y, None);
+        assert_eq!(stage.background, Background::Grid);
+        assert_eq!((stage.arena_left, stage.arena_right), (-10.0, 10.0));
+        assert!(stage.platforms().is_empty());
+        assert_eq!(stage.spawn_count, 4);
+    }
+
+    #[test]
+    fn errors_point_at_the_line() {
+        assert_eq!(
+            parse_err("name X\n\nwall 1 2\n"),
+            (3, StageErrorKind::UnknownDirective)
+        );
+        assert_eq!(
+            parse_err("name X\nplatform 1 2 three 4\n"),
+            (2, StageErrorKind::BadNumber)
+        );
+        assert_eq!(
+            parse_err("name X\nplatform 1 2 3 4 sticky\n"),
+            (2, StageErrorKind::UnknownCollision)
+        );
+        assert_eq!(
+            parse_err("name X\nspawn 1 2 3\n"),
+            (2, StageErrorKind::TrailingInput)
+        );
+        assert_eq!(
+            parse_err("name X\narena 5 -5\n"),
+            (2, StageErrorKind::BadArena)
+        );
+        assert_eq!(
+            parse_err("name X\nbackground space\n"),
+            (2, StageErrorKind::UnknownBackground)
+        );
+        assert_eq!(
+            parse_err("name X\ncrate 0 0\ncrate 0 0\ncrate 0 0\ncrate 0 0\ncrate 0 0\n"),
+            (6, StageErrorKind::TooManyCrates)
+        );
+        assert_eq!(
+            parse_err("spawn -1 0\nspawn 1 0\nspawn -2 3\nspawn 2 3\n"),
+            (0, StageErrorKind::MissingName)
+        );
+        assert_eq!(
+            parse_err("name X\nspawn 0 0\n"),
+            (0, StageErrorKind::NotEnoughSpawns)
+        );
+    }
+
+    #[test]
+    fn built_in_stages_parse() {
+        for (source, stage) in STAGE_SOURCES.iter().zip(STAGES.iter()) {
+            let parsed = parse_stage(source).unwrap();
+            assert_eq!(parsed.name, stage.name);
+            assert!(parsed.spawn_count >= MAX_PLAYERS);
+            assert!(parsed.platform_count > 0);
+        }
+        assert_eq!(stage_name(2), "RING VOID");
+        assert_eq!(stage_name(NUM_STAGES).as_ptr(), stage_name(0).as_ptr());
     }
 }
@@ -0,0 +1,114 @@
+//! Match statistics
+//!
+//! Deterministic per-match counters kept in the simulation state. Nothing in
+//! gameplay reads them; they feed the results screen and host-side tools like
+//! the headless match runner.
+
+use crate::world::WORLD;
+
+// =============================================================================
+// KILL CAUSES
+// =============================================================================
+
+pub const KILL_CAUSE_COUNT: usize = 9;
+
+/// What ended a life
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KillCause {
+    Bullet,
+    Melee,
+    OvertimeWall,
+    Fall,
+    Spread,
+    Ricochet,
+    Laser,
+    Grenade,
+    Stomp,
+}
+
+impl KillCause {
+    pub const ALL: [KillCause; KILL_CAUSE_COUNT] = [
+        KillCause::Bullet,
+        KillCause::Melee,
+        KillCause::OvertimeWall,
+        KillCause::Fall,
+        KillCause::Spread,
+        KillCause::Ricochet,
+        KillCause::Laser,
+        KillCause::Grenade,
+        KillCause::Stomp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KillCause::Bullet => "bullet",
+            KillCause::Melee => "melee",
+            KillCause::OvertimeWall => "overtime wall",
+            KillCause::Fall => "fall",
+            KillCause::Spread => "spread shot",
+            KillCause::Ricochet => "ricochet",
+            KillCause::Laser => "laser",
+            KillCause::Grenade => "grenade",
+            KillCause::Stomp => "stomp",
+        }
+    }
+}
+
+// =============================================================================
+// MATCH STATS
+// =============================================================================
+
+#[derive(Clone, Copy)]
+pub struct MatchStats {
+    pub start_tick: u32,
+    pub deaths_by_cause: [u32; KILL_CAUSE_COUNT],
+    pub clashes: u32, // Bullets that cancelled each other out
+    pub deflects: u32,
+    pub perfect_parries: u32, // Also counted in `deflects`
+}
+
+impl MatchStats {
+    pub const fn new() -> Self {
+        Self {
+            start_tick: 0,
+            deaths_by_cause: [0; KILL_CAUSE_COUNT],
+            clashes: 0,
+            deflects: 0,
+            perfect_parries: 0,
+        }
+    }
+}
+
+/// Clear counters at match start
+pub fn reset_stats() {
+    unsafe {
+        WORLD.sim.stats = MatchStats::new();
+        WORLD.sim.stats.start_tick = WORLD.sim.tick;
+    }
+}
+
+pub fn record_death(cause: KillCause) {
+    unsafe {
+        WORLD.sim.stats.deaths_by_cause[cause as usize] += 1;
+    }
+}
+
+pub fn record_clash() {
+    unsafe {
+        WORLD.sim.stats.clashes += 1;
+    }
+}
+
+pub fn record_deflect(perfect: bool) {
+    unsafe {
+        WORLD.sim.stats.deflects += 1;
+        if perfect {
+            WORLD.sim.stats.perfect_parries += 1;
+        }
+    }
+}
+
+/// Ticks since the match started
+pub fn match_ticks() -> u32 {
+    unsafe { WORLD.sim.tick.wrapping_sub(WORLD.sim.stats.start_tick) }
+}
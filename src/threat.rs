@@ -0,0 +1,273 @@
+//! Bot threat prediction
+//!
+//! Bots trace every enemy bullet they know about forward, tick by tick, the
+//! way `update_bullets` will move it (grenades arc, platforms stop it), and
+//! test the path against where their own body is headed. Bullets that will
+//! miss, including ones flying away, are left alone. One that will hit gets
+//! parried if the bot means to and its swing can come out in time, timed
+//! to meet the bullet `ai_parry_lead` frames into the swing; if not,
+//! the bot looks for a jump, drop or sidestep (previewed with
+//! `nav::preview`) that clears every known path without leaving the stage.
+//! The same tracing keeps bots from firing into platforms.
+
+use crate::combat::{normalize_aim, segment_aabb_entry, MAX_BULLETS};
+use crate::nav::{self, Maneuver, PREVIEW_TICKS};
+use crate::perception::{PerceptionFrame, SeenBullet};
+use crate::player::{Player, MELEE_WINDUP_DURATION, PLAYER_HEIGHT, PLAYER_WIDTH};
+use crate::weapons::{Weapon, GRENADE_BLAST_RADIUS, GRENADE_GRAVITY, GRENADE_MAX_FALL};
+use crate::world::WORLD;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Ticks of bullet flight traced ahead (as far as bots preview their moves)
+const TRACE_TICKS: usize = PREVIEW_TICKS;
+/// Slack around the hitbox, for the bot's own prediction error
+const HIT_MARGIN: f32 = 0.1;
+/// Bullet moves between pressing melee and the deflect going live
+const SWING_LIVE: usize = MELEE_WINDUP_DURATION as usize - 1;
+/// Hits further out than this don't make a bot dodge yet
+const DODGE_TICKS: usize = 20;
+/// Longest shot traced by `shot_is_clear`
+const MAX_SHOT_TICKS: usize = 60;
+
+// =============================================================================
+// DATA STRUCTURES
+// =============================================================================
+
+/// Where a bullet will be after each of the next ticks
+#[derive(Clone, Copy)]
+struct BulletPath {
+    points: [(f32, f32); TRACE_TICKS + 1], // points[0] is where it is now
+    len: usize,                            // Moves before it stops or the trace ends
+    blast: bool,                           // Goes off where it stops (grenades)
+    vx: f32,
+    vy: f32,
+}
+
+impl BulletPath {
+    const fn new() -> Self {
+        Self {
+            points: [(0.0, 0.0); TRACE_TICKS + 1],
+            len: 0,
+            blast: false,
+            vx: 0.0,
+            vy: 0.0,
+        }
+    }
+}
+
+/// What a bot should do about the bullets it knows of
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Response {
+    /// Swing now; the bullet will be at (dx, dy) from the bot's center when
+    /// it would hit
+    Parry(f32, f32),
+    Dodge(Maneuver),
+}
+
+// =============================================================================
+// TRACING
+// =============================================================================
+
+/// One bullet move, as `update_bullets` makes it; false if it runs into a
+/// platform instead (ricochets aren't followed past a bounce)
+fn bullet_step(pos: &mut (f32, f32), vel: &mut (f32, f32), kind: Weapon) -> bool {
+    if kind == Weapon::Grenade {
+        vel.1 = (vel.1 - GRENADE_GRAVITY).max(-GRENADE_MAX_FALL);
+    }
+    unsafe {
+        for p in WORLD.sim.platforms.iter().filter(|p| p.active) {
+            if segment_aabb_entry(*pos, *vel, (p.x, p.y, p.width, p.height)).is_some() {
+                return false;
+            }
+        }
+    }
+    pos.0 += vel.0;
+    pos.1 += vel.1;
+    true
+}
+
+/// Path of a seen bullet from now on: it's first moved `delay` ticks, to
+/// catch up with where it is by now
+fn trace_bullet(b: &SeenBullet, delay: u32) -> BulletPath {
+    let mut path = BulletPath::new();
+    path.blast = b.kind == Weapon::Grenade;
+    let mut pos = (b.x, b.y);
+    let mut vel = (b.vx, b.vy);
+    for _ in 0..delay {
+        if !bullet_step(&mut pos, &mut vel, b.kind) {
+            path.points[0] = pos;
+            return path;
+        }
+    }
+
+    path.points[0] = pos;
+    (path.vx, path.vy) = vel;
+    while path.len < TRACE_TICKS && bullet_step(&mut pos, &mut vel, b.kind) {
+        path.len += 1;
+        path.points[path.len] = pos;
+    }
+    path
+}
+
+/// First tick on which a path meets a body moving along `body`
+fn hit_tick(path: &BulletPath, body: &[(f32, f32); PREVIEW_TICKS]) -> Option<usize> {
+    for (tick, &(x, y)) in body.iter().enumerate().take(path.len) {
+        let (x0, y0) = path.points[tick];
+        let (x1, y1) = path.points[tick + 1];
+        let rect = (
+            x - HIT_MARGIN,
+            y - HIT_MARGIN,
+            PLAYER_WIDTH + HIT_MARGIN * 2.0,
+            PLAYER_HEIGHT + HIT_MARGIN * 2.0,
+        );
+        if segment_aabb_entry((x0, y0), (x1 - x0, y1 - y0), rect).is_some() {
+            return Some(tick);
+        }
+    }
+
+    // A grenade that stops within reach goes off there
+    if path.blast && path.len < TRACE_TICKS {
+        let (x, y) = body[path.len];
+        let (bx, by) = path.points[path.len];
+        let dx = x + PLAYER_WIDTH * 0.5 - bx;
+        let dy = y + PLAYER_HEIGHT * 0.5 - by;
+        let r = GRENADE_BLAST_RADIUS + HIT_MARGIN;
+        if dx * dx + dy * dy <= r * r {
+            return Some(path.len);
+        }
+    }
+    None
+}
+
+/// Earliest hit among `paths` on a body moving along `body`, and which path
+fn first_hit(paths: &[BulletPath], body: &[(f32, f32); PREVIEW_TICKS]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    for (i, path) in paths.iter().enumerate() {
+        if let Some(tick) = hit_tick(path, body) {
+            if best.is_none_or(|(t, _)| tick < t) {
+                best = Some((tick, i));
+            }
+        }
+    }
+    best
+}
+
+// =============================================================================
+// QUERIES
+// =============================================================================
+
+/// How bot `p` should answer the enemy bullets it knows of (its noticed and
+/// ignored ones) in `seen`, a frame `delay` ticks old. `None` means carry on.
+pub fn respond(p: &Player, seen: &PerceptionFrame, delay: u32) -> Option<Response> {
+    let known = p.ai_noticed | p.ai_ignored;
+    if known == 0 {
+        return None;
+    }
+
+    let mut paths = [BulletPath::new(); MAX_BULLETS];
+    let mut slots = [0usize; MAX_BULLETS];
+    let mut count = 0;
+    for (i, b) in seen.bullets.iter().enumerate() {
+        if known & (1 << i) != 0 {
+            paths[count] = trace_bullet(b, delay);
+            slots[count] = i;
+            count += 1;
+        }
+    }
+    let paths = &paths[..count];
+
+    // Standing pat: does anything hit at all?
+    let hold = nav::preview(p, Maneuver::Hold)?;
+    let (tick, k) = first_hit(paths, &hold)?;
+    let path = &paths[k];
+
+    // Parry what it means to parry, once the swing will be live in time;
+    // wait while that would meet the bullet before the planned frame
+    let swing_ready = p.ai_melee_cooldown == 0 && p.melee_timer == 0 && p.melee_windup == 0;
+    if p.ai_noticed & (1 << slots[k]) != 0 && swing_ready && tick >= SWING_LIVE {
+        if tick > SWING_LIVE + p.ai_parry_lead as usize {
+            return None;
+        }
+        let (bx, by) = path.points[tick];
+        let (x, y) = hold[tick];
+        return Some(Response::Parry(
+            bx - (x + PLAYER_WIDTH * 0.5),
+            by - (y + PLAYER_HEIGHT * 0.5),
+        ));
+    }
+    if tick > DODGE_TICKS {
+        return None;
+    }
+
+    // Get out of the way: over or under shots that come in level, aside
+    // from ones that come in steep
+    let cx = hold[0].0 + PLAYER_WIDTH * 0.5;
+    let level = path.vy.abs() <= path.vx.abs();
+    let side = if level {
+        if path.vx > 0.0 {
+            1.0
+        } else {
+            -1.0
+        }
+    } else if path.points[tick].0 > cx {
+        -1.0
+    } else {
+        1.0
+    };
+    let moves = if level {
+        [
+            Maneuver::Jump(0.0),
+            Maneuver::Drop,
+            Maneuver::Jump(side),
+            Maneuver::Walk(side),
+            Maneuver::Walk(-side),
+        ]
+    } else {
+        [
+            Maneuver::Walk(side),
+            Maneuver::Jump(side),
+            Maneuver::Walk(-side),
+            Maneuver::Drop,
+            Maneuver::Jump(0.0),
+        ]
+    };
+
+    // First move that clears everything, else the one that buys most time
+    let mut best: Option<(usize, Maneuver)> = None;
+    for m in moves {
+        let Some(body) = nav::preview(p, m) else {
+            continue;
+        };
+        let Some((t, _)) = first_hit(paths, &body) else {
+            return Some(Response::Dodge(m));
+        };
+        if t > tick && best.is_none_or(|(bt, _)| t > bt) {
+            best = Some((t, m));
+        }
+    }
+    best.map(|(_, m)| Response::Dodge(m))
+}
+
+/// Whether a shot fired now along `aim` (stick values, snapped like
+/// `spawn_bullet`) gets `dist` from the player's center before a platform
+/// stops it
+pub fn shot_is_clear(p: &Player, aim: (f32, f32), kind: Weapon, dist: f32) -> bool {
+    let dir = normalize_aim(aim.0, aim.1, p.facing_right);
+    let speed = kind.speed();
+    let mut pos = (p.x + PLAYER_WIDTH * 0.5, p.y + PLAYER_HEIGHT * 0.5);
+    let mut vel = (dir.0 * speed, dir.1 * speed);
+    let mut travelled = 0.0;
+    for _ in 0..MAX_SHOT_TICKS {
+        if travelled >= dist {
+            return true;
+        }
+        if !bullet_step(&mut pos, &mut vel, kind) {
+            return false;
+        }
+        travelled += speed;
+    }
+    true
+}
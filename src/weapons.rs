@@ -0,0 +1,220 @@
+//! Weapons and weapon crates
+//!
+//! Everyone starts with the pistol, which draws on the regular ammo pool.
+//! Crates appear at the stage's `crate` spots and swap in a special weapon
+//! with a few shots of its own; once those are spent the pistol is back.
+//! Each weapon fires its own bullet `kind`, handled in combat.rs.
+
+use crate::audio;
+use crate::combat::{BULLET_LIFETIME, BULLET_SPEED};
+use crate::player::{aabb_overlap, PLAYER_HEIGHT, PLAYER_WIDTH};
+use crate::rng::game_range;
+use crate::stage::{current_stage_def, MAX_CRATE_SPOTS};
+use crate::stats::KillCause;
+use crate::world::WORLD;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+pub const WEAPON_COUNT: usize = 5;
+
+/// Angle between spread shot pellets (about 15 degrees)
+pub const SPREAD_ANGLE: f32 = 0.26;
+pub const SPREAD_PELLETS: usize = 3;
+/// Platform bounces before a ricochet round stops
+pub const RICOCHET_BOUNCES: u32 = 3;
+pub const GRENADE_GRAVITY: f32 = 0.012;
+pub const GRENADE_MAX_FALL: f32 = BULLET_SPEED; // Never outrun the platform thickness check
+pub const GRENADE_BLAST_RADIUS: f32 = 2.2;
+
+pub const CRATE_SIZE: f32 = 0.7;
+// Rounds end on the first kill, so crates show up early (like round-start chests)
+const CRATE_FIRST_DELAY: u32 = 60; // First crate one second into a round
+const CRATE_STAGGER: u32 = 60; // Later spots wait a little longer
+const CRATE_RESPAWN_DELAY: u32 = 60 * 8;
+
+// =============================================================================
+// WEAPONS
+// =============================================================================
+
+/// Held weapon, and the kind of bullet it fires
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Weapon {
+    Pistol,
+    Spread,   // Three pellets in a fan, short range
+    Ricochet, // Bounces off platforms
+    Laser,    // Slow, pierces through players
+    Grenade,  // Arcs under gravity, explodes on contact
+}
+
+impl Weapon {
+    pub const ALL: [Weapon; WEAPON_COUNT] = [
+        Weapon::Pistol,
+        Weapon::Spread,
+        Weapon::Ricochet,
+        Weapon::Laser,
+        Weapon::Grenade,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::Pistol => "PISTOL",
+            Weapon::Spread => "SPREAD",
+            Weapon::Ricochet => "RICOCHET",
+            Weapon::Laser => "LASER",
+            Weapon::Grenade => "GRENADE",
+        }
+    }
+
+    /// Shots a crate grants (the pistol uses regular ammo instead)
+    pub fn shots(self) -> u32 {
+        match self {
+            Weapon::Pistol => 0,
+            Weapon::Spread => 3,
+            Weapon::Ricochet => 4,
+            Weapon::Laser => 2,
+            Weapon::Grenade => 2,
+        }
+    }
+
+    /// Launch speed in units per tick
+    pub fn speed(self) -> f32 {
+        match self {
+            Weapon::Pistol | Weapon::Spread => BULLET_SPEED,
+            Weapon::Ricochet => 0.35,
+            Weapon::Laser => 0.2,
+            Weapon::Grenade => 0.3,
+        }
+    }
+
+    /// Ticks before the bullet fizzles out (grenades explode)
+    pub fn lifetime(self) -> u32 {
+        match self {
+            Weapon::Pistol => BULLET_LIFETIME,
+            Weapon::Spread => 30,
+            Weapon::Ricochet => 240,
+            Weapon::Laser => 150,
+            Weapon::Grenade => 150,
+        }
+    }
+
+    pub fn color(self) -> u32 {
+        match self {
+            Weapon::Pistol => 0xFFFF00FF,
+            Weapon::Spread => 0xFF9020FF,
+            Weapon::Ricochet => 0x40FF80FF,
+            Weapon::Laser => 0xFF40FFFF,
+            Weapon::Grenade => 0xFF3020FF,
+        }
+    }
+
+    pub fn kill_cause(self) -> KillCause {
+        match self {
+            Weapon::Pistol => KillCause::Bullet,
+            Weapon::Spread => KillCause::Spread,
+            Weapon::Ricochet => KillCause::Ricochet,
+            Weapon::Laser => KillCause::Laser,
+            Weapon::Grenade => KillCause::Grenade,
+        }
+    }
+}
+
+// =============================================================================
+// CRATES
+// =============================================================================
+
+/// One crate per stage spot; waits out `timer`, then sits until grabbed
+#[derive(Clone, Copy)]
+pub struct WeaponCrate {
+    pub active: bool,
+    pub weapon: Weapon,
+    pub timer: u32,
+}
+
+impl WeaponCrate {
+    pub const fn new() -> Self {
+        Self {
+            active: false,
+            weapon: Weapon::Pistol,
+            timer: 0,
+        }
+    }
+}
+
+/// Bottom-left corner of a crate spot on the current stage
+pub fn crate_pos(spot: usize) -> (f32, f32) {
+    current_stage_def().crate_spots[spot]
+}
+
+/// Empty every spot and restart the spawn timers (called each round)
+pub fn setup_crates() {
+    unsafe {
+        for (i, c) in WORLD.sim.crates.iter_mut().enumerate() {
+            *c = WeaponCrate::new();
+            c.timer = CRATE_FIRST_DELAY + i as u32 * CRATE_STAGGER;
+        }
+    }
+}
+
+/// Spawn crates when their timers run out and hand weapons to whoever touches one
+pub fn update_crates() {
+    unsafe {
+        let spots = current_stage_def().crate_spot_count.min(MAX_CRATE_SPOTS);
+        for spot in 0..spots {
+            let c = &mut WORLD.sim.crates[spot];
+            if !c.active {
+                if c.timer > 0 {
+                    c.timer -= 1;
+                } else {
+                    // Anything but the pistol
+                    let pick = game_range(1, WEAPON_COUNT as i32).max(1) as usize;
+                    c.weapon = Weapon::ALL[pick];
+                    c.active = true;
+                }
+                continue;
+            }
+
+            let (cx, cy) = crate_pos(spot);
+            for p in &mut WORLD.sim.players {
+                if !p.active || p.dead {
+                    continue;
+                }
+                if aabb_overlap(
+                    (cx, cy, CRATE_SIZE, CRATE_SIZE),
+                    (p.x, p.y, PLAYER_WIDTH, PLAYER_HEIGHT),
+                ) {
+                    p.weapon = c.weapon;
+                    p.weapon_shots = c.weapon.shots();
+                    c.active = false;
+                    c.timer = CRATE_RESPAWN_DELAY;
+                    audio::play_pickup(cx / 10.0);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Center of the closest waiting crate to (x, y), for bots
+pub fn nearest_crate(x: f32, y: f32) -> Option<(f32, f32)> {
+    unsafe {
+        let spots = current_stage_def().crate_spot_count.min(MAX_CRATE_SPOTS);
+        let mut best = None;
+        let mut best_dist_sq = f32::MAX;
+        for spot in 0..spots {
+            if !WORLD.sim.crates[spot].active {
+                continue;
+            }
+            let (cx, cy) = crate_pos(spot);
+            let cx = cx + CRATE_SIZE * 0.5;
+            let cy = cy + CRATE_SIZE * 0.5;
+            let d = (cx - x) * (cx - x) + (cy - y) * (cy - y);
+            if d < best_dist_sq {
+                best_dist_sq = d;
+                best = Some((cx, cy));
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,172 @@
+//! World state
+//!
+//! The whole game lives in one `World` value. `SimState` holds everything that
+//! decides the outcome of a match (and therefore has to be saved, restored and
+//! compared for rollback); `FxState` holds presentation-only state that can be
+//! thrown away or diverge between peers without affecting gameplay.
+
+use crate::combat::{Bullet, MAX_BULLETS};
+use crate::game_state::{
+    EffectLight, GameConfig, GameState, PausePage, TransitionPhase, CAMERA_FOV_DEFAULT,
+    MAX_EFFECT_LIGHTS,
+};
+use crate::particles::{Particle, MAX_PARTICLES};
+use crate::perception::Perception;
+use crate::pickups::{Pickup, MAX_PICKUPS};
+use crate::player::{Player, MAX_PLAYERS};
+use crate::rng::Rng;
+use crate::stage::{Hill, Platform, MAX_CRATE_SPOTS, MAX_PLATFORMS};
+use crate::stats::MatchStats;
+use crate::weapons::WeaponCrate;
+
+// =============================================================================
+// SIMULATION STATE
+// =============================================================================
+
+/// Rollback-relevant state: everything gameplay reads or writes
+#[derive(Clone, Copy)]
+pub struct SimState {
+    // Match flow
+    pub game: GameState,
+    pub config: GameConfig,
+    pub tick: u32,
+    pub round_number: u32,
+    pub hit_freeze: u32, // Game pauses when > 0
+    pub match_end_tick: u32,
+    pub rng: Rng, // Gameplay randomness (seeded per match)
+
+    // Menus
+    pub title_idle_ticks: u32, // Attract/demo mode counter
+    pub lobby_index: u32,
+    pub pause_page: PausePage,
+    pub pause_index: u32,
+
+    // Entities
+    pub players: [Player; MAX_PLAYERS],
+    pub bullets: [Bullet; MAX_BULLETS],
+    pub pickups: [Pickup; MAX_PICKUPS],
+    pub crates: [WeaponCrate; MAX_CRATE_SPOTS],
+
+    // Stage
+    pub platforms: [Platform; MAX_PLATFORMS],
+    pub has_pit: bool,
+    pub pit_y: f32,
+    pub hill: Hill,
+
+    pub stats: MatchStats,
+    pub perception: Perception, // What bots have seen recently
+}
+
+impl SimState {
+    pub const fn new() -> Self {
+        Self {
+            game: GameState::new(),
+            config: GameConfig::new(),
+            tick: 0,
+            round_number: 1,
+            hit_freeze: 0,
+            match_end_tick: 0,
+            rng: Rng::new(0),
+            title_idle_ticks: 0,
+            lobby_index: 0,
+            pause_page: PausePage::Main,
+            pause_index: 0,
+            players: [Player::new(); MAX_PLAYERS],
+            bullets: [Bullet::new(); MAX_BULLETS],
+            pickups: [Pickup::new(); MAX_PICKUPS],
+            crates: [WeaponCrate::new(); MAX_CRATE_SPOTS],
+            platforms: [Platform::new(); MAX_PLATFORMS],
+            has_pit: false,
+            hill: Hill::new(),
+            pit_y: -10.0,
+            stats: MatchStats::new(),
+            perception: Perception::new(),
+        }
+    }
+}
+
+// =============================================================================
+// PRESENTATION STATE
+// =============================================================================
+
+/// Presentation-only state: never read by gameplay
+#[derive(Clone, Copy)]
+pub struct FxState {
+    pub rng: Rng, // Cosmetic randomness (particles, shake)
+    pub particles: [Particle; MAX_PARTICLES],
+    pub effect_lights: [EffectLight; MAX_EFFECT_LIGHTS],
+
+    // Screen shake
+    pub shake: f32, // Current intensity (0.0 - 1.0)
+    pub shake_x: f32,
+    pub shake_y: f32,
+
+    // Hit feedback
+    pub impact_flash: u32, // White overlay when > 0
+    pub camera_fov: f32,
+    pub camera_fov_target: f32,
+    pub deflect_popup_ticks: u32,
+    pub deflect_player: u32,
+    pub deflect_perfect: bool, // Popup reads PERFECT PARRY instead of DEFLECT
+
+    // Stage transition fade
+    pub transition_phase: TransitionPhase,
+    pub transition_progress: f32, // 0.0 to 1.0
+}
+
+impl FxState {
+    pub const fn new() -> Self {
+        Self {
+            rng: Rng::new(0x9E37_79B9),
+            particles: [Particle::new(); MAX_PARTICLES],
+            effect_lights: [EffectLight::new(); MAX_EFFECT_LIGHTS],
+            shake: 0.0,
+            shake_x: 0.0,
+            shake_y: 0.0,
+            impact_flash: 0,
+            camera_fov: CAMERA_FOV_DEFAULT,
+            camera_fov_target: CAMERA_FOV_DEFAULT,
+            deflect_popup_ticks: 0,
+            deflect_player: 0,
+            deflect_perfect: false,
+            transition_phase: TransitionPhase::None,
+            transition_progress: 0.0,
+        }
+    }
+}
+
+// =============================================================================
+// WORLD
+// =============================================================================
+
+#[derive(Clone, Copy)]
+pub struct World {
+    pub sim: SimState,
+    pub fx: FxState,
+}
+
+impl World {
+    pub const fn new() -> Self {
+        Self {
+            sim: SimState::new(),
+            fx: FxState::new(),
+        }
+    }
+
+    /// Copy out the simulation state (e.g. before a speculative tick)
+    pub fn save(&self) -> SimState {
+        self.sim
+    }
+
+    /// Roll the simulation back to a saved state. Presentation state is kept
+    /// so effects already on screen don't pop.
+    pub fn restore(&mut self, saved: &SimState) {
+        self.sim = *saved;
+    }
+}
+
+// =============================================================================
+// GLOBAL STATE
+// =============================================================================
+
+pub static mut WORLD: World = World::new();
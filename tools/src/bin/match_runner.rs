@@ -0,0 +1,521 @@
+//! Headless match runner
+//!
+//! Plays bot-vs-bot matches on the host with no rendering and prints balance
+//! data: win rates per spawn slot (by stage and by difficulty), average match
+//! length, and deaths by cause. With `--teams 1` the win rates are per team;
+//! `--profiles` pits bot play styles against each other by slot.
+//!
+//! `--record DIR` saves every match as a `.ndrp` replay, and `--replay FILE`
+//! plays one back and checks it against the checksum it was saved with.
+//!
+//! ```text
+//! cargo run --release -p neon-duel-tools --bin match_runner -- --matches 3000 --bots 4
+//! cargo run --release -p neon-duel-tools --bin match_runner -- --profiles brawler,sniper
+//! cargo run --release -p neon-duel-tools --bin match_runner -- --matches 5 --record out
+//! cargo run --release -p neon-duel-tools --bin match_runner -- --replay out/match-3.ndrp
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use neon_duel::bot_profile::{bot_profile_name, NUM_BOT_PROFILES};
+use neon_duel::checksum;
+use neon_duel::game_state::{team_of, GamePhase, MatchRule, NUM_STAGES, NUM_TEAMS, TEAM_NAMES};
+use neon_duel::platform;
+use neon_duel::player::MAX_PLAYERS;
+use neon_duel::replay::{self, ReplayMode};
+use neon_duel::stage::stage_name;
+use neon_duel::stats::{self, KillCause, KILL_CAUSE_COUNT};
+use neon_duel::world::{World, WORLD};
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+const TICKS_PER_SECOND: f64 = 60.0;
+const MAX_MATCH_TICKS: u32 = 60 * 60 * 15; // Give up on a match after 15 minutes
+const NUM_DIFFICULTIES: usize = 3;
+const DIFFICULTY_NAMES: [&str; NUM_DIFFICULTIES] = ["EASY", "NORMAL", "HARD"];
+
+const USAGE: &str = "usage: match_runner [options]
+  --matches N      matches to play (default 1000)
+  --bots N         bots per match, 2-4 (default 4)
+  --seed N         base RNG seed (default 1)
+  --stage N        only play stage N (default: cycle all stages)
+  --difficulty N   only use difficulty N, 0-2 (default: cycle all)
+  --kills N        kills to win (default 5)
+  --stocks N       stock mode with N lives, 0 = play to --kills (default 0)
+  --hill N         king of the hill, first to hold N seconds (default 0 = off)
+  --time N         round time in seconds, 0 = infinite (default 45)
+  --teams 0|1      2v2 team mode, slots alternate RED/BLUE (default 0)
+  --ff 0|1         friendly fire in team mode (default 0)
+  --profiles A,B.. bot profile per slot, by name (default: all BALANCED)
+  --record DIR     save each match as DIR/match-N.ndrp
+  --replay FILE    play a saved match and check it for desyncs (other options
+                   are ignored)";
+
+// =============================================================================
+// OPTIONS
+// =============================================================================
+
+struct Options {
+    matches: u32,
+    bots: usize,
+    seed: u32,
+    stage: Option<u32>,
+    difficulty: Option<u32>,
+    kills: u32,
+    stocks: u32,
+    hill: u32,
+    time: u32,
+    teams: bool,
+    friendly_fire: bool,
+    profiles: [u32; MAX_PLAYERS],
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+}
+
+impl Options {
+    fn rule(&self) -> MatchRule {
+        if self.stocks > 0 {
+            MatchRule::Stock
+        } else if self.hill > 0 {
+            MatchRule::Hill
+        } else {
+            MatchRule::Kills
+        }
+    }
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut opts = Options {
+        matches: 1000,
+        bots: 4,
+        seed: 1,
+        stage: None,
+        difficulty: None,
+        kills: 5,
+        stocks: 0,
+        hill: 0,
+        time: 45,
+        teams: false,
+        friendly_fire: false,
+        profiles: [0; MAX_PLAYERS],
+        record: None,
+        replay: None,
+    };
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut i = 0;
+    while i < args.len() {
+        let flag = args[i].as_str();
+        if flag == "--help" || flag == "-h" {
+            println!("{USAGE}");
+            exit(0);
+        }
+        let value = args
+            .get(i + 1)
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        if flag == "--profiles" {
+            opts.profiles = parse_profiles(value)?;
+            i += 2;
+            continue;
+        }
+        if flag == "--record" || flag == "--replay" {
+            let path = Some(PathBuf::from(value));
+            if flag == "--record" {
+                opts.record = path;
+            } else {
+                opts.replay = path;
+            }
+            i += 2;
+            continue;
+        }
+        let n: u32 = value
+            .parse()
+            .map_err(|_| format!("invalid number for {flag}: {value}"))?;
+        match flag {
+            "--matches" => opts.matches = n,
+            "--bots" => opts.bots = n as usize,
+            "--seed" => opts.seed = n,
+            "--stage" => opts.stage = Some(n),
+            "--difficulty" => opts.difficulty = Some(n),
+            "--kills" => opts.kills = n,
+            "--stocks" => opts.stocks = n,
+            "--hill" => opts.hill = n,
+            "--time" => opts.time = n,
+            "--teams" => opts.teams = n != 0,
+            "--ff" => opts.friendly_fire = n != 0,
+            _ => return Err(format!("unknown option {flag}")),
+        }
+        i += 2;
+    }
+
+    if !(2..=MAX_PLAYERS).contains(&opts.bots) {
+        return Err(format!("--bots must be 2-{MAX_PLAYERS}"));
+    }
+    if opts.stocks > 0 && opts.hill > 0 {
+        return Err("--stocks and --hill can't be combined".to_string());
+    }
+    if opts.teams && opts.bots < 2 * NUM_TEAMS as usize {
+        return Err(format!("--teams needs at least {} bots", 2 * NUM_TEAMS));
+    }
+    if opts.stage.is_some_and(|s| s >= NUM_STAGES) {
+        return Err(format!("--stage must be 0-{}", NUM_STAGES - 1));
+    }
+    if opts
+        .difficulty
+        .is_some_and(|d| d as usize >= NUM_DIFFICULTIES)
+    {
+        return Err(format!("--difficulty must be 0-{}", NUM_DIFFICULTIES - 1));
+    }
+    Ok(opts)
+}
+
+/// Comma-separated profile names for slots P1, P2, ...; missing slots stay
+/// on the first profile
+fn parse_profiles(list: &str) -> Result<[u32; MAX_PLAYERS], String> {
+    let mut profiles = [0; MAX_PLAYERS];
+    let names: Vec<&str> = list.split(',').collect();
+    if names.len() > MAX_PLAYERS {
+        return Err(format!("--profiles takes at most {MAX_PLAYERS} names"));
+    }
+    for (slot, name) in profiles.iter_mut().zip(names) {
+        *slot = (0..NUM_BOT_PROFILES)
+            .find(|&p| bot_profile_name(p).eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| {
+                let known: Vec<&str> = (0..NUM_BOT_PROFILES).map(bot_profile_name).collect();
+                format!("unknown bot profile {name} ({})", known.join(", "))
+            })?;
+    }
+    Ok(profiles)
+}
+
+// =============================================================================
+// SIMULATION
+// =============================================================================
+
+struct MatchResult {
+    winner: Option<usize>, // Slot, or team in team mode; None on timeout
+    ticks: u32,
+    deaths: [u32; KILL_CAUSE_COUNT],
+    clashes: u32,
+    deflects: u32,
+    perfect_parries: u32,
+}
+
+fn run_match(opts: &Options, stage: u32, difficulty: u32, seed: u32) -> MatchResult {
+    unsafe {
+        WORLD = World::new();
+        platform::host().reset(0, seed);
+
+        WORLD.sim.config.stage_select = stage;
+        WORLD.sim.config.bot_difficulty = difficulty;
+        WORLD.sim.config.kills_to_win = opts.kills;
+        WORLD.sim.config.rule = opts.rule();
+        WORLD.sim.config.stocks = opts.stocks;
+        WORLD.sim.config.hill_seconds = opts.hill;
+        WORLD.sim.config.round_time_seconds = opts.time;
+        WORLD.sim.config.team_mode = opts.teams;
+        WORLD.sim.config.friendly_fire = opts.friendly_fire;
+        WORLD.sim.config.bot_profiles = opts.profiles;
+        neon_duel::start_bot_match(opts.bots);
+
+        // The winning kill moves straight to FinalKo; the rest is presentation
+        while !matches!(
+            WORLD.sim.game.phase,
+            GamePhase::FinalKo | GamePhase::MatchEnd
+        ) {
+            if stats::match_ticks() >= MAX_MATCH_TICKS {
+                return MatchResult {
+                    winner: None,
+                    ticks: stats::match_ticks(),
+                    deaths: WORLD.sim.stats.deaths_by_cause,
+                    clashes: WORLD.sim.stats.clashes,
+                    deflects: WORLD.sim.stats.deflects,
+                    perfect_parries: WORLD.sim.stats.perfect_parries,
+                };
+            }
+            neon_duel::update();
+        }
+
+        let winner_idx = WORLD.sim.game.winner_idx as usize;
+        MatchResult {
+            winner: Some(if opts.teams {
+                team_of(winner_idx) as usize
+            } else {
+                winner_idx
+            }),
+            ticks: stats::match_ticks(),
+            deaths: WORLD.sim.stats.deaths_by_cause,
+            clashes: WORLD.sim.stats.clashes,
+            deflects: WORLD.sim.stats.deflects,
+            perfect_parries: WORLD.sim.stats.perfect_parries,
+        }
+    }
+}
+
+// =============================================================================
+// REPLAYS
+// =============================================================================
+
+/// Play the current match on to the end of its recording and save it
+fn save_replay(path: &Path) -> Result<(), String> {
+    while replay::mode() == ReplayMode::Recording {
+        neon_duel::update();
+    }
+    let data = replay::recorded().ok_or_else(|| match replay::last_error() {
+        Some(e) => format!("recording failed: {e:?}"),
+        None => "recording didn't finish".to_string(),
+    })?;
+    fs::write(path, data).map_err(|e| format!("can't write {}: {e}", path.display()))
+}
+
+/// Play a saved match through to its footer and check it for desyncs
+fn check_replay(path: &Path) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let header = replay::read_header(&data).map_err(|e| format!("bad replay: {e:?}"))?;
+    let players = header.participants.iter().filter(|p| p.active).count();
+    println!(
+        "{}: {}, {} players, {} bytes",
+        path.display(),
+        stage_name(header.config.stage_select),
+        players,
+        data.len()
+    );
+
+    unsafe {
+        WORLD = World::new();
+        neon_duel::play_replay(&data).map_err(|e| format!("bad replay: {e:?}"))?;
+        while replay::mode() == ReplayMode::Playing {
+            neon_duel::update();
+        }
+        println!("Played {} ticks", WORLD.sim.tick - header.start_tick);
+    }
+
+    if let Some(e) = replay::last_error() {
+        return Err(format!("replay ended early: {e:?}"));
+    }
+    match checksum::first_desync() {
+        Some(d) => Err(format!(
+            "desync at tick {} in {} (local {:08x}, recorded {:08x})",
+            d.tick,
+            d.subsystem.name(),
+            d.local,
+            d.remote
+        )),
+        None => {
+            println!(
+                "Final checksum {:08x} matches the recording",
+                checksum::current().combined()
+            );
+            Ok(())
+        }
+    }
+}
+
+// =============================================================================
+// REPORT
+// =============================================================================
+
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    matches: u32,
+    wins: [u32; MAX_PLAYERS],
+    ticks: u64,
+}
+
+impl Bucket {
+    fn add(&mut self, result: &MatchResult) {
+        self.matches += 1;
+        self.ticks += result.ticks as u64;
+        if let Some(w) = result.winner {
+            self.wins[w] += 1;
+        }
+    }
+
+    fn avg_seconds(&self) -> f64 {
+        if self.matches == 0 {
+            return 0.0;
+        }
+        self.ticks as f64 / self.matches as f64 / TICKS_PER_SECOND
+    }
+
+    fn row(&self, label: &str, columns: usize) {
+        print!("  {label:<14}");
+        for w in &self.wins[..columns] {
+            let pct = if self.matches == 0 {
+                0.0
+            } else {
+                *w as f64 * 100.0 / self.matches as f64
+            };
+            print!(" {pct:>7.1}%");
+        }
+        println!(" {:>8} {:>8.1}s", self.matches, self.avg_seconds());
+    }
+}
+
+fn table_header(title: &str, opts: &Options) {
+    println!();
+    println!("{title}");
+    print!("  {:<14}", "");
+    if opts.teams {
+        for name in TEAM_NAMES {
+            print!(" {name:>8}");
+        }
+    } else {
+        for slot in 1..=opts.bots {
+            print!(" {:>8}", format!("P{slot}"));
+        }
+    }
+    println!(" {:>8} {:>9}", "matches", "avg len");
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+fn main() {
+    let opts = match parse_options() {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("error: {e}\n{USAGE}");
+            exit(2);
+        }
+    };
+
+    neon_duel::init();
+
+    if let Some(path) = &opts.replay {
+        if let Err(e) = check_replay(path) {
+            eprintln!("error: {e}");
+            exit(1);
+        }
+        return;
+    }
+    if let Some(dir) = &opts.record {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("error: can't create {}: {e}", dir.display());
+            exit(1);
+        }
+    }
+
+    let mut by_stage = [Bucket::default(); NUM_STAGES as usize];
+    let mut by_difficulty = [Bucket::default(); NUM_DIFFICULTIES];
+    let mut total = Bucket::default();
+    let mut deaths = [0u64; KILL_CAUSE_COUNT];
+    let mut clashes = 0u64;
+    let mut deflects = 0u64;
+    let mut perfect_parries = 0u64;
+    let mut timeouts = 0;
+
+    for m in 0..opts.matches {
+        // Spread matches evenly over the stage x difficulty grid
+        let stage = opts.stage.unwrap_or(m % NUM_STAGES);
+        let difficulty = opts
+            .difficulty
+            .unwrap_or((m / NUM_STAGES) % NUM_DIFFICULTIES as u32);
+        let seed = opts.seed.wrapping_mul(0x9E37_79B9).wrapping_add(m + 1);
+
+        let result = run_match(&opts, stage, difficulty, seed);
+        if result.winner.is_none() {
+            timeouts += 1;
+        } else if let Some(dir) = &opts.record {
+            let path = dir.join(format!("match-{}.ndrp", m + 1));
+            if let Err(e) = save_replay(&path) {
+                eprintln!("warning: match {} not saved: {e}", m + 1);
+            }
+        }
+        by_stage[stage as usize].add(&result);
+        by_difficulty[difficulty as usize].add(&result);
+        total.add(&result);
+        for (sum, n) in deaths.iter_mut().zip(result.deaths) {
+            *sum += n as u64;
+        }
+        clashes += result.clashes as u64;
+        deflects += result.deflects as u64;
+        perfect_parries += result.perfect_parries as u64;
+    }
+
+    println!(
+        "NEON DUEL match runner: {} matches, {} bots{}, {}, seed {}",
+        opts.matches,
+        opts.bots,
+        match (opts.teams, opts.friendly_fire) {
+            (false, _) => "",
+            (true, false) => " in teams",
+            (true, true) => " in teams (friendly fire)",
+        },
+        match opts.rule() {
+            MatchRule::Kills => format!("first to {} kills", opts.kills),
+            MatchRule::Stock => format!("{} stocks", opts.stocks),
+            MatchRule::Hill => format!("first to hold the hill {}s", opts.hill),
+        },
+        opts.seed
+    );
+    let lineup: Vec<String> = opts.profiles[..opts.bots]
+        .iter()
+        .enumerate()
+        .map(|(slot, &p)| format!("P{} {}", slot + 1, bot_profile_name(p)))
+        .collect();
+    println!("Bot profiles: {}", lineup.join(", "));
+    println!(
+        "Average match length: {:.1}s ({} timed out after {}s)",
+        total.avg_seconds(),
+        timeouts,
+        MAX_MATCH_TICKS / 60
+    );
+
+    let (by, columns) = if opts.teams {
+        ("team", NUM_TEAMS as usize)
+    } else {
+        ("spawn slot", opts.bots)
+    };
+
+    table_header(&format!("Win rate by {by}, per stage"), &opts);
+    for (stage, bucket) in by_stage.iter().enumerate() {
+        if bucket.matches > 0 {
+            bucket.row(stage_name(stage as u32), columns);
+        }
+    }
+    total.row("ALL", columns);
+
+    table_header(&format!("Win rate by {by}, per difficulty"), &opts);
+    for (difficulty, bucket) in by_difficulty.iter().enumerate() {
+        if bucket.matches > 0 {
+            bucket.row(DIFFICULTY_NAMES[difficulty], columns);
+        }
+    }
+
+    println!();
+    println!("Deaths by cause");
+    let total_deaths: u64 = deaths.iter().sum();
+    for cause in KillCause::ALL {
+        let n = deaths[cause as usize];
+        let pct = if total_deaths == 0 {
+            0.0
+        } else {
+            n as f64 * 100.0 / total_deaths as f64
+        };
+        println!("  {:<14} {:>8} {:>7.1}%", cause.name(), n, pct);
+    }
+    println!(
+        "  {:<14} {:>8} ({:.1} per match)",
+        "total",
+        total_deaths,
+        total_deaths as f64 / opts.matches.max(1) as f64
+    );
+    println!();
+    println!(
+        "Bullet clashes: {} ({:.1} per match)",
+        clashes,
+        clashes as f64 / opts.matches.max(1) as f64
+    );
+    println!(
+        "Deflects: {} ({:.1} per match), {} perfect parries",
+        deflects,
+        deflects as f64 / opts.matches.max(1) as f64,
+        perfect_parries
+    );
+}
@@ -0,0 +1,472 @@
+//! Stage checker
+//!
+//! Validates stage files before they reach a playtest:
+//! - every spawn point and weapon crate spot stands on a platform (not over
+//!   the pit)
+//! - no platform is thinner than a bullet travels in one tick
+//! - every platform is reachable from every spawn, using the real jump,
+//!   gravity, wall-jump, drop-through and solid-platform rules
+//!
+//! With no arguments the built-in stages are checked; otherwise each argument
+//! is a `.stage` file. Exits 1 if any check fails, 2 on usage or I/O errors.
+//!
+//! ```text
+//! cargo run -p neon-duel-tools --bin stage_check -- assets/stages/*.stage
+//! ```
+
+use std::process::exit;
+
+use neon_duel::combat::BULLET_SPEED;
+use neon_duel::player::{
+    AIR_FRICTION, DEATH_Y, DROP_THROUGH_FRAMES, FRICTION, GRAVITY, JUMP_FORCE, MAX_PLAYERS,
+    MOVE_SPEED, PLAYER_HEIGHT, PLAYER_WIDTH, SIDE_EPSILON,
+};
+use neon_duel::stage::{parse_stage, Collision, Platform, StageDef, STAGES};
+use neon_duel::weapons::CRATE_SIZE;
+
+// =============================================================================
+// CONSTANTS
+// =============================================================================
+
+/// Longest airborne stretch simulated per jump (5 seconds)
+const MAX_FLIGHT_TICKS: u32 = 300;
+
+/// Horizontal spacing of take-off positions along a platform
+const TAKEOFF_STEP: f32 = 0.1;
+
+/// Wall jumps chained in one flight, and how often one is tried while touching
+const MAX_WALL_JUMPS: u32 = 2;
+const WALL_JUMP_INTERVAL: u32 = 4;
+
+/// Moving platforms are checked at this many points along their path
+const MOVER_SNAPSHOTS: usize = 5;
+
+/// Landing tolerance used by the player collision code
+const LANDING_SNAP: f32 = 0.2;
+
+/// Running speed on the ground: `(v + MOVE_SPEED * 0.15) * FRICTION` settles here
+const GROUND_RUN_SPEED: f32 = MOVE_SPEED * 0.15 * FRICTION / (1.0 - FRICTION);
+
+const USAGE: &str = "usage: stage_check [file.stage ...]
+  with no files, checks the stages built into the game";
+
+// =============================================================================
+// REPORT
+// =============================================================================
+
+#[derive(Default)]
+struct Report {
+    failures: Vec<String>,
+}
+
+impl Report {
+    fn fail(&mut self, msg: String) {
+        self.failures.push(msg);
+    }
+}
+
+fn describe(stage: &StageDef, idx: usize) -> String {
+    let p = &stage.platforms[idx];
+    format!("platform {idx} (x {:.1}, y {:.1})", p.x, p.y)
+}
+
+// =============================================================================
+// SPAWNS AND THICKNESS
+// =============================================================================
+
+/// Horizontal span a platform covers at every point of its path
+fn always_covered(p: &Platform) -> (f32, f32) {
+    if p.moving {
+        (p.move_max, p.move_min + p.width)
+    } else {
+        (p.x, p.x + p.width)
+    }
+}
+
+/// Top of the highest platform under `cx` at or below `y` that stays under it
+fn support_top(stage: &StageDef, cx: f32, y: f32) -> Option<f32> {
+    stage
+        .platforms()
+        .iter()
+        .filter(|p| {
+            let (left, right) = always_covered(p);
+            p.has_floor() && cx >= left && cx <= right && p.y + p.height <= y + 0.001
+        })
+        .map(|p| p.y + p.height)
+        .fold(None, |best: Option<f32>, top| {
+            Some(best.map_or(top, |b| b.max(top)))
+        })
+}
+
+fn check_spawns(stage: &StageDef, report: &mut Report) {
+    for (i, &(sx, sy)) in stage.spawns().iter().enumerate().take(MAX_PLAYERS) {
+        let cx = sx + PLAYER_WIDTH * 0.5;
+        if sx < stage.arena_left || sx + PLAYER_WIDTH > stage.arena_right {
+            report.fail(format!("spawn {i} ({sx:.1}, {sy:.1}) is outside the arena"));
+            continue;
+        }
+
+        // Highest platform under the player's center that it would land on
+        match support_top(stage, cx, sy) {
+            None => report.fail(format!(
+                "spawn {i} ({sx:.1}, {sy:.1}) is not above a platform"
+            )),
+            Some(top) if sy - top > PLAYER_HEIGHT => report.fail(format!(
+                "spawn {i} ({sx:.1}, {sy:.1}) drops {:.1} units before landing",
+                sy - top
+            )),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Crates don't fall, so they have to sit right on a platform
+fn check_crates(stage: &StageDef, report: &mut Report) {
+    for (i, &(x, y)) in stage.crate_spots().iter().enumerate() {
+        if x < stage.arena_left || x + CRATE_SIZE > stage.arena_right {
+            report.fail(format!("crate {i} ({x:.1}, {y:.1}) is outside the arena"));
+            continue;
+        }
+        match support_top(stage, x + CRATE_SIZE * 0.5, y) {
+            Some(top) if y - top < 0.05 => {}
+            _ => report.fail(format!(
+                "crate {i} ({x:.1}, {y:.1}) is not resting on a platform"
+            )),
+        }
+    }
+}
+
+fn check_thickness(stage: &StageDef, report: &mut Report) {
+    for (i, p) in stage.platforms().iter().enumerate() {
+        let thinnest = p.width.min(p.height);
+        if thinnest < BULLET_SPEED {
+            report.fail(format!(
+                "{} is {thinnest:.2} thick; bullets move {BULLET_SPEED:.2} per tick and can skip it",
+                describe(stage, i)
+            ));
+        }
+    }
+}
+
+// =============================================================================
+// REACHABILITY
+// =============================================================================
+
+/// Player state during a simulated flight (mirrors `update_player`)
+#[derive(Clone, Copy)]
+struct Flight {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    input: f32,
+    drop_timer: u32,
+    wall_jumps: u32,
+    ticks: u32,
+}
+
+/// Platform layout with every mover frozen at the same point of its path
+fn snapshot(stage: &StageDef, k: usize) -> Vec<Platform> {
+    let t = k as f32 / (MOVER_SNAPSHOTS - 1) as f32;
+    stage
+        .platforms()
+        .iter()
+        .map(|p| {
+            let mut p = *p;
+            if p.moving {
+                p.x = p.move_min + (p.move_max - p.move_min) * t;
+            }
+            p
+        })
+        .collect()
+}
+
+fn overlaps(x: f32, y: f32, p: &Platform) -> bool {
+    x < p.x + p.width && x + PLAYER_WIDTH > p.x && y < p.y + p.height && y + PLAYER_HEIGHT > p.y
+}
+
+/// Same probe as the wall-jump check: a point just outside the player's side
+fn touching_side(f: &Flight, platforms: &[Platform]) -> Option<f32> {
+    for p in platforms.iter().filter(|p| p.has_sides()) {
+        let in_band = f.y + PLAYER_HEIGHT > p.y && f.y < p.y + p.height;
+        if !in_band {
+            continue;
+        }
+        let left = f.x - 0.1;
+        let right = f.x + PLAYER_WIDTH + 0.1;
+        if left >= p.x && left <= p.x + p.width {
+            return Some(1.0); // Wall on the left pushes right
+        }
+        if right >= p.x && right <= p.x + p.width {
+            return Some(-1.0);
+        }
+    }
+    None
+}
+
+/// Advance one tick; returns true once the flight is over (landed or fell out)
+fn step(f: &mut Flight, stage: &StageDef, platforms: &[Platform], landed: &mut [bool]) -> bool {
+    f.ticks += 1;
+    f.vx = ((f.vx + f.input * MOVE_SPEED * 0.08) * AIR_FRICTION).clamp(-MOVE_SPEED, MOVE_SPEED);
+    f.vy -= GRAVITY;
+
+    let mut nx = f.x + f.vx;
+    let mut ny = f.y + f.vy;
+
+    // Blocking sides first, at the old height
+    for p in platforms.iter().filter(|p| p.has_sides()) {
+        if overlaps(nx, f.y, p) {
+            let from_left = f.x + PLAYER_WIDTH <= p.x + SIDE_EPSILON;
+            let from_right = f.x >= p.x + p.width - SIDE_EPSILON;
+            let nearer_left = f.x + PLAYER_WIDTH * 0.5 < p.x + p.width * 0.5;
+            nx = if from_left || (!from_right && nearer_left) {
+                p.x - PLAYER_WIDTH
+            } else {
+                p.x + p.width
+            };
+            f.vx = 0.0;
+        }
+    }
+
+    // Then floors and ceilings
+    for (i, p) in platforms.iter().enumerate() {
+        if !overlaps(nx, ny, p) {
+            continue;
+        }
+        let can_land = p.has_floor() && (f.drop_timer == 0 || p.collision == Collision::Solid);
+        if can_land && f.vy <= 0.0 && f.y >= p.y + p.height - LANDING_SNAP {
+            landed[i] = true;
+            return true;
+        }
+        if p.has_ceiling() && f.vy > 0.0 && f.y + PLAYER_HEIGHT <= p.y + LANDING_SNAP {
+            ny = p.y - PLAYER_HEIGHT;
+            f.vy = 0.0;
+        }
+    }
+
+    f.x = nx.clamp(stage.arena_left, stage.arena_right - PLAYER_WIDTH);
+    f.y = ny;
+    f.drop_timer = f.drop_timer.saturating_sub(1);
+    f.y < DEATH_Y || f.ticks >= MAX_FLIGHT_TICKS
+}
+
+/// Fly until landing or falling out, marking every platform landed on
+fn fly(mut f: Flight, stage: &StageDef, platforms: &[Platform], landed: &mut [bool]) {
+    loop {
+        // Branch: wall jump now (steering away or back) vs. carry on
+        if f.drop_timer == 0
+            && f.wall_jumps < MAX_WALL_JUMPS
+            && f.ticks % WALL_JUMP_INTERVAL == WALL_JUMP_INTERVAL - 1
+        {
+            if let Some(dir) = touching_side(&f, platforms) {
+                for input in [dir, -dir] {
+                    let mut jump = f;
+                    jump.vy = JUMP_FORCE * 0.9;
+                    jump.vx = MOVE_SPEED * 0.8 * dir;
+                    jump.input = input;
+                    jump.wall_jumps += 1;
+                    if !step(&mut jump, stage, platforms, landed) {
+                        fly(jump, stage, platforms, landed);
+                    }
+                }
+            }
+        }
+
+        if step(&mut f, stage, platforms, landed) {
+            return;
+        }
+    }
+}
+
+/// Platforms a player standing on `from` can land on in one move
+fn reachable_from(stage: &StageDef, from: usize) -> Vec<bool> {
+    let mut landed = vec![false; stage.platform_count];
+
+    if !stage.platforms[from].has_floor() {
+        return landed; // Walls can't be stood on
+    }
+
+    for k in 0..MOVER_SNAPSHOTS {
+        let platforms = snapshot(stage, k);
+        let src = &platforms[from];
+
+        let mut x = src.x - PLAYER_WIDTH + TAKEOFF_STEP;
+        while x < src.x + src.width {
+            let y = src.y + src.height;
+            for input in [-1.0, 0.0, 1.0] {
+                let start = Flight {
+                    x,
+                    y,
+                    vx: GROUND_RUN_SPEED * input,
+                    vy: 0.0,
+                    input,
+                    drop_timer: 0,
+                    wall_jumps: 0,
+                    ticks: 0,
+                };
+
+                // Full jump
+                fly(
+                    Flight {
+                        vy: JUMP_FORCE,
+                        ..start
+                    },
+                    stage,
+                    &platforms,
+                    &mut landed,
+                );
+
+                // Drop through (down + jump), only from one-way floors
+                if src.collision == Collision::Solid {
+                    continue;
+                }
+                fly(
+                    Flight {
+                        vy: -0.05,
+                        drop_timer: DROP_THROUGH_FRAMES,
+                        ..start
+                    },
+                    stage,
+                    &platforms,
+                    &mut landed,
+                );
+            }
+            x += TAKEOFF_STEP;
+        }
+
+        // Running off either edge
+        for (x, input) in [
+            (src.x - PLAYER_WIDTH - 0.01, -1.0),
+            (src.x + src.width + 0.01, 1.0),
+        ] {
+            let start = Flight {
+                x,
+                y: src.y + src.height,
+                vx: GROUND_RUN_SPEED * input,
+                vy: 0.0,
+                input,
+                drop_timer: 0,
+                wall_jumps: 0,
+                ticks: 0,
+            };
+            fly(start, stage, &platforms, &mut landed);
+        }
+    }
+    landed
+}
+
+/// Platform a spawn point lands on (highest one under the player)
+fn spawn_platform(stage: &StageDef, (sx, sy): (f32, f32)) -> Option<usize> {
+    stage
+        .platforms()
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| {
+            p.has_floor()
+                && sx < p.x + p.width
+                && sx + PLAYER_WIDTH > p.x
+                && p.y + p.height <= sy + 0.001
+        })
+        .max_by(|a, b| (a.1.y + a.1.height).total_cmp(&(b.1.y + b.1.height)))
+        .map(|(i, _)| i)
+}
+
+fn check_reachability(stage: &StageDef, report: &mut Report) {
+    let edges: Vec<Vec<bool>> = (0..stage.platform_count)
+        .map(|i| reachable_from(stage, i))
+        .collect();
+
+    for (s, &spawn) in stage.spawns().iter().enumerate().take(MAX_PLAYERS) {
+        let Some(start) = spawn_platform(stage, spawn) else {
+            continue; // Already reported by the spawn check
+        };
+
+        let mut seen = vec![false; stage.platform_count];
+        let mut queue = vec![start];
+        seen[start] = true;
+        while let Some(i) = queue.pop() {
+            for (j, &edge) in edges[i].iter().enumerate() {
+                if edge && !seen[j] {
+                    seen[j] = true;
+                    queue.push(j);
+                }
+            }
+        }
+
+        // Walls have nothing to stand on, so they never count as unreachable
+        let missed = seen
+            .iter()
+            .enumerate()
+            .filter(|&(i, &ok)| !ok && stage.platforms[i].has_floor());
+        for (i, _) in missed {
+            report.fail(format!(
+                "{} cannot be reached from spawn {s}",
+                describe(stage, i)
+            ));
+        }
+    }
+}
+
+// =============================================================================
+// MAIN
+// =============================================================================
+
+fn check_stage(label: &str, stage: &StageDef) -> bool {
+    let mut report = Report::default();
+    check_spawns(stage, &mut report);
+    check_crates(stage, &mut report);
+    check_thickness(stage, &mut report);
+    check_reachability(stage, &mut report);
+
+    if report.failures.is_empty() {
+        println!(
+            "ok    {label}: {} ({} platforms, {} spawns, {} crates)",
+            stage.name, stage.platform_count, stage.spawn_count, stage.crate_spot_count
+        );
+        true
+    } else {
+        println!("FAIL  {label}: {}", stage.name);
+        for msg in &report.failures {
+            println!("        {msg}");
+        }
+        false
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{USAGE}");
+        exit(0);
+    }
+
+    let mut all_ok = true;
+    if args.is_empty() {
+        for (i, stage) in STAGES.iter().enumerate() {
+            all_ok &= check_stage(&format!("built-in stage {i}"), stage);
+        }
+    } else {
+        for path in &args {
+            let src = match std::fs::read_to_string(path) {
+                Ok(src) => src,
+                Err(e) => {
+                    eprintln!("error: {path}: {e}");
+                    exit(2);
+                }
+            };
+            // The parser hands out &'static strs (the name) into its input
+            let src: &'static str = Box::leak(src.into_boxed_str());
+            match parse_stage(src) {
+                Ok(stage) => all_ok &= check_stage(path, &stage),
+                Err(e) => {
+                    println!("FAIL  {path}:{}: {}", e.line, e.kind.message());
+                    all_ok = false;
+                }
+            }
+        }
+    }
+
+    if !all_ok {
+        exit(1);
+    }
+}